@@ -0,0 +1,83 @@
+// Measures how fast rot13, AES-256-GCM, and ChaCha20-Poly1305 are at sizes from a few bytes to 100 MiB, to set
+// a baseline and catch anything that makes them slower, and how long each key derivation function takes on its
+// own. Run with "make bench", or
+// "cargo bench --bench cipher_bench". See the README for comparing a change against a saved baseline.
+//
+// rot13 is little more than a table lookup per byte, so at 1 MiB it should manage well over 500 MiB/s.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_project::kdf::{Argon2idKdf, KdfAlgorithm, Pbkdf2Kdf, ScryptKdf};
+use rust_project::{rot13, Aes256Gcm, ChaCha20Poly1305, Cipher};
+
+// The sizes the ciphers are timed at, with a name for each.
+const SIZES: [(&str, usize); 4] = [
+    ("64 B", 64),
+    ("1 KiB", 1024),
+    ("1 MiB", 1024 * 1024),
+    ("100 MiB", 100 * 1024 * 1024),
+];
+
+// This function makes ASCII text of the given size, mostly letters with some spaces and punctuation, like
+// the text rot13 is usually used on.
+fn text(size: usize) -> String {
+    "Why did the chicken cross the road? To get to the other side! "
+        .chars()
+        .cycle()
+        .take(size)
+        .collect()
+}
+
+fn rot13_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rot13");
+    for (name, size) in SIZES {
+        let text = text(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        // Each 100 MiB run takes a while, so fewer of them are timed.
+        group.sample_size(if size >= 100 * 1024 * 1024 { 10 } else { 100 });
+        group.bench_with_input(BenchmarkId::from_parameter(name), &text, |b, text| {
+            b.iter(|| rot13(black_box(text)))
+        });
+    }
+    group.finish();
+}
+
+// The authenticated ciphers are timed with a fixed key and nonce, so only encrypting is measured; deriving the
+// key is timed on its own below. Reusing a nonce like this is only safe because nothing is kept.
+fn aead_sizes(c: &mut Criterion) {
+    let ciphers: [&dyn Cipher; 2] = [&Aes256Gcm, &ChaCha20Poly1305];
+    let (key, nonce) = ([7; 32], [9; 12]);
+    let mut group = c.benchmark_group("aead");
+    for (name, size) in SIZES {
+        let data = text(size).into_bytes();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.sample_size(if size >= 100 * 1024 * 1024 { 10 } else { 100 });
+        for cipher in ciphers {
+            group.bench_with_input(BenchmarkId::new(cipher.name(), name), &data, |b, data| {
+                b.iter(|| cipher.encrypt(&key, &nonce, black_box(data), b""))
+            });
+        }
+    }
+    group.finish();
+}
+
+// Key derivation is timed separately from any cipher, since it's done once per file however big it is. The
+// settings are the cheapest recommended ones for each function.
+fn key_derivation(c: &mut Criterion) {
+    let kdfs: Vec<Box<dyn KdfAlgorithm>> = vec![
+        Box::new(Argon2idKdf { memory_kib: 19 * 1024, iterations: 2, parallelism: 1 }),
+        Box::new(Pbkdf2Kdf { iterations: 600_000 }),
+        Box::new(ScryptKdf { log_n: 14, r: 8, p: 1 }),
+    ];
+    let mut group = c.benchmark_group("kdf");
+    group.sample_size(10);
+    for kdf in &kdfs {
+        group.bench_function(kdf.name(), |b| {
+            let mut key = [0; 32];
+            b.iter(|| kdf.derive(black_box(b"correct horse battery staple"), black_box(b"NaCl salt 16 ch"), &mut key))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, rot13_sizes, aead_sizes, key_derivation);
+criterion_main!(benches);
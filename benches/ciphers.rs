@@ -0,0 +1,54 @@
+// Measures how fast each cipher in the library gets through 1 MiB of text, to catch anything that makes them
+// slower. Run with "cargo bench". The text is the same on every run, so results can be compared.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_project::{
+    named_alphabet, parse_alphabet, Affine, Base32, Caesar, Cipher, Columnar, Direction, RailFence, Rot13, Rot13Rot5,
+    Rot13Unicode, Substitution, Zeroizing,
+};
+
+// How much text each cipher is timed on.
+const SIZE: usize = 1024 * 1024;
+
+// This function makes 1 MiB of mixed text: upper and lower case letters, digits, punctuation, line breaks, and
+// some characters that take several bytes in UTF-8, repeated until it's big enough.
+fn mixed_text() -> Vec<u8> {
+    let sample = "The Quick Brown Fox Jumps Over The Lazy Dog, 1234567890 times!\n\
+                  Grüße aus Zürich; naïve café owners say \"¡hola!\" (€5 a cup).\n";
+    let mut text = sample.repeat(SIZE / sample.len() + 1);
+    // Cut it at exactly SIZE bytes, backing up to the start of a character so it's still valid UTF-8.
+    let mut end = SIZE;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    text.into_bytes()
+}
+
+fn ciphers(c: &mut Criterion) {
+    let text = mixed_text();
+    let alphabet = parse_alphabet("QWERTYUIOPASDFGHJKLZXCVBNM").expect("every letter is there once");
+    let ciphers: Vec<Box<dyn Cipher>> = vec![
+        Box::new(Rot13),
+        Box::new(Affine { a: 5, b: 8 }),
+        Box::new(RailFence { rails: 3 }),
+        Box::new(Columnar { key: Zeroizing::new(String::from("ZEBRAS")) }),
+        Box::new(Substitution { mapping: Zeroizing::new(alphabet) }),
+        Box::new(Base32),
+        Box::new(Rot13Rot5),
+        Box::new(Rot13Unicode),
+        Box::new(Caesar { shift: 3, alphabet: named_alphabet("spanish").expect("a built-in alphabet") }),
+    ];
+
+    let mut group = c.benchmark_group("encrypt");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for cipher in &ciphers {
+        group.bench_with_input(BenchmarkId::from_parameter(cipher.name()), &text, |b, text| {
+            b.iter(|| cipher.process(text, Direction::Encrypt).expect("the text is valid UTF-8"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ciphers);
+criterion_main!(benches);
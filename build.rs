@@ -0,0 +1,36 @@
+// This build script records the git commit the app was built from, so "--version" can report it.
+// Building from a source archive without git still works; the commit is just left out.
+// With the ffi feature, it also writes the C header for the library's C interface.
+
+use std::process::Command;
+
+fn main() {
+    let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output();
+    if let Ok(output) = output {
+        let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !hash.is_empty() {
+            println!("cargo:rustc-env=GIT_HASH={hash}");
+        }
+    }
+    // Only look for a new commit when the checked out commit might have changed.
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    #[cfg(feature = "ffi")]
+    write_c_header();
+}
+
+// This function writes include/rfec.h, declaring the functions and constants in src/ffi.rs for C programs.
+#[cfg(feature = "ffi")]
+fn write_c_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).unwrap();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{crate_dir}/src/ffi.rs"))
+        .generate()
+        .expect("could not generate the C header")
+        .write_to_file(format!("{crate_dir}/include/rfec.h"));
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
@@ -0,0 +1,35 @@
+/*
+ * Encrypts a file with the columnar cipher through the library's C interface, then decrypts it again.
+ *
+ * Build the library and this example with "make c-example", or by hand:
+ *
+ *     cargo build --release --features ffi
+ *     cc examples/c_usage.c -Iinclude -Ltarget/release -lrust_project -o target/c_usage
+ *     LD_LIBRARY_PATH=target/release target/c_usage notes.txt notes.enc notes.dec
+ */
+
+#include <stdio.h>
+#include "rfec.h"
+
+int main(int argc, char **argv) {
+    if (argc != 4) {
+        fprintf(stderr, "Usage: %s INFILE ENCRYPTED DECRYPTED\n", argv[0]);
+        return 2;
+    }
+
+    int result = rfec_encrypt(argv[1], argv[2], "ZEBRAS", RFEC_CIPHER_COLUMNAR);
+    if (result != RFEC_OK) {
+        fprintf(stderr, "Could not encrypt %s: error %d\n", argv[1], result);
+        return 1;
+    }
+    printf("Encrypted %s to %s\n", argv[1], argv[2]);
+
+    /* The file's header says which cipher encrypted it, so the cipher ID here is only used without one. */
+    result = rfec_decrypt(argv[2], argv[3], "ZEBRAS", RFEC_CIPHER_COLUMNAR);
+    if (result != RFEC_OK) {
+        fprintf(stderr, "Could not decrypt %s: error %d\n", argv[2], result);
+        return 1;
+    }
+    printf("Decrypted %s to %s\n", argv[2], argv[3]);
+    return 0;
+}
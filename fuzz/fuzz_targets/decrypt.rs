@@ -0,0 +1,57 @@
+// Feeds arbitrary bytes to everything that reads encrypted data, to make sure malformed input (an empty file,
+// a header cut short, absurd length fields) is always turned away with an error instead of a panic or a read
+// past the end of the data.
+//
+// Run it from the top of the repository with a nightly toolchain:
+//
+//     cargo install cargo-fuzz
+//     cargo +nightly fuzz run decrypt
+//
+// Anything that makes it crash is saved under fuzz/artifacts/decrypt, and can be replayed with
+// "cargo +nightly fuzz run decrypt <file>".
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_project::jwe::deserialize_jwe_compact;
+use rust_project::kdf::kdf_from_bytes;
+use rust_project::{
+    base32_decode, named_alphabet, parse_alphabet, Affine, Base32, Caesar, Cipher, Columnar, Direction, FileHeader,
+    RailFence, Rot13, Rot13Rot5, Rot13Unicode, Substitution, Zeroizing,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // The header comes first, then whatever's left goes to the cipher it names, like decrypting a file does.
+    let body = match FileHeader::parse(data) {
+        Ok(Some((_, rest))) => rest,
+        Ok(None) => data,
+        Err(_) => return,
+    };
+
+    let ciphers: [Box<dyn Cipher>; 9] = [
+        Box::new(Rot13),
+        Box::new(Affine { a: 5, b: 8 }),
+        Box::new(RailFence { rails: 3 }),
+        Box::new(Columnar { key: Zeroizing::new(String::from("ZEBRAS")) }),
+        Box::new(Substitution {
+            mapping: Zeroizing::new(parse_alphabet("QWERTYUIOPASDFGHJKLZXCVBNM").expect("a valid alphabet")),
+        }),
+        Box::new(Base32),
+        Box::new(Rot13Rot5),
+        Box::new(Rot13Unicode),
+        Box::new(Caesar { shift: 3, alphabet: named_alphabet("spanish").expect("a built-in alphabet") }),
+    ];
+    for cipher in &ciphers {
+        let _ = cipher.process(body, Direction::Decrypt);
+    }
+
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = base32_decode(text);
+        let _ = deserialize_jwe_compact(text);
+    }
+
+    // Stored KDF settings are length-checked before they're read.
+    for kdf in ["argon2id", "pbkdf2", "scrypt"] {
+        let _ = kdf_from_bytes(kdf, data);
+    }
+});
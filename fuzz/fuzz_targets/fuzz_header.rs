@@ -0,0 +1,28 @@
+// Feeds arbitrary bytes to FileHeader::read_from, the way a damaged or truncated encrypted file would reach it,
+// to make sure it always comes back with either a header or a clean error, and never panics.
+//
+// Run it from the top of the repository with a nightly toolchain (CONTRIBUTING.md has the release checklist):
+//
+//     cargo +nightly fuzz run fuzz_header
+//
+// The seed files in fuzz/corpus/fuzz_header give it a valid header to start mutating from.
+
+#![no_main]
+
+use std::io::{Cursor, ErrorKind};
+use libfuzzer_sys::fuzz_target;
+use rust_project::FileHeader;
+
+fuzz_target!(|data: &[u8]| {
+    let mut stream = Cursor::new(data);
+    match FileHeader::read_from(&mut stream) {
+        Ok(header) => {
+            // A header that was read writes back out as exactly the bytes it came from, and the stream is left
+            // at the start of the encrypted data.
+            assert_eq!(header.to_bytes()[..], data[..header.len()]);
+            assert_eq!(stream.position(), header.len() as u64);
+        }
+        // Too little data, or data that isn't a header this version knows, are the only ways it can fail.
+        Err(e) => assert!(matches!(e.kind(), ErrorKind::UnexpectedEof | ErrorKind::InvalidData), "{e}"),
+    }
+});
@@ -0,0 +1,15 @@
+// A simple fuzz target to start with: rot13 on arbitrary text has to give back the same number of characters,
+// and applying it twice has to give back the text it started with.
+//
+//     cargo +nightly fuzz run fuzz_rot13
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_project::rot13;
+
+fuzz_target!(|text: &str| {
+    let rotated = rot13(text);
+    assert_eq!(rotated.len(), text.len());
+    assert_eq!(rot13(&rotated), text);
+});
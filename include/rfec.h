@@ -0,0 +1,94 @@
+#ifndef RFEC_H
+#define RFEC_H
+
+/* Generated by cbindgen from src/ffi.rs when building with --features ffi. Don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Success.
+#define RFEC_OK 0
+
+// A pointer that had to be given was NULL.
+#define RFEC_ERR_NULL_POINTER -1
+
+// A path or passphrase wasn't valid UTF-8.
+#define RFEC_ERR_INVALID_UTF8 -2
+
+// The cipher ID isn't one of the ciphers below.
+#define RFEC_ERR_UNKNOWN_CIPHER -3
+
+// The passphrase can't be used as the cipher's key.
+#define RFEC_ERR_INVALID_KEY -4
+
+// The input file couldn't be read.
+#define RFEC_ERR_READ -5
+
+// The output file couldn't be written.
+#define RFEC_ERR_WRITE -6
+
+// The cipher couldn't encrypt/decrypt the file, for example because it isn't text.
+#define RFEC_ERR_CIPHER -7
+
+// Something went wrong inside the library. This is a bug.
+#define RFEC_ERR_PANIC -8
+
+// ROT13. The cipher IDs are the same ones the command-line tool saves in file headers.
+#define RFEC_CIPHER_ROT13 1
+
+// The passphrase is the multiplier and the shift, like "5,8".
+#define RFEC_CIPHER_AFFINE 2
+
+// The passphrase is the number of rails, like "3".
+#define RFEC_CIPHER_RAIL_FENCE 3
+
+// The passphrase is the key.
+#define RFEC_CIPHER_COLUMNAR 4
+
+// The passphrase is the 26 letters A to Z are replaced with.
+#define RFEC_CIPHER_SUBSTITUTION 5
+
+// Encodes any file as Base32 text.
+#define RFEC_CIPHER_BASE32 6
+
+// ROT13 for letters and ROT5 for digits.
+#define RFEC_CIPHER_ROT13_5 9
+
+// ROT13 extended to Greek, Cyrillic, and Hebrew letters.
+#define RFEC_CIPHER_ROT13_UNICODE 10
+
+// The passphrase is the shift, optionally followed by the name of an alphabet, like "3" or "3,spanish".
+#define RFEC_CIPHER_CAESAR 11
+
+// Encrypts the file at `in_path` with a cipher, saving the result to `out_path`, which is replaced if it's
+// already there.
+//
+// `cipher_id` is one of the `RFEC_CIPHER_*` IDs, and `passphrase` is the cipher's key. It can be NULL for the
+// ciphers that don't need a key. All the strings are NUL-terminated UTF-8.
+//
+// # Safety
+//
+// Each pointer has to be NULL or point to a NUL-terminated string that stays valid during the call.
+int rfec_encrypt(const char *in_path,
+                 const char *out_path,
+                 const char *passphrase,
+                 uint8_t cipher_id);
+
+// Decrypts the file at `in_path`, saving the result to `out_path`, which is replaced if it's already there.
+//
+// A file with a header is decrypted with the cipher the header names, and `cipher_id` is only used for files
+// without one. Files with a MAC, or that were compressed or armored, give [`RFEC_ERR_CIPHER`]. Otherwise, this
+// takes the same arguments as [`rfec_encrypt`].
+//
+// # Safety
+//
+// Each pointer has to be NULL or point to a NUL-terminated string that stays valid during the call.
+int rfec_decrypt(const char *in_path,
+                 const char *out_path,
+                 const char *passphrase,
+                 uint8_t cipher_id);
+
+#endif  /* RFEC_H */
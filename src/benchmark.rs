@@ -0,0 +1,244 @@
+// This module measures how fast each cipher encrypts and decrypts on the user's computer, so they can pick
+// one that's fast enough for their files.
+
+use std::fs;
+use std::process;
+use std::env;
+use std::time::Instant;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::json;
+use rust_project::{parse_alphabet, CipherError, Direction, Zeroizing};
+use crate::progress::format_bytes;
+use crate::{map_file, read_with_progress, select_cipher, transform, Config, Verbosity, CIPHERS};
+
+// How many times each cipher is run before it's timed, so the caches and memory allocator are warmed up.
+const WARM_UP_ITERATIONS: usize = 3;
+
+// The warm-up runs only use this much of the payload, so they don't take longer than the real thing.
+const WARM_UP_SIZE: usize = 1024 * 1024;
+
+// This struct holds the results of benchmarking one cipher.
+struct Measurement {
+    // The name of the cipher, like "rot13".
+    cipher: &'static str,
+    // How many mebibytes a second were encrypted.
+    encrypt_speed: f64,
+    // How many mebibytes a second were decrypted.
+    decrypt_speed: f64,
+    // How much bigger the encrypted payload was than the original, as a percentage.
+    overhead: f64,
+    // How many times faster encrypting was on several threads than on one, for ciphers that can use them.
+    parallel_speedup: Option<f64>,
+}
+
+// This function benchmarks the ciphers asked for on random text, printing a table of the results (or a JSON
+// object for each cipher). It returns an error if a cipher fails or doesn't give back the original text.
+pub fn run_benchmark(config: &Config) -> Result<(), String> {
+    let ciphers: Vec<&'static str> = match config.cipher.as_str() {
+        "all" => CIPHERS.iter().map(|cipher| cipher.name).collect(),
+        name => CIPHERS.iter().map(|cipher| cipher.name).filter(|&cipher| cipher == name).collect(),
+    };
+
+    if !config.json {
+        println!("Benchmarking on {} of random text...", format_bytes(config.benchmark_size as u64));
+    }
+    let payload = random_text(config.benchmark_size);
+
+    if !config.json {
+        println!(
+            "{:<17} {:>15} {:>15} {:>10} {:>10}",
+            "cipher", "encrypt MiB/s", "decrypt MiB/s", "overhead", "parallel"
+        );
+    }
+    for &cipher in &ciphers {
+        let measurement = measure(cipher, &payload, config)?;
+        if config.json {
+            println!("{}", json!({
+                "cipher": measurement.cipher,
+                "bytes": payload.len(),
+                "encrypt_mib_per_sec": measurement.encrypt_speed,
+                "decrypt_mib_per_sec": measurement.decrypt_speed,
+                "overhead_percent": measurement.overhead,
+                "parallel_speedup": measurement.parallel_speedup,
+            }));
+        } else {
+            let speedup = match measurement.parallel_speedup {
+                Some(speedup) => format!("{speedup:.1}x"),
+                None => String::from("-"),
+            };
+            println!(
+                "{:<17} {:>15.1} {:>15.1} {:>9.1}% {:>10}",
+                measurement.cipher, measurement.encrypt_speed, measurement.decrypt_speed, measurement.overhead, speedup
+            );
+        }
+    }
+
+    // Also see how much faster it is to map a big file into memory than to read it, unless mapping is off.
+    if config.mmap_threshold.is_some() {
+        let read_config = cipher_config(ciphers.first().copied().unwrap_or("rot13"), config);
+        let (buffered_speed, mapped_speed) = measure_reading(&payload, &read_config)?;
+        if config.json {
+            println!("{}", json!({
+                "bytes": payload.len(),
+                "buffered_read_mib_per_sec": buffered_speed,
+                "mmap_read_mib_per_sec": mapped_speed,
+            }));
+        } else {
+            println!(
+                "Reading and encrypting a file: {buffered_speed:.1} MiB/s buffered, {mapped_speed:.1} MiB/s mapped"
+            );
+        }
+    }
+    Ok(())
+}
+
+// This function saves the payload to a temporary file, then times reading it back and encrypting it, first by
+// reading it into memory and then by mapping it. It returns both speeds in mebibytes a second.
+fn measure_reading(payload: &[u8], config: &Config) -> Result<(f64, f64), String> {
+    let path = env::temp_dir().join(format!("rust_project-benchmark-{}", process::id()));
+    let path_str = path.to_string_lossy();
+    fs::write(&path, payload).map_err(|e| format!("could not write {path_str}: {e}"))?;
+    let fail = |e: String| {
+        let _ = fs::remove_file(&path);
+        e
+    };
+
+    let (key, nonce) = random_key_and_nonce(config);
+    let start = Instant::now();
+    let buffered =
+        read_with_progress(&path_str, None, true).map_err(|e| fail(format!("could not read {path_str}: {e}")))?;
+    transform_keyed(&buffered, config, &key, &nonce).map_err(|e| fail(e.to_string()))?;
+    let buffered_seconds = start.elapsed().as_secs_f64();
+    drop(buffered);
+
+    let start = Instant::now();
+    let mapped = map_file(&path).ok_or_else(|| fail(format!("could not map {path_str} into memory")))?;
+    transform_keyed(&mapped, config, &key, &nonce).map_err(|e| fail(e.to_string()))?;
+    let mapped_seconds = start.elapsed().as_secs_f64();
+    drop(mapped);
+
+    let _ = fs::remove_file(&path);
+    let mebibytes = payload.len() as f64 / (1024.0 * 1024.0);
+    Ok((mebibytes / buffered_seconds.max(f64::EPSILON), mebibytes / mapped_seconds.max(f64::EPSILON)))
+}
+
+// This function times one cipher encrypting the payload and then decrypting it again.
+fn measure(cipher: &'static str, payload: &[u8], config: &Config) -> Result<Measurement, String> {
+    let encrypt_config = cipher_config(cipher, config);
+    let decrypt_config = Config { mode: Direction::Decrypt, ..encrypt_config.clone() };
+    let fail = |e| format!("{cipher} failed: {e}");
+    let (key, nonce) = random_key_and_nonce(&encrypt_config);
+    let run = |data: &[u8], config: &Config| transform_keyed(data, config, &key, &nonce).map_err(fail);
+
+    let warm_up = &payload[..payload.len().min(WARM_UP_SIZE)];
+    for _ in 0..WARM_UP_ITERATIONS {
+        let encrypted = run(warm_up, &encrypt_config)?;
+        run(&encrypted, &decrypt_config)?;
+    }
+
+    let start = Instant::now();
+    let encrypted = run(payload, &encrypt_config)?;
+    let encrypt_seconds = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let decrypted = run(&encrypted, &decrypt_config)?;
+    let decrypt_seconds = start.elapsed().as_secs_f64();
+
+    // A fast cipher is no use if it doesn't give back what went in.
+    if decrypted != payload {
+        return Err(format!("{cipher} did not decrypt back to the original text."));
+    }
+
+    // Big payloads are split up and encrypted on several threads when the cipher allows it. Time doing it on
+    // a single thread as well to see how much that helps, and make sure both ways give the same result.
+    let mut parallel_speedup = None;
+    if select_cipher(&encrypt_config).works_letter_by_letter() && encrypt_config.threads > 1 {
+        let sequential_config = Config { threads: 1, ..encrypt_config.clone() };
+        let start = Instant::now();
+        let sequential = transform(payload, &sequential_config).map_err(fail)?;
+        let sequential_seconds = start.elapsed().as_secs_f64();
+        if sequential != encrypted {
+            return Err(format!("{cipher} gave a different result on several threads than on one."));
+        }
+        parallel_speedup = Some(sequential_seconds / encrypt_seconds.max(f64::EPSILON));
+    }
+
+    let mebibytes = payload.len() as f64 / (1024.0 * 1024.0);
+    let overhead = if payload.is_empty() {
+        0.0
+    } else {
+        (encrypted.len() as f64 - payload.len() as f64) * 100.0 / payload.len() as f64
+    };
+    Ok(Measurement {
+        cipher,
+        encrypt_speed: mebibytes / encrypt_seconds.max(f64::EPSILON),
+        decrypt_speed: mebibytes / decrypt_seconds.max(f64::EPSILON),
+        overhead,
+        parallel_speedup,
+    })
+}
+
+// This function makes a copy of the settings that uses the given cipher. Any key or other setting the
+// cipher needs that wasn't given on the command line is filled in, so it's ready before the timing starts.
+fn cipher_config(cipher: &str, config: &Config) -> Config {
+    let mut cipher_config = Config {
+        mode: Direction::Encrypt,
+        verbosity: Verbosity::Normal,
+        quiet: true,
+        cipher: String::from(cipher),
+        ..config.clone()
+    };
+    match cipher {
+        "affine" => {
+            cipher_config.affine_a = Some(config.affine_a.unwrap_or(5));
+            cipher_config.affine_b = Some(config.affine_b.unwrap_or(8));
+        }
+        "rail-fence" => cipher_config.rails = Some(config.rails.unwrap_or(3)),
+        "caesar" => cipher_config.shift = Some(config.shift.unwrap_or(3)),
+        "columnar" => {
+            cipher_config.key.get_or_insert_with(|| Zeroizing::new(String::from("ZEBRAS")));
+        }
+        "substitution" => {
+            let qwerty = parse_alphabet("QWERTYUIOPASDFGHJKLZXCVBNM").expect("every letter is there once");
+            cipher_config.alphabet.get_or_insert_with(|| Zeroizing::new(qwerty));
+        }
+        _ => {}
+    }
+    cipher_config
+}
+
+// This function makes a random key and nonce for ciphers that take them, like ChaCha20-Poly1305. Deriving one
+// from a password would take longer than encrypting the payload, so it's left out of the timing. The other
+// ciphers get an empty key and nonce.
+fn random_key_and_nonce(config: &Config) -> (Zeroizing<Vec<u8>>, Vec<u8>) {
+    let cipher = select_cipher(config);
+    let mut key = Zeroizing::new(vec![0; cipher.key_len()]);
+    let mut nonce = vec![0; cipher.nonce_len()];
+    OsRng.fill_bytes(&mut key);
+    OsRng.fill_bytes(&mut nonce);
+    (key, nonce)
+}
+
+// This function encrypts/decrypts like transform, except that ciphers that take a key of their own are given
+// the one from random_key_and_nonce.
+fn transform_keyed(data: &[u8], config: &Config, key: &[u8], nonce: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let cipher = select_cipher(config);
+    match (cipher.key_len(), config.mode) {
+        (0, _) => transform(data, config),
+        (_, Direction::Encrypt) => cipher.encrypt(key, nonce, data, &[]),
+        (_, Direction::Decrypt) => cipher.decrypt(key, nonce, data, &[]),
+    }
+}
+
+// This function makes a payload of random printable ASCII text, so every cipher (even the ones that only
+// work on text) can take it.
+fn random_text(size: usize) -> Vec<u8> {
+    let mut payload = vec![0; size];
+    OsRng.fill_bytes(&mut payload);
+    for byte in &mut payload {
+        // There are 95 printable characters, from the space up to the tilde.
+        *byte = b' ' + *byte % 95;
+    }
+    payload
+}
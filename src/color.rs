@@ -0,0 +1,107 @@
+// This module colors the important parts of what's printed (errors in red, warnings in yellow, and successes
+// in green) so they're easy to spot. Colors are only used when the text is going to a terminal that can show
+// them, so nothing piped to a file or another program ever has escape codes in it. Setting the NO_COLOR
+// environment variable turns them off too, and "--color always" or "--color never" overrides all of that.
+
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+// This enum lists the choices for "--color".
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorChoice {
+    // Use colors when printing to a terminal, unless NO_COLOR is set.
+    Auto,
+    // Always use colors, even when the output is piped.
+    Always,
+    // Never use colors.
+    Never,
+}
+
+impl ColorChoice {
+    // The names of the choices, as "--color" takes them.
+    pub const NAMES: [&'static str; 3] = ["auto", "always", "never"];
+
+    // This function reads a "--color" value, giving None if it isn't one of the choices.
+    pub fn parse(value: &str) -> Option<ColorChoice> {
+        match value {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+// This enum lists the colors used, and what they mean.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Color {
+    // Something went wrong.
+    Red,
+    // Something was skipped, or might need looking at.
+    Yellow,
+    // Something worked.
+    Green,
+}
+
+// This enum lists where some text is going to be printed, since only one of them might be a terminal.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// The choice made with "--color". It's set once the arguments have been read, and until then it's "auto".
+static CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+// This function records the choice made with "--color".
+pub fn set_choice(choice: ColorChoice) {
+    let _ = CHOICE.set(choice);
+}
+
+// This function wraps some text in the escape codes that color it, if colors should be used on the stream the
+// text is going to. Otherwise, the text is given back as it was.
+pub fn paint(text: &str, color: Color, stream: Stream) -> String {
+    if !enabled(stream) {
+        return text.to_string();
+    }
+    let code = match color {
+        Color::Red => "31",
+        Color::Yellow => "33",
+        Color::Green => "32",
+    };
+    format!("\x1b[{code}m{text}\x1b[0m")
+}
+
+// This function decides whether to use colors on a stream.
+fn enabled(stream: Stream) -> bool {
+    match CHOICE.get().copied().unwrap_or(ColorChoice::Auto) {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let is_terminal = match stream {
+                Stream::Stdout => io::stdout().is_terminal(),
+                Stream::Stderr => io::stderr().is_terminal(),
+            };
+            // NO_COLOR only counts when it's set to something (see no-color.org). A "dumb" terminal can't
+            // show colors either.
+            is_terminal
+                && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && env::var_os("TERM").is_none_or(|term| term != "dumb")
+                && understands_escape_codes()
+        }
+    }
+}
+
+// The old Windows console prints escape codes as they are, instead of coloring anything. Windows Terminal
+// (which sets WT_SESSION) and terminals that set TERM, like the ones that come with Git, understand them.
+#[cfg(windows)]
+fn understands_escape_codes() -> bool {
+    env::var_os("WT_SESSION").is_some() || env::var_os("TERM").is_some()
+}
+
+// Every terminal on other systems understands escape codes.
+#[cfg(not(windows))]
+fn understands_escape_codes() -> bool {
+    true
+}
@@ -0,0 +1,258 @@
+// This module writes tab completion scripts for bash, zsh, fish, and PowerShell, for "completions SHELL".
+//
+// The options and commands are read out of the help text, so anything "--help" describes can be completed
+// without listing it twice. The cipher names come from CIPHERS, so a new cipher completes after "--cipher" as
+// soon as it's added there.
+
+use crate::color::ColorChoice;
+use crate::{CIPHERS, HELP};
+
+// The shells there are completion scripts for.
+pub const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+
+// This struct describes one option found in the help text.
+struct Flag {
+    // The option's name, dashes included, like "--cipher" or "-c".
+    name: String,
+    // What the option's value is called in the help text, like "FILE", if it takes one.
+    value: Option<String>,
+}
+
+impl Flag {
+    // This function gives the words that can follow the option, if there's a fixed list of them.
+    fn choices(&self) -> Option<Vec<String>> {
+        let value = self.value.as_deref()?;
+        if self.name == "--cipher" || self.name == "-c" {
+            return Some(CIPHERS.iter().map(|cipher| cipher.name.to_string()).chain([String::from("all")]).collect());
+        }
+        if self.name == "--color" {
+            return Some(ColorChoice::NAMES.iter().map(|name| name.to_string()).collect());
+        }
+        // A value like "base64|hex" lists its choices.
+        value.contains('|').then(|| value.split('|').map(String::from).collect())
+    }
+
+    // This function works out whether the option's value is a path, from what the help text calls it.
+    fn takes_path(&self) -> bool {
+        matches!(self.value.as_deref(), Some("FILE" | "PATH" | "DIR" | "OUTDIR"))
+    }
+}
+
+// This function gives a completion script for one of SHELLS, or None for any other shell.
+pub fn script(shell: &str) -> Option<String> {
+    let flags = flags();
+    let commands = commands();
+    match shell {
+        "bash" => Some(bash(&flags, &commands)),
+        "zsh" => Some(zsh(&flags, &commands)),
+        "fish" => Some(fish(&flags, &commands)),
+        "powershell" => Some(powershell(&flags, &commands)),
+        _ => None,
+    }
+}
+
+// This function finds every option mentioned in the help text, and what its value is called if it takes one.
+fn flags() -> Vec<Flag> {
+    let mut flags: Vec<Flag> = Vec::new();
+    let words: Vec<&str> = HELP
+        .split(|c: char| c.is_whitespace() || matches!(c, '[' | ']' | '(' | ')' | ','))
+        .map(|word| word.trim_end_matches(['.', ';', ':']))
+        .collect();
+    for (i, name) in words.iter().enumerate() {
+        let letters = name.trim_start_matches('-');
+        let dashes = name.len() - letters.len();
+        let is_long = dashes == 2
+            && letters.len() > 1
+            && letters.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        let is_short =
+            dashes == 1 && (1..=2).contains(&letters.len()) && letters.chars().all(|c| c.is_ascii_alphabetic());
+        if !is_long && !is_short {
+            continue;
+        }
+        // A value is written in capitals after the option, like "--rails N", or as a list of choices, like
+        // "--format base64|hex".
+        let value = words.get(i + 1).filter(|next| {
+            next.contains('|') || (!next.is_empty() && next.chars().all(|c| c.is_ascii_uppercase()))
+        });
+        let value = value.map(|value| value.to_string());
+        match flags.iter_mut().find(|flag| flag.name == *name) {
+            Some(flag) => flag.value = flag.value.take().or(value),
+            None => flags.push(Flag { name: name.to_string(), value }),
+        }
+    }
+    // A short option takes the same value as the long one it's listed with, like "-c, --cipher NAME".
+    for line in HELP.lines() {
+        let names: Vec<&str> = line.split_whitespace().take_while(|word| word.starts_with('-')).collect();
+        if let [short, long, ..] = names.as_slice() {
+            let (short, long) = (short.trim_end_matches(','), long.trim_end_matches(','));
+            let value = flags.iter().find(|flag| flag.name == long).and_then(|flag| flag.value.clone());
+            if let Some(flag) = flags.iter_mut().find(|flag| flag.name == short) {
+                flag.value = flag.value.take().or(value);
+            }
+        }
+    }
+    flags
+}
+
+// This function finds the commands in the help text, like "verify", which are listed under "Commands:".
+fn commands() -> Vec<String> {
+    HELP.lines()
+        .skip_while(|line| *line != "Commands:")
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter(|line| line.starts_with("  ") && !line.starts_with("   "))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+// This function joins some words with spaces.
+fn join(words: &[String]) -> String {
+    words.join(" ")
+}
+
+// This function lists every option's name.
+fn names(flags: &[Flag]) -> Vec<String> {
+    flags.iter().map(|flag| flag.name.clone()).collect()
+}
+
+// This function lists the names of the options that take a value, but not one that can be completed.
+fn free_value_names(flags: &[Flag]) -> Vec<String> {
+    flags
+        .iter()
+        .filter(|flag| flag.value.is_some() && flag.choices().is_none() && !flag.takes_path())
+        .map(|flag| flag.name.clone())
+        .collect()
+}
+
+fn bash(flags: &[Flag], commands: &[String]) -> String {
+    let mut cases = String::new();
+    for flag in flags {
+        if let Some(choices) = flag.choices() {
+            cases += &format!(
+                "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+                flag.name,
+                join(&choices)
+            );
+        }
+    }
+    let free = free_value_names(flags);
+    if !free.is_empty() {
+        cases += &format!("        {}) return ;;\n", free.join("|"));
+    }
+    format!(
+        r#"# bash completion for rust_project. Load it with: source <(rust_project completions bash)
+_rust_project() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+{cases}    esac
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "{flags}" -- "$cur"))
+    elif [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W "{commands}" -- "$cur") $(compgen -f -- "$cur"))
+    else
+        COMPREPLY=($(compgen -f -- "$cur"))
+    fi
+}}
+complete -o filenames -F _rust_project rust_project
+"#,
+        flags = join(&names(flags)),
+        commands = join(commands),
+    )
+}
+
+fn zsh(flags: &[Flag], commands: &[String]) -> String {
+    let mut cases = String::new();
+    for flag in flags {
+        if let Some(choices) = flag.choices() {
+            cases += &format!("        ({}) compadd -- {}; return ;;\n", flag.name, join(&choices));
+        }
+    }
+    let free = free_value_names(flags);
+    if !free.is_empty() {
+        cases += &format!("        ({}) return ;;\n", free.join("|"));
+    }
+    format!(
+        r#"#compdef rust_project
+# zsh completion for rust_project. Load it with: source <(rust_project completions zsh)
+_rust_project() {{
+    case "$words[CURRENT-1]" in
+{cases}    esac
+    if [[ "$PREFIX" == -* ]]; then
+        compadd -- {flags}
+    else
+        if (( CURRENT == 2 )); then
+            compadd -- {commands}
+        fi
+        _files
+    fi
+}}
+if [[ "$zsh_eval_context[-1]" == loadautofunc ]]; then
+    _rust_project "$@"
+else
+    compdef _rust_project rust_project
+fi
+"#,
+        flags = join(&names(flags)),
+        commands = join(commands),
+    )
+}
+
+fn fish(flags: &[Flag], commands: &[String]) -> String {
+    let mut script = String::from("# fish completion for rust_project. Load it with:\n");
+    script += "#     rust_project completions fish | source\n";
+    script += &format!("complete -c rust_project -n __fish_use_subcommand -a '{}'\n", join(commands));
+    for flag in flags {
+        let name = match flag.name.strip_prefix("--") {
+            Some(long) => format!("-l {long}"),
+            // fish only has single letter short options, so "-vv" is an old-style one.
+            None if flag.name.len() == 2 => format!("-s {}", &flag.name[1..]),
+            None => format!("-o {}", &flag.name[1..]),
+        };
+        let value = match (flag.choices(), flag.takes_path()) {
+            (Some(choices), _) => format!(" -x -a '{}'", join(&choices)),
+            (None, true) => String::from(" -r -F"),
+            (None, false) if flag.value.is_some() => String::from(" -x"),
+            (None, false) => String::new(),
+        };
+        script += &format!("complete -c rust_project {name}{value}\n");
+    }
+    script
+}
+
+fn powershell(flags: &[Flag], commands: &[String]) -> String {
+    let quoted = |words: &[String]| words.iter().map(|word| format!("'{word}'")).collect::<Vec<_>>().join(", ");
+    let mut cases = String::new();
+    for flag in flags {
+        if let Some(choices) = flag.choices() {
+            cases += &format!("        '{}' {{ @({}) }}\n", flag.name, quoted(&choices));
+        }
+    }
+    let free = free_value_names(flags);
+    if !free.is_empty() {
+        cases += &format!("        {{ $_ -in @({}) }} {{ return }}\n", quoted(&free));
+    }
+    format!(
+        r#"# PowerShell completion for rust_project. Load it with:
+#     rust_project completions powershell | Out-String | Invoke-Expression
+Register-ArgumentCompleter -Native -CommandName rust_project -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements | ForEach-Object {{ $_.ToString() }})
+    $previous = if ($wordToComplete) {{ $words[-2] }} else {{ $words[-1] }}
+    $choices = switch -CaseSensitive ($previous) {{
+{cases}        default {{
+            if ($wordToComplete -like '-*') {{ @({flags}) }}
+            elseif ($words.Count -le 2) {{ @({commands}) }}
+        }}
+    }}
+    # With nothing to offer, PowerShell completes file names instead.
+    $choices | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }}
+}}
+"#,
+        flags = quoted(&names(flags)),
+        commands = quoted(commands),
+    )
+}
@@ -0,0 +1,193 @@
+// This module reads default options from a config file, so options someone always wants don't have to be typed
+// every time. The file is TOML, and each key is the long name of a command-line option:
+//
+//     cipher = "base32"
+//     suffix = "enc"
+//     quiet = true
+//
+// A few options can also be set with environment variables, like RUST_PROJECT_CIPHER=base32, which is handier
+// for CI systems, and keeps the key out of the command line.
+//
+// Anything given on the command line takes priority over the environment variables, which take priority over
+// the config file, which takes priority over the app's built-in defaults. The options that are switched on by a
+// flag (like "force") can't be switched off again on the command line once the config file turns them on,
+// except for "quiet" and "verbose", which give way to each other.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use rust_project::Zeroizing;
+use crate::color::ColorChoice;
+use crate::CIPHERS;
+
+// The environment variables that can set options, and the option each one sets.
+pub const ENV_CIPHER: &str = "RUST_PROJECT_CIPHER";
+pub const ENV_SUFFIX: &str = "RUST_PROJECT_SUFFIX";
+pub const ENV_KEY: &str = "RUST_PROJECT_KEY";
+pub const ENV_VERBOSE: &str = "RUST_PROJECT_VERBOSE";
+
+// This struct holds the defaults read from a config file and the environment. Options neither of them mention
+// are None.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FileDefaults {
+    #[serde(deserialize_with = "cipher_name")]
+    pub cipher: Option<String>,
+    #[serde(deserialize_with = "extension")]
+    pub ext: Option<String>,
+    #[serde(deserialize_with = "extension")]
+    pub suffix: Option<String>,
+    pub quiet: Option<bool>,
+    pub verbose: Option<bool>,
+    pub force: Option<bool>,
+    pub fail_fast: Option<bool>,
+    #[serde(deserialize_with = "thread_count")]
+    pub threads: Option<usize>,
+    #[serde(deserialize_with = "color_choice")]
+    pub color: Option<ColorChoice>,
+    pub json: Option<bool>,
+    pub checksum: Option<bool>,
+    pub preserve: Option<bool>,
+    pub preserve_perms: Option<bool>,
+    pub header: Option<bool>,
+    pub exclude_hidden: Option<bool>,
+    #[serde(alias = "log-file")]
+    pub log: Option<String>,
+    // The key can only come from the environment, since a config file is too easy to leave lying around.
+    #[serde(skip)]
+    pub key: Option<Zeroizing<String>>,
+}
+
+// This function reads the defaults from the config file given with "--config", or from the usual config file
+// for this user if there is one, and then from the environment variables. "--no-config" skips the config file
+// entirely, but not the environment variables. The defaults have to be read before the rest of the arguments
+// are parsed, since they're parsed on top of them.
+pub fn load(args: &[String]) -> Result<FileDefaults, String> {
+    let mut defaults = load_file(args)?;
+    apply_env(&mut defaults)?;
+    Ok(defaults)
+}
+
+// This function reads the defaults from the config file, if there's one to read.
+fn load_file(args: &[String]) -> Result<FileDefaults, String> {
+    // Everything after "--" is a path, even if it looks like one of these options.
+    let mut options = args.iter().skip(1).take_while(|arg| *arg != "--");
+    let mut config_path = None;
+    while let Some(arg) = options.next() {
+        if arg == "--no-config" {
+            return Ok(FileDefaults::default());
+        } else if arg == "--config" {
+            config_path = Some(PathBuf::from(options.next().ok_or("--config needs a file.")?));
+        }
+    }
+    match config_path {
+        Some(path) => read(&path),
+        // The usual config file doesn't have to be there.
+        None => match default_path() {
+            Some(path) if path.is_file() => read(&path),
+            _ => Ok(FileDefaults::default()),
+        },
+    }
+}
+
+// This function gives where the config file usually is: "rust_project/config.toml" in the user's config
+// directory. That's $XDG_CONFIG_HOME (or ~/.config) on Linux and macOS, and %APPDATA% on Windows.
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("APPDATA")?),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+}
+
+// This function reads and checks a config file. A mistake in it is reported with the file's path, and the line
+// and key it's on.
+fn read(path: &Path) -> Result<FileDefaults, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("could not read config file {}: {e}", path.display()))?;
+    toml::from_str(&text).map_err(|e| format!("in config file {}: {e}", path.display()))
+}
+
+// This function replaces the defaults from the config file with any set by environment variables. A variable
+// that's set to something that can't be used is reported along with its name.
+fn apply_env(defaults: &mut FileDefaults) -> Result<(), String> {
+    if let Some(cipher) = env_value(ENV_CIPHER)? {
+        defaults.cipher = Some(check_cipher(cipher).map_err(|e| format!("in {ENV_CIPHER}: {e}"))?);
+    }
+    if let Some(suffix) = env_value(ENV_SUFFIX)? {
+        defaults.suffix = Some(check_extension(&suffix).map_err(|e| format!("in {ENV_SUFFIX}: {e}"))?);
+    }
+    if let Some(key) = env_value(ENV_KEY)? {
+        defaults.key = Some(Zeroizing::new(key));
+    }
+    if let Some(verbose) = env_value(ENV_VERBOSE)? {
+        let verbose = parse_bool(&verbose)
+            .ok_or_else(|| format!("in {ENV_VERBOSE}: use true or false (or 1 or 0), not {verbose}"))?;
+        // Being verbose replaces being quiet, the same as -v on the command line does.
+        defaults.verbose = Some(verbose);
+        if verbose {
+            defaults.quiet = Some(false);
+        }
+    }
+    Ok(())
+}
+
+// This function gives the value of an environment variable, or None if it isn't set.
+fn env_value(name: &str) -> Result<Option<String>, String> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(format!("in {name}: the value isn't valid UTF-8")),
+    }
+}
+
+// This function reads a yes or no from an environment variable.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" | "" => Some(false),
+        _ => None,
+    }
+}
+
+// These functions check the values that need more than the right type, the same way the command line does.
+
+fn check_cipher(name: String) -> Result<String, String> {
+    if !CIPHERS.iter().any(|cipher| cipher.name == name) {
+        let names: Vec<&str> = CIPHERS.iter().map(|cipher| cipher.name).collect();
+        return Err(format!("unknown cipher {name}; use one of {}", names.join(", ")));
+    }
+    Ok(name)
+}
+
+fn check_extension(ext: &str) -> Result<String, String> {
+    let ext = ext.trim_start_matches('.');
+    if ext.is_empty() {
+        return Err(String::from("the extension can't be empty"));
+    }
+    Ok(ext.to_string())
+}
+
+fn cipher_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    check_cipher(String::deserialize(deserializer)?).map(Some).map_err(D::Error::custom)
+}
+
+fn extension<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    check_extension(&String::deserialize(deserializer)?).map(Some).map_err(D::Error::custom)
+}
+
+fn thread_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<usize>, D::Error> {
+    match usize::deserialize(deserializer)? {
+        0 => Err(D::Error::custom("threads needs to be at least 1")),
+        threads => Ok(Some(threads)),
+    }
+}
+
+fn color_choice<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ColorChoice>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    let choice = ColorChoice::parse(&value)
+        .ok_or_else(|| D::Error::custom(format!("unknown color choice {value}; use auto, always, or never")))?;
+    Ok(Some(choice))
+}
@@ -0,0 +1,172 @@
+//! A C interface to the ciphers, built with the `ffi` feature, for programs written in C or C++.
+//!
+//! [`rfec_encrypt`] encrypts a file into another one, starting it with a [`FileHeader`] naming the cipher, the
+//! same as the command-line tool does, and [`rfec_decrypt`] decrypts one. Building with the feature also
+//! writes the C declarations to `include/rfec.h`. See `examples/c_usage.c` for how to call them.
+//!
+//! Both functions return [`RFEC_OK`] on success, or one of the negative `RFEC_ERR_*` codes. Nothing panics
+//! across the boundary into C: a panic is caught and reported as [`RFEC_ERR_PANIC`].
+
+use std::ffi::{c_char, c_int, CStr};
+use std::fs;
+use std::panic;
+use crate::{named_alphabet, parse_alphabet, Affine, Base32, Caesar, Cipher, CipherError, Columnar, Direction};
+use crate::{FileHeader, RailFence, Rot13, Rot13Rot5, Rot13Unicode, Substitution, Zeroizing};
+
+/// Success.
+pub const RFEC_OK: c_int = 0;
+/// A pointer that had to be given was NULL.
+pub const RFEC_ERR_NULL_POINTER: c_int = -1;
+/// A path or passphrase wasn't valid UTF-8.
+pub const RFEC_ERR_INVALID_UTF8: c_int = -2;
+/// The cipher ID isn't one of the ciphers below.
+pub const RFEC_ERR_UNKNOWN_CIPHER: c_int = -3;
+/// The passphrase can't be used as the cipher's key.
+pub const RFEC_ERR_INVALID_KEY: c_int = -4;
+/// The input file couldn't be read.
+pub const RFEC_ERR_READ: c_int = -5;
+/// The output file couldn't be written.
+pub const RFEC_ERR_WRITE: c_int = -6;
+/// The cipher couldn't encrypt/decrypt the file, for example because it isn't text.
+pub const RFEC_ERR_CIPHER: c_int = -7;
+/// Something went wrong inside the library. This is a bug.
+pub const RFEC_ERR_PANIC: c_int = -8;
+
+/// ROT13. The cipher IDs are the same ones the command-line tool saves in file headers.
+pub const RFEC_CIPHER_ROT13: u8 = 1;
+/// The passphrase is the multiplier and the shift, like "5,8".
+pub const RFEC_CIPHER_AFFINE: u8 = 2;
+/// The passphrase is the number of rails, like "3".
+pub const RFEC_CIPHER_RAIL_FENCE: u8 = 3;
+/// The passphrase is the key.
+pub const RFEC_CIPHER_COLUMNAR: u8 = 4;
+/// The passphrase is the 26 letters A to Z are replaced with.
+pub const RFEC_CIPHER_SUBSTITUTION: u8 = 5;
+/// Encodes any file as Base32 text.
+pub const RFEC_CIPHER_BASE32: u8 = 6;
+/// ROT13 for letters and ROT5 for digits.
+pub const RFEC_CIPHER_ROT13_5: u8 = 9;
+/// ROT13 extended to Greek, Cyrillic, and Hebrew letters.
+pub const RFEC_CIPHER_ROT13_UNICODE: u8 = 10;
+/// The passphrase is the shift, optionally followed by the name of an alphabet, like "3" or "3,spanish".
+pub const RFEC_CIPHER_CAESAR: u8 = 11;
+
+/// Encrypts the file at `in_path` with a cipher, saving the result to `out_path`, which is replaced if it's
+/// already there.
+///
+/// `cipher_id` is one of the `RFEC_CIPHER_*` IDs, and `passphrase` is the cipher's key. It can be NULL for the
+/// ciphers that don't need a key. All the strings are NUL-terminated UTF-8.
+///
+/// # Safety
+///
+/// Each pointer has to be NULL or point to a NUL-terminated string that stays valid during the call.
+#[no_mangle]
+pub unsafe extern "C" fn rfec_encrypt(
+    in_path: *const c_char,
+    out_path: *const c_char,
+    passphrase: *const c_char,
+    cipher_id: u8,
+) -> c_int {
+    catch_panics(|| {
+        let (in_path, out_path) = (required_str(in_path)?, required_str(out_path)?);
+        let cipher = cipher_for(cipher_id, optional_str(passphrase)?)?;
+        let data = fs::read(in_path).map_err(|_| RFEC_ERR_READ)?;
+        let mut encrypted = FileHeader::new(cipher_id).to_bytes();
+        encrypted.extend(cipher.process(&data, Direction::Encrypt).map_err(cipher_error)?);
+        fs::write(out_path, encrypted).map_err(|_| RFEC_ERR_WRITE)
+    })
+}
+
+/// Decrypts the file at `in_path`, saving the result to `out_path`, which is replaced if it's already there.
+///
+/// A file with a header is decrypted with the cipher the header names, and `cipher_id` is only used for files
+/// without one. Files with a MAC, or that were compressed or armored, give [`RFEC_ERR_CIPHER`]. Otherwise, this
+/// takes the same arguments as [`rfec_encrypt`].
+///
+/// # Safety
+///
+/// Each pointer has to be NULL or point to a NUL-terminated string that stays valid during the call.
+#[no_mangle]
+pub unsafe extern "C" fn rfec_decrypt(
+    in_path: *const c_char,
+    out_path: *const c_char,
+    passphrase: *const c_char,
+    cipher_id: u8,
+) -> c_int {
+    catch_panics(|| {
+        let (in_path, out_path) = (required_str(in_path)?, required_str(out_path)?);
+        let passphrase = optional_str(passphrase)?;
+        let data = fs::read(in_path).map_err(|_| RFEC_ERR_READ)?;
+        let (cipher_id, encrypted) = match FileHeader::parse(&data).map_err(cipher_error)? {
+            // A MAC can't be checked, nor compression or armor undone, here, so rather than giving back the
+            // file with a tag left on the end (or worse), those files are refused.
+            Some((header, _)) if header.mac || header.compressed || header.armored => return Err(RFEC_ERR_CIPHER),
+            Some((header, encrypted)) => (header.cipher_id, encrypted),
+            None => (cipher_id, &data[..]),
+        };
+        let decrypted = cipher_for(cipher_id, passphrase)?.process(encrypted, Direction::Decrypt);
+        fs::write(out_path, decrypted.map_err(cipher_error)?).map_err(|_| RFEC_ERR_WRITE)
+    })
+}
+
+// This function runs the body of one of the C functions, turning its result into a return code, and a panic
+// into RFEC_ERR_PANIC, since unwinding into C is undefined behavior.
+fn catch_panics(body: impl FnOnce() -> Result<(), c_int> + panic::UnwindSafe) -> c_int {
+    match panic::catch_unwind(body) {
+        Ok(Ok(())) => RFEC_OK,
+        Ok(Err(code)) => code,
+        Err(_) => RFEC_ERR_PANIC,
+    }
+}
+
+// This function reads a string given by C that has to be there.
+unsafe fn required_str<'a>(ptr: *const c_char) -> Result<&'a str, c_int> {
+    optional_str(ptr)?.ok_or(RFEC_ERR_NULL_POINTER)
+}
+
+// This function reads a string given by C that can be NULL.
+unsafe fn optional_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>, c_int> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: the caller promises a non-NULL pointer is to a NUL-terminated string that outlives the call.
+    let text = unsafe { CStr::from_ptr(ptr) };
+    text.to_str().map(Some).map_err(|_| RFEC_ERR_INVALID_UTF8)
+}
+
+// This function makes the cipher with an ID, with its key read from the passphrase.
+fn cipher_for(cipher_id: u8, passphrase: Option<&str>) -> Result<Box<dyn Cipher>, c_int> {
+    let key = || passphrase.ok_or(RFEC_ERR_NULL_POINTER);
+    Ok(match cipher_id {
+        RFEC_CIPHER_ROT13 => Box::new(Rot13),
+        RFEC_CIPHER_AFFINE => {
+            let (a, b) = key()?.split_once(',').ok_or(RFEC_ERR_INVALID_KEY)?;
+            let number = |text: &str| text.trim().parse().map_err(|_| RFEC_ERR_INVALID_KEY);
+            Box::new(Affine { a: number(a)?, b: number(b)? })
+        }
+        RFEC_CIPHER_RAIL_FENCE => {
+            Box::new(RailFence { rails: key()?.trim().parse().map_err(|_| RFEC_ERR_INVALID_KEY)? })
+        }
+        RFEC_CIPHER_COLUMNAR => Box::new(Columnar { key: Zeroizing::new(key()?.to_string()) }),
+        RFEC_CIPHER_SUBSTITUTION => {
+            Box::new(Substitution { mapping: Zeroizing::new(parse_alphabet(key()?).map_err(cipher_error)?) })
+        }
+        RFEC_CIPHER_BASE32 => Box::new(Base32),
+        RFEC_CIPHER_ROT13_5 => Box::new(Rot13Rot5),
+        RFEC_CIPHER_ROT13_UNICODE => Box::new(Rot13Unicode),
+        RFEC_CIPHER_CAESAR => {
+            let (shift, name) = key()?.split_once(',').unwrap_or((key()?, "latin"));
+            let shift = shift.trim().parse().map_err(|_| RFEC_ERR_INVALID_KEY)?;
+            Box::new(Caesar { shift, alphabet: named_alphabet(name.trim()).ok_or(RFEC_ERR_INVALID_KEY)? })
+        }
+        _ => return Err(RFEC_ERR_UNKNOWN_CIPHER),
+    })
+}
+
+// This function picks the return code for a cipher's error.
+fn cipher_error(e: CipherError) -> c_int {
+    match e {
+        CipherError::InvalidKey(_) => RFEC_ERR_INVALID_KEY,
+        CipherError::InvalidInput(_) | CipherError::UnsupportedVersion(_) => RFEC_ERR_CIPHER,
+    }
+}
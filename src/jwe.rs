@@ -0,0 +1,160 @@
+//! JSON Web Encryption (JWE) compact serialization, as described in RFC 7516, so encrypted output can be
+//! passed to other JWT/JWE tools.
+//!
+//! A compact token is five base64url parts separated by dots: the protected header, the encrypted key, the
+//! initialization vector, the ciphertext, and the authentication tag. This module only packs and unpacks
+//! those parts; producing the ciphertext and tag is up to the cipher.
+//!
+//! ```
+//! use rust_project::jwe::{deserialize_jwe_compact, serialize_jwe_compact, JweHeader};
+//!
+//! let header = JweHeader::direct_a256gcm();
+//! let token = serialize_jwe_compact(&header, b"ciphertext", &[7; 16], &[1; 12], &[]);
+//! assert_eq!(token.split('.').count(), 5);
+//!
+//! let decoded = deserialize_jwe_compact(&token).unwrap();
+//! assert_eq!(decoded.header, header);
+//! assert_eq!(decoded.ciphertext, b"ciphertext");
+//! assert_eq!(decoded.tag, [7; 16]);
+//! assert_eq!(decoded.iv, [1; 12]);
+//! assert!(decoded.encrypted_key.is_empty());
+//! ```
+//!
+//! [`encrypt_direct`] and [`decrypt_direct`] do the whole job for "dir" and "A256GCM": the content is encrypted
+//! with [`Aes256Gcm`] using a 256-bit key both sides already have.
+//!
+//! ```
+//! use rust_project::jwe::{decrypt_direct, encrypt_direct};
+//! use rust_project::SecretKey;
+//!
+//! let key = SecretKey::from_slice(&[42; 32]).unwrap();
+//! let token = encrypt_direct(&key, &[1; 12], b"attack at dawn");
+//! assert!(token.starts_with("eyJhbGciOiJkaXIiLCJlbmMiOiJBMjU2R0NNIn0.."));
+//! assert_eq!(decrypt_direct(&token, &key).unwrap(), b"attack at dawn");
+//! ```
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::{json, Value};
+use crate::{Aes256Gcm, Cipher, CipherError, SecretKey};
+
+/// The protected header of a JWE token, naming how the key was agreed on and how the content was encrypted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JweHeader {
+    /// The key management algorithm, like "dir" for a key both sides already share.
+    pub alg: String,
+    /// The content encryption algorithm, like "A256GCM" for AES-GCM with a 256-bit key.
+    pub enc: String,
+}
+
+impl JweHeader {
+    /// The header for content encrypted directly with a shared 256-bit AES-GCM key.
+    pub fn direct_a256gcm() -> JweHeader {
+        JweHeader { alg: String::from("dir"), enc: String::from("A256GCM") }
+    }
+
+    /// The header as it's written in a token: JSON, in base64url. AES-GCM authenticates this text as its
+    /// additional data.
+    pub fn protected(&self) -> String {
+        URL_SAFE_NO_PAD.encode(json!({ "alg": self.alg, "enc": self.enc }).to_string())
+    }
+}
+
+/// The five parts of a JWE token in compact serialization, decoded from base64url.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JweCompact {
+    /// The protected header.
+    pub header: JweHeader,
+    /// The protected header exactly as it appeared in the token. AES-GCM authenticates this text as its
+    /// additional data, so it has to be kept as it was rather than re-encoded from `header`.
+    pub protected: String,
+    /// The content encryption key, encrypted for the recipient. This is empty with "dir".
+    pub encrypted_key: Vec<u8>,
+    /// The initialization vector.
+    pub iv: Vec<u8>,
+    /// The encrypted content.
+    pub ciphertext: Vec<u8>,
+    /// The authentication tag.
+    pub tag: Vec<u8>,
+}
+
+/// Packs the parts of an encrypted message into a JWE compact token.
+pub fn serialize_jwe_compact(
+    header: &JweHeader,
+    ciphertext: &[u8],
+    tag: &[u8],
+    iv: &[u8],
+    encrypted_key: &[u8],
+) -> String {
+    let parts = [encrypted_key, iv, ciphertext, tag].map(|part| URL_SAFE_NO_PAD.encode(part));
+    format!("{}.{}", header.protected(), parts.join("."))
+}
+
+/// Encrypts some data into a JWE compact token with "dir" and "A256GCM": AES-256-GCM with the key itself, and
+/// the 12-byte initialization vector given. An IV must never be used twice with the same key; see
+/// [`nonce`](crate::nonce).
+pub fn encrypt_direct(key: &SecretKey, iv: &[u8; 12], plaintext: &[u8]) -> String {
+    let header = JweHeader::direct_a256gcm();
+    let mut sealed = Aes256Gcm
+        .encrypt(key.as_bytes(), iv, plaintext, header.protected().as_bytes())
+        .expect("the key and IV are the right lengths");
+    let tag = sealed.split_off(sealed.len() - Aes256Gcm::TAG_LEN);
+    serialize_jwe_compact(&header, &sealed, &tag, iv, &[])
+}
+
+/// Decrypts a JWE compact token made by [`encrypt_direct`], or anything else using "dir" and "A256GCM".
+///
+/// A token using other algorithms is rejected with [`CipherError::InvalidInput`], and so is one whose tag
+/// doesn't match, because the key is wrong or the token has been changed.
+pub fn decrypt_direct(token: &str, key: &SecretKey) -> Result<Vec<u8>, CipherError> {
+    let compact = deserialize_jwe_compact(token)?;
+    if compact.header != JweHeader::direct_a256gcm() {
+        return Err(CipherError::InvalidInput(format!(
+            "the JWE token uses \"{}\" and \"{}\", but only \"dir\" and \"A256GCM\" are supported",
+            compact.header.alg, compact.header.enc
+        )));
+    }
+    if !compact.encrypted_key.is_empty() {
+        return Err(CipherError::InvalidInput(String::from("a \"dir\" JWE token can't have an encrypted key")));
+    }
+    let mut sealed = compact.ciphertext;
+    sealed.extend(compact.tag);
+    Aes256Gcm.decrypt(key.as_bytes(), &compact.iv, &sealed, compact.protected.as_bytes())
+}
+
+/// Unpacks a JWE compact token into its parts.
+///
+/// A token without exactly five parts, with a part that isn't base64url, or with a header that isn't a JSON
+/// object naming its "alg" and "enc" is rejected with [`CipherError::InvalidInput`].
+pub fn deserialize_jwe_compact(token: &str) -> Result<JweCompact, CipherError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let [protected, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+        return Err(CipherError::InvalidInput(format!(
+            "a JWE token has 5 parts separated by dots, but this one has {}",
+            parts.len()
+        )));
+    };
+    let decode = |name: &str, part: &str| {
+        URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|e| CipherError::InvalidInput(format!("the JWE {name} isn't valid base64url ({e})")))
+    };
+
+    let header: Value = serde_json::from_slice(&decode("header", protected)?)
+        .map_err(|e| CipherError::InvalidInput(format!("the JWE header isn't valid JSON ({e})")))?;
+    let field = |name: &str| {
+        header[name]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| CipherError::InvalidInput(format!("the JWE header has no \"{name}\"")))
+    };
+
+    Ok(JweCompact {
+        header: JweHeader { alg: field("alg")?, enc: field("enc")? },
+        protected: protected.to_string(),
+        encrypted_key: decode("encrypted key", encrypted_key)?,
+        iv: decode("initialization vector", iv)?,
+        ciphertext: decode("ciphertext", ciphertext)?,
+        tag: decode("authentication tag", tag)?,
+    })
+}
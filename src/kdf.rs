@@ -0,0 +1,385 @@
+//! Key derivation: turning a password (or one key) into the keys for the ciphers that need them.
+//!
+//! Passwords are short and easy to guess, so they're run through a deliberately slow and memory-hungry
+//! function first. That makes every guess an attacker tries cost as much as it does for the real user.
+//!
+//! scrypt is supported with the same parameters as OpenSSL's `enc -scrypt` and Node.js's
+//! `crypto.scryptSync`, so keys derived here match keys derived there. These are the test vectors from
+//! RFC 7914, section 12 (the first 32 bytes of each, since this always derives a 32-byte key):
+//!
+//! ```
+//! use rust_project::kdf::derive_key_scrypt;
+//!
+//! let hex = |key: &[u8]| key.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+//!
+//! let key = derive_key_scrypt(b"", b"", 16, 1, 1).unwrap();
+//! assert_eq!(hex(key.as_bytes()), "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442");
+//!
+//! let key = derive_key_scrypt(b"password", b"NaCl", 1024, 8, 16).unwrap();
+//! assert_eq!(hex(key.as_bytes()), "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162");
+//!
+//! let key = derive_key_scrypt(b"pleaseletmein", b"SodiumChloride", 16384, 8, 1).unwrap();
+//! assert_eq!(hex(key.as_bytes()), "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2");
+//! ```
+
+use argon2::Argon2;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use crate::{CipherError, SecretKey};
+
+/// The scrypt cost settings for keys typed in by someone waiting on them, as suggested by the scrypt paper.
+pub const SCRYPT_INTERACTIVE: (u64, u32, u32) = (1 << 14, 8, 1);
+
+/// The scrypt cost settings for protecting something sensitive, where taking a few seconds is fine.
+pub const SCRYPT_SENSITIVE: (u64, u32, u32) = (1 << 20, 8, 1);
+
+/// The Argon2id settings RFC 9106 recommends for when 2 GiB of memory is too much: 64 MiB, 3 passes, 4 lanes.
+pub const ARGON2ID_DEFAULT: Argon2idKdf = Argon2idKdf { memory_kib: 64 * 1024, iterations: 3, parallelism: 4 };
+
+/// The PBKDF2-HMAC-SHA256 iteration count OWASP recommends.
+pub const PBKDF2_DEFAULT: Pbkdf2Kdf = Pbkdf2Kdf { iterations: 600_000 };
+
+/// The most Argon2id settings read back from stored data may ask for: those of the paranoid profile.
+///
+/// Stored settings can't be trusted until the data they came with has been authenticated, which takes the key
+/// they derive. Without a limit, a doctored file could make deriving that key take hours or more memory than
+/// there is. The same goes for [`PBKDF2_MAX_ITERATIONS`] and [`SCRYPT_MAX_COST`].
+pub const ARGON2ID_MAX: Argon2idKdf = Argon2idKdf { memory_kib: 256 * 1024, iterations: 4, parallelism: 4 };
+
+/// The most PBKDF2 iterations stored data may ask for, about ten times [`PBKDF2_DEFAULT`].
+pub const PBKDF2_MAX_ITERATIONS: u32 = 6_000_000;
+
+/// The most scrypt work stored data may ask for, as N × r × p: that of [`SCRYPT_SENSITIVE`].
+pub const SCRYPT_MAX_COST: u64 = SCRYPT_SENSITIVE.0 * SCRYPT_SENSITIVE.1 as u64 * SCRYPT_SENSITIVE.2 as u64;
+
+/// Derives a 32-byte key from a password and salt with scrypt.
+///
+/// `n` is the CPU/memory cost, and must be a power of two greater than 1. `r` is the block size and `p` is how
+/// many times the work is done in parallel. Settings scrypt can't use are rejected with
+/// [`CipherError::InvalidKey`].
+pub fn derive_key_scrypt(
+    password: &[u8],
+    salt: &[u8],
+    n: u64,
+    r: u32,
+    p: u32,
+) -> Result<SecretKey, CipherError> {
+    if n < 2 || !n.is_power_of_two() {
+        return Err(CipherError::InvalidKey(format!("the scrypt cost N must be a power of two above 1, not {n}")));
+    }
+    let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, 32)
+        .map_err(|e| CipherError::InvalidKey(format!("the scrypt settings can't be used ({e})")))?;
+    let mut key = SecretKey::zeroed();
+    scrypt::scrypt(password, salt, &params, key.as_mut_bytes())
+        .expect("32 bytes is a valid scrypt output length");
+    Ok(key)
+}
+
+/// Derives a 32-byte sub-key from a master key with HKDF-SHA256 (RFC 5869).
+///
+/// Two things that use keys, like a cipher and a MAC, should never share one, or a weakness in one can give
+/// away the other. Instead, each gets its own sub-key, derived from the master key with a different `info`
+/// (like `b"cipher"` or `b"mac"`). The master key itself is never used to encrypt or authenticate anything.
+///
+/// HKDF first mixes the master key and `salt` into a pseudorandom key ("extract"), then expands that with
+/// `info` into the sub-key ("expand"). Sub-keys with different `info` can't be worked out from each other,
+/// even knowing one of them. The salt can be empty, but a random one (or the public values of a key
+/// exchange) makes the sub-keys stronger.
+///
+/// ```
+/// use rust_project::kdf::derive_subkeys;
+///
+/// let master = [7; 32];
+/// let cipher_key = derive_subkeys(&master, b"salt", b"cipher");
+/// let mac_key = derive_subkeys(&master, b"salt", b"mac");
+/// assert_ne!(cipher_key.as_bytes(), mac_key.as_bytes());
+/// assert_ne!(cipher_key.as_bytes(), &master);
+/// assert_eq!(cipher_key.as_bytes(), derive_subkeys(&master, b"salt", b"cipher").as_bytes());
+/// ```
+pub fn derive_subkeys(master: &[u8; 32], salt: &[u8], info: &[u8]) -> SecretKey {
+    let mut key = SecretKey::zeroed();
+    Hkdf::<Sha256>::new(Some(salt), master)
+        .expand(info, key.as_mut_bytes())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// A way of turning a password into a key, so they can be swapped for one another like [`Cipher`]s.
+///
+/// Each one knows its cost settings, and can write them as bytes (to store next to the encrypted data) and read
+/// them back, so a key can be derived again later with exactly the same settings.
+///
+/// ```
+/// use rust_project::kdf::{kdf_from_bytes, Argon2idKdf, KdfAlgorithm, Pbkdf2Kdf, ScryptKdf};
+///
+/// let kdfs: Vec<Box<dyn KdfAlgorithm>> = vec![
+///     Box::new(Argon2idKdf { memory_kib: 64, iterations: 1, parallelism: 1 }),
+///     Box::new(Pbkdf2Kdf { iterations: 1000 }),
+///     Box::new(ScryptKdf { log_n: 4, r: 1, p: 1 }),
+/// ];
+/// for kdf in kdfs {
+///     let mut key = [0; 32];
+///     kdf.derive(b"correct horse", b"some salt", &mut key).unwrap();
+///
+///     // Saving the settings and reading them back gives the same key.
+///     let restored = kdf_from_bytes(kdf.name(), &kdf.params_to_bytes()).unwrap();
+///     let mut again = [0; 32];
+///     restored.derive(b"correct horse", b"some salt", &mut again).unwrap();
+///     assert_eq!(key, again, "{}", kdf.name());
+/// }
+/// ```
+///
+/// [`Cipher`]: crate::Cipher
+pub trait KdfAlgorithm: Send + Sync {
+    /// The KDF's name, like "scrypt".
+    fn name(&self) -> &'static str;
+
+    /// Derives a key from a password and salt, filling the whole of `output`.
+    fn derive(&self, password: &[u8], salt: &[u8], output: &mut [u8]) -> Result<(), CipherError>;
+
+    /// The KDF's cost settings as bytes, to be read back with [`KdfAlgorithm::params_from_bytes`].
+    fn params_to_bytes(&self) -> Vec<u8>;
+
+    /// Reads cost settings written by [`KdfAlgorithm::params_to_bytes`]. Settings that are cut short, or ask for
+    /// more work than [`ARGON2ID_MAX`] and the like allow, are rejected with [`CipherError::InvalidInput`].
+    fn params_from_bytes(data: &[u8]) -> Result<Box<dyn KdfAlgorithm>, CipherError>
+    where
+        Self: Sized;
+}
+
+/// Reads the cost settings for the KDF with the given name, so data can say which KDF it was made with
+/// (and how) without knowing about each one.
+pub fn kdf_from_bytes(name: &str, data: &[u8]) -> Result<Box<dyn KdfAlgorithm>, CipherError> {
+    match name {
+        "argon2id" => Argon2idKdf::params_from_bytes(data),
+        "pbkdf2" => Pbkdf2Kdf::params_from_bytes(data),
+        "scrypt" => ScryptKdf::params_from_bytes(data),
+        _ => Err(CipherError::InvalidInput(format!("unknown KDF {name}"))),
+    }
+}
+
+/// The ID each KDF is saved as in a [`FileHeader`](crate::FileHeader), by name. These must never change, or keys
+/// for older data will be derived with the wrong KDF.
+///
+/// ```
+/// use rust_project::kdf::{kdf_id, kdf_name};
+///
+/// assert_eq!(kdf_id("scrypt"), Some(3));
+/// assert_eq!(kdf_name(3), Some("scrypt"));
+/// assert_eq!(kdf_name(0), None);
+/// ```
+pub const KDF_IDS: [(&str, u8); 3] = [("argon2id", 1), ("pbkdf2", 2), ("scrypt", 3)];
+
+/// Gives the ID a KDF is saved as, from its name.
+pub fn kdf_id(name: &str) -> Option<u8> {
+    KDF_IDS.iter().find(|(kdf, _)| *kdf == name).map(|(_, id)| *id)
+}
+
+/// Gives the name of the KDF saved as an ID.
+pub fn kdf_name(id: u8) -> Option<&'static str> {
+    KDF_IDS.iter().find(|(_, kdf)| *kdf == id).map(|(name, _)| *name)
+}
+
+/// One of the KDFs along with its settings. Unlike a `Box<dyn KdfAlgorithm>`, it can be copied, compared, and
+/// written as a constant, like the ones in [`CipherProfile`](crate::CipherProfile).
+///
+/// ```
+/// use rust_project::kdf::{kdf_id, KdfParams, ScryptKdf};
+///
+/// let kdf = KdfParams::Scrypt(ScryptKdf { log_n: 14, r: 8, p: 1 });
+/// assert_eq!(kdf.algorithm().name(), "scrypt");
+/// assert_eq!(kdf.id(), kdf_id("scrypt").unwrap());
+/// assert_eq!(kdf.algorithm().params_to_bytes(), [14, 0, 0, 0, 8, 0, 0, 0, 1]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KdfParams {
+    /// Argon2id, with its settings.
+    Argon2id(Argon2idKdf),
+    /// PBKDF2-HMAC-SHA256, with its settings.
+    Pbkdf2(Pbkdf2Kdf),
+    /// scrypt, with its settings.
+    Scrypt(ScryptKdf),
+}
+
+impl KdfParams {
+    /// The KDF itself, to derive keys with.
+    pub fn algorithm(&self) -> &dyn KdfAlgorithm {
+        match self {
+            KdfParams::Argon2id(kdf) => kdf,
+            KdfParams::Pbkdf2(kdf) => kdf,
+            KdfParams::Scrypt(kdf) => kdf,
+        }
+    }
+
+    /// The ID the KDF is saved as in a [`FileHeader`](crate::FileHeader).
+    pub fn id(&self) -> u8 {
+        kdf_id(self.algorithm().name()).expect("every KDF has an ID")
+    }
+}
+
+/// Argon2id (RFC 9106), the winner of the Password Hashing Competition and the best choice for new data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Argon2idKdf {
+    /// How much memory each derivation uses, in kibibytes.
+    pub memory_kib: u32,
+    /// How many passes are made over that memory.
+    pub iterations: u32,
+    /// How many lanes the memory is split into, which can be worked on at the same time.
+    pub parallelism: u32,
+}
+
+impl KdfAlgorithm for Argon2idKdf {
+    fn name(&self) -> &'static str {
+        "argon2id"
+    }
+
+    fn derive(&self, password: &[u8], salt: &[u8], output: &mut [u8]) -> Result<(), CipherError> {
+        let params = argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, Some(output.len()))
+            .map_err(|e| CipherError::InvalidKey(format!("the Argon2id settings can't be used ({e})")))?;
+        Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(password, salt, output)
+            .map_err(|e| CipherError::InvalidKey(format!("Argon2id couldn't derive a key ({e})")))
+    }
+
+    fn params_to_bytes(&self) -> Vec<u8> {
+        [self.memory_kib, self.iterations, self.parallelism].iter().flat_map(|value| value.to_be_bytes()).collect()
+    }
+
+    fn params_from_bytes(data: &[u8]) -> Result<Box<dyn KdfAlgorithm>, CipherError> {
+        let [memory_kib, iterations, parallelism] = read_u32s(data, "argon2id")?;
+        if memory_kib > ARGON2ID_MAX.memory_kib
+            || iterations > ARGON2ID_MAX.iterations
+            || parallelism > ARGON2ID_MAX.parallelism
+        {
+            return Err(too_costly("argon2id"));
+        }
+        Ok(Box::new(Argon2idKdf { memory_kib, iterations, parallelism }))
+    }
+}
+
+/// PBKDF2 with HMAC-SHA256 (RFC 8018), for working with older tools. It needs no extra memory, so it's much
+/// cheaper to attack with special hardware than Argon2id or scrypt.
+///
+/// ```
+/// use rust_project::kdf::{KdfAlgorithm, Pbkdf2Kdf};
+///
+/// let mut key = [0; 32];
+/// Pbkdf2Kdf { iterations: 4096 }.derive(b"password", b"salt", &mut key).unwrap();
+/// let hex: String = key.iter().map(|byte| format!("{byte:02x}")).collect();
+/// assert_eq!(hex, "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pbkdf2Kdf {
+    /// How many times HMAC-SHA256 is applied.
+    pub iterations: u32,
+}
+
+impl KdfAlgorithm for Pbkdf2Kdf {
+    fn name(&self) -> &'static str {
+        "pbkdf2"
+    }
+
+    fn derive(&self, password: &[u8], salt: &[u8], output: &mut [u8]) -> Result<(), CipherError> {
+        if self.iterations == 0 {
+            return Err(CipherError::InvalidKey(String::from("PBKDF2 needs at least one iteration")));
+        }
+        pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, self.iterations, output);
+        Ok(())
+    }
+
+    fn params_to_bytes(&self) -> Vec<u8> {
+        self.iterations.to_be_bytes().to_vec()
+    }
+
+    fn params_from_bytes(data: &[u8]) -> Result<Box<dyn KdfAlgorithm>, CipherError> {
+        let [iterations] = read_u32s(data, "pbkdf2")?;
+        if iterations > PBKDF2_MAX_ITERATIONS {
+            return Err(too_costly("pbkdf2"));
+        }
+        Ok(Box::new(Pbkdf2Kdf { iterations }))
+    }
+}
+
+/// scrypt (RFC 7914), with the same settings as [`derive_key_scrypt`] except that N is given as a power of two.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScryptKdf {
+    /// The CPU/memory cost N, as a power of two: N = 2^log_n.
+    pub log_n: u8,
+    /// The block size.
+    pub r: u32,
+    /// How many times the work is done in parallel.
+    pub p: u32,
+}
+
+impl KdfAlgorithm for ScryptKdf {
+    fn name(&self) -> &'static str {
+        "scrypt"
+    }
+
+    fn derive(&self, password: &[u8], salt: &[u8], output: &mut [u8]) -> Result<(), CipherError> {
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, output.len())
+            .map_err(|e| CipherError::InvalidKey(format!("the scrypt settings can't be used ({e})")))?;
+        scrypt::scrypt(password, salt, &params, output)
+            .map_err(|e| CipherError::InvalidKey(format!("scrypt couldn't derive a key ({e})")))
+    }
+
+    fn params_to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.log_n];
+        bytes.extend(self.r.to_be_bytes());
+        bytes.extend(self.p.to_be_bytes());
+        bytes
+    }
+
+    fn params_from_bytes(data: &[u8]) -> Result<Box<dyn KdfAlgorithm>, CipherError> {
+        let (&log_n, rest) = data.split_first().ok_or_else(|| cut_short("scrypt"))?;
+        let [r, p] = read_u32s(rest, "scrypt")?;
+        let kdf = ScryptKdf { log_n, r, p };
+        kdf.check_cost()?;
+        Ok(Box::new(kdf))
+    }
+}
+
+impl ScryptKdf {
+    /// Checks the settings don't ask for more work than [`SCRYPT_MAX_COST`], so data saved with them can be
+    /// read back. Settings that do are rejected with [`CipherError::InvalidInput`].
+    ///
+    /// ```
+    /// use rust_project::kdf::ScryptKdf;
+    ///
+    /// assert!(ScryptKdf { log_n: 20, r: 8, p: 1 }.check_cost().is_ok());
+    /// assert!(ScryptKdf { log_n: 20, r: 8, p: 2 }.check_cost().is_err());
+    /// assert!(ScryptKdf { log_n: 200, r: 1, p: 1 }.check_cost().is_err());
+    /// ```
+    pub fn check_cost(&self) -> Result<(), CipherError> {
+        let cost = 1u64
+            .checked_shl(self.log_n.into())
+            .and_then(|n| n.checked_mul(self.r.into()))
+            .and_then(|nr| nr.checked_mul(self.p.into()));
+        match cost {
+            Some(cost) if cost <= SCRYPT_MAX_COST => Ok(()),
+            _ => Err(too_costly("scrypt")),
+        }
+    }
+}
+
+// This function reads exactly N big-endian 32-bit numbers, which is how the KDFs store their settings.
+fn read_u32s<const N: usize>(data: &[u8], kdf: &str) -> Result<[u32; N], CipherError> {
+    if data.len() != N * 4 {
+        return Err(cut_short(kdf));
+    }
+    let mut values = [0; N];
+    for (value, bytes) in values.iter_mut().zip(data.chunks_exact(4)) {
+        *value = u32::from_be_bytes(bytes.try_into().expect("chunks of 4 bytes"));
+    }
+    Ok(values)
+}
+
+// This function makes the error for KDF settings that are the wrong length.
+fn cut_short(kdf: &str) -> CipherError {
+    CipherError::InvalidInput(format!("the {kdf} settings are the wrong length"))
+}
+
+// This function makes the error for stored KDF settings that ask for more work than is allowed.
+fn too_costly(kdf: &str) -> CipherError {
+    CipherError::InvalidInput(format!("the {kdf} settings ask for more work than is allowed"))
+}
@@ -0,0 +1,1875 @@
+//! The ciphers behind the command-line tool, free of any file or terminal I/O so they can be used
+//! from other Rust programs too.
+//!
+//! ```
+//! use rust_project::{rot13, rotate_by};
+//!
+//! assert_eq!(rot13("Hello, World!"), "Uryyb, Jbeyq!");
+//! assert_eq!(rotate_by("Hello, World!", 3), "Khoor, Zruog!");
+//! assert_eq!(rotate_by("Khoor, Zruog!", -3), "Hello, World!");
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::{LazyLock, PoisonError, RwLock, RwLockReadGuard};
+use aes_gcm::Aes256Gcm as Aes256GcmAead;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::ChaCha20Poly1305 as ChaCha20Poly1305Aead;
+use hmac::{Hmac, Mac};
+use crate::kdf::{Argon2idKdf, KdfParams, ScryptKdf, ARGON2ID_DEFAULT};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// Keys are held in this wrapper, which overwrites them with zeros when they're dropped so they don't linger in
+/// memory (or get swapped to disk) after they're no longer needed.
+pub use zeroize::Zeroizing;
+
+pub mod jwe;
+pub mod kdf;
+pub mod nonce;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm_api;
+
+/// The ways a cipher can refuse to encrypt/decrypt.
+#[derive(Debug, PartialEq)]
+pub enum CipherError {
+    /// The key (or another setting) given to the cipher can't be used, along with the reason why.
+    InvalidKey(String),
+    /// The text given to the cipher can't be encrypted/decrypted, along with the reason why.
+    InvalidInput(String),
+    /// The data starts with a [`FileHeader`] from a version of the format this version doesn't know.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CipherError::InvalidKey(reason) => write!(f, "invalid key: {reason}"),
+            CipherError::InvalidInput(reason) => write!(f, "invalid input: {reason}"),
+            CipherError::UnsupportedVersion(version) => write!(f, "unsupported format version {version}"),
+        }
+    }
+}
+
+impl Error for CipherError {}
+
+/// A 256-bit key, like one derived from a password or a sub-key derived from that.
+///
+/// The key is held in [`Zeroizing`], so it's overwritten with zeros when it's dropped, and it's never printed:
+/// its `Debug` output leaves the key out, so it can't end up in a log by accident.
+///
+/// ```
+/// use rust_project::SecretKey;
+///
+/// let mut key = SecretKey::zeroed();
+/// key.as_mut_bytes()[0] = 7;
+/// assert_eq!(key.as_bytes()[0], 7);
+/// assert_eq!(format!("{key:?}"), "SecretKey(..)");
+///
+/// assert!(SecretKey::from_slice(&[1; 32]).is_some());
+/// assert!(SecretKey::from_slice(&[1; 16]).is_none());
+/// ```
+#[derive(Clone)]
+pub struct SecretKey(Zeroizing<[u8; 32]>);
+
+impl SecretKey {
+    /// The size of the key in bytes.
+    pub const LEN: usize = 32;
+
+    /// Makes a key of all zeros, to be filled in with [`SecretKey::as_mut_bytes`].
+    pub fn zeroed() -> SecretKey {
+        SecretKey(Zeroizing::new([0; SecretKey::LEN]))
+    }
+
+    /// Copies a key out of some bytes, which must be exactly [`SecretKey::LEN`] long.
+    pub fn from_slice(bytes: &[u8]) -> Option<SecretKey> {
+        if bytes.len() != SecretKey::LEN {
+            return None;
+        }
+        let mut key = SecretKey::zeroed();
+        key.as_mut_bytes().copy_from_slice(bytes);
+        Some(key)
+    }
+
+    /// The bytes of the key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The bytes of the key, to be written to.
+    pub fn as_mut_bytes(&mut self) -> &mut [u8; 32] {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretKey {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+/// What each byte becomes under ROT13: letters are rotated, and every other byte stays as it is.
+///
+/// Bytes from 128 up (the parts of multi-byte UTF-8 characters) are never letters, so they pass through
+/// unchanged and the result is still valid UTF-8.
+const ROT13_TABLE: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let c = byte as u8;
+        table[byte] = match c {
+            b'A'..=b'M' | b'a'..=b'm' => c + 13,
+            b'N'..=b'Z' | b'n'..=b'z' => c - 13,
+            _ => c,
+        };
+        byte += 1;
+    }
+    table
+};
+
+/// What each byte becomes under ROT13 with ROT5: letters are rotated by 13, digits by 5, and every other byte
+/// stays as it is.
+const ROT13_5_TABLE: [u8; 256] = {
+    let mut table = ROT13_TABLE;
+    let mut digit = b'0';
+    while digit <= b'9' {
+        table[digit as usize] = if digit <= b'4' { digit + 5 } else { digit - 5 };
+        digit += 1;
+    }
+    table
+};
+
+/// Encrypts/decrypts a string via ROT13, ignoring non-alphabetical characters.
+///
+/// ROT13 is its own inverse, so the same function both encrypts and decrypts.
+///
+/// Every printable ASCII character comes out as it would by rotating letters one at a time:
+///
+/// ```
+/// use rust_project::rot13;
+///
+/// for c in ' '..='~' {
+///     let expected = match c {
+///         'A'..='M' | 'a'..='m' => (c as u8 + 13) as char,
+///         'N'..='Z' | 'n'..='z' => (c as u8 - 13) as char,
+///         _ => c,
+///     };
+///     assert_eq!(rot13(&c.to_string()), expected.to_string());
+/// }
+/// assert_eq!(rot13("Grüße, Zoë"), "Teüßr, Mbë");
+/// ```
+pub fn rot13(text: &str) -> String {
+    // Each byte is looked up in the table rather than worked out, which is the fastest way to do it.
+    let rotated = text.bytes().map(|byte| ROT13_TABLE[byte as usize]).collect();
+    String::from_utf8(rotated).expect("only ASCII letters are changed, so the text is still UTF-8")
+}
+
+/// Encrypts/decrypts a string via ROT13 for letters and ROT5 for digits, so numbers are hidden too. Letters are
+/// rotated 13 places through the alphabet and digits 5 places through 0 to 9, each wrapping around on its own,
+/// so a letter never turns into a digit or the other way around. Everything else is left as it is.
+///
+/// Like [`rot13`], it's its own inverse, so the same function both encrypts and decrypts.
+///
+/// ```
+/// use rust_project::rot13_5;
+///
+/// assert_eq!(rot13_5("abc123"), "nop678");
+/// assert_eq!(rot13_5("nop678"), "abc123");
+/// assert_eq!(rot13_5("Call 555-0199!"), "Pnyy 000-5644!");
+/// ```
+pub fn rot13_5(text: &str) -> String {
+    let rotated = text.bytes().map(|byte| ROT13_5_TABLE[byte as usize]).collect();
+    String::from_utf8(rotated).expect("only ASCII letters and digits are changed, so the text is still UTF-8")
+}
+
+/// The alphabets [`rot13_unicode`] rotates letters within, each one in order. Upper and lower case are separate
+/// alphabets, so a letter keeps its case.
+const UNICODE_ALPHABETS: [&str; 7] = [
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "abcdefghijklmnopqrstuvwxyz",
+    "ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩ",
+    "αβγδεζηθικλμνξοπρστυφχψω",
+    "АБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯ",
+    "абвгдежзийклмнопрстуфхцчшщъыьэюя",
+    // Hebrew has no case, and its five final forms are counted as letters of their own.
+    "אבגדהוזחטיךכלםמןנסעףפץצקרשת",
+];
+
+/// Encrypts/decrypts a string via ROT13 extended to other alphabets: every letter is rotated halfway through
+/// its own alphabet. That's 13 places for the Latin alphabet (so ASCII text comes out just like [`rot13`]), 12
+/// for Greek, 16 for the Russian Cyrillic alphabet А to Я, and 13 for Hebrew.
+///
+/// An alphabet with an odd number of letters can't be split into two equal halves, so its last letter is left as
+/// it is and the others are rotated halfway through the rest. Of the alphabets here, only Hebrew (27 letters
+/// with the final forms) is like that, so ת stays ת. This keeps the function its own inverse for every alphabet.
+///
+/// Anything that isn't one of those letters is left alone: digits, punctuation, combining marks, letters from
+/// other scripts, and letters outside the basic alphabets (like Ё, final sigma ς, or a precomposed é). A letter
+/// followed by a combining accent is still rotated, and the accent stays with the new letter.
+///
+/// ```
+/// use rust_project::rot13_unicode;
+///
+/// assert_eq!(rot13_unicode("Привет, мир!"), "Яаштхв, ьша!");
+/// assert_eq!(rot13_unicode("Яаштхв, ьша!"), "Привет, мир!");
+/// assert_eq!(rot13_unicode("Γεια σου"), "Ορφν ζγθ");
+/// assert_eq!(rot13_unicode("Hello, World!"), "Uryyb, Jbeyq!");
+/// ```
+pub fn rot13_unicode(text: &str) -> String {
+    text.chars().map(rotate_within_alphabet).collect()
+}
+
+// This function rotates a letter halfway through the alphabet it's in from UNICODE_ALPHABETS, leaving the last
+// letter of an odd-sized alphabet where it is. Anything else is given back unchanged.
+fn rotate_within_alphabet(c: char) -> char {
+    if c.is_ascii() {
+        return ROT13_TABLE[c as usize] as char;
+    }
+    for alphabet in UNICODE_ALPHABETS {
+        let letters: Vec<char> = alphabet.chars().collect();
+        if let Some(position) = letters.iter().position(|&letter| letter == c) {
+            // The letters that are swapped with each other: all of them, or all but the last for an odd number.
+            let paired = letters.len() - letters.len() % 2;
+            if position >= paired {
+                return c;
+            }
+            return letters[(position + paired / 2) % paired];
+        }
+    }
+    c
+}
+
+/// Rotates every ASCII letter in a string forward through the alphabet by `shift` places (a Caesar cipher),
+/// ignoring non-alphabetical characters.
+///
+/// Letters wrap around from "z" back to "a" and keep their case. A negative shift rotates backwards, so
+/// `rotate_by(text, -shift)` undoes `rotate_by(text, shift)`, and a shift of 13 is the same as [`rot13`].
+pub fn rotate_by(text: &str, shift: i32) -> String {
+    // Any shift is the same as one between 0 and 25, since rotating by 26 goes all the way around.
+    let shift = shift.rem_euclid(26) as u8;
+    text.chars().map(|c| {
+        // Work out which letter of the alphabet this is (0 to 25), rotate it, then turn it back into a
+        // letter of the same case.
+        match c {
+            'A'..='Z' => (b'A' + (c as u8 - b'A' + shift) % 26) as char,
+            'a'..='z' => (b'a' + (c as u8 - b'a' + shift) % 26) as char,
+            _ => c
+        }
+    }).collect()
+}
+
+/// The alphabets [`named_alphabet`] knows, by name, each one in order. They're written in lower case, and
+/// [`rotate_alphabet`] rotates the upper case letters the same way.
+pub const NAMED_ALPHABETS: [(&str, &str); 4] = [
+    ("latin", "abcdefghijklmnopqrstuvwxyz"),
+    // The accented vowels come straight after the plain ones, and ñ after n, as Spanish dictionaries sort them.
+    ("spanish", "aábcdeéfghiíjklmnñoópqrstuúüvwxyz"),
+    ("cyrillic", "абвгдеёжзийклмнопрстуфхцчшщъыьэюя"),
+    ("greek", "αβγδεζηθικλμνξοπρστυφχψω"),
+];
+
+/// Finds one of the [`NAMED_ALPHABETS`] by its name, like "spanish", giving back its letters in order.
+///
+/// ```
+/// use rust_project::named_alphabet;
+///
+/// assert_eq!(named_alphabet("greek").unwrap().len(), 24);
+/// assert_eq!(named_alphabet("klingon"), None);
+/// ```
+pub fn named_alphabet(name: &str) -> Option<Vec<char>> {
+    NAMED_ALPHABETS
+        .iter()
+        .find(|(alphabet_name, _)| *alphabet_name == name)
+        .map(|(_, letters)| letters.chars().collect())
+}
+
+/// Rotates every character in a string that's in `alphabet` forward through it by `shift` places (a Caesar
+/// cipher over any alphabet), leaving everything else unchanged.
+///
+/// Characters wrap around from the end of the alphabet back to its start, and a negative shift rotates
+/// backwards, so `rotate_alphabet(text, -shift, alphabet)` undoes `rotate_alphabet(text, shift, alphabet)`.
+///
+/// If every letter in the alphabet has a single letter of the other case, and none of those are in the alphabet
+/// too, then the letters of the other case are rotated the same way and keep their case. An alphabet given in
+/// lower case rotates upper case letters too, while one that lists both cases treats them as different letters.
+///
+/// Each letter of the alphabet is one `char`, so a letter written as a base letter followed by a combining mark
+/// (like "n\u{303}" for ñ) is rotated by its base letter, and the mark is left where it is. Each character should
+/// only be in the alphabet once.
+///
+/// ```
+/// use rust_project::{named_alphabet, rotate_alphabet};
+///
+/// let spanish = named_alphabet("spanish").unwrap();
+/// assert_eq!(rotate_alphabet("Año", 1, &spanish), "Áoó");
+/// assert_eq!(rotate_alphabet("Áoó", -1, &spanish), "Año");
+/// assert_eq!(rotate_alphabet("Мир", 3, &named_alphabet("cyrillic").unwrap()), "Плу");
+/// ```
+pub fn rotate_alphabet(text: &str, shift: i32, alphabet: &[char]) -> String {
+    let positions: HashMap<char, usize> = alphabet.iter().enumerate().map(|(i, &letter)| (letter, i)).collect();
+    let has_case = alphabet
+        .iter()
+        .all(|&letter| other_case(letter).is_some_and(|other| !positions.contains_key(&other)));
+    // Any shift is the same as one from 0 up to the size of the alphabet, since that goes all the way around.
+    let shift = (shift as i64).rem_euclid(alphabet.len().max(1) as i64) as usize;
+    let rotate = |position: usize| alphabet[(position + shift) % alphabet.len()];
+    text.chars()
+        .map(|c| {
+            if let Some(&position) = positions.get(&c) {
+                rotate(position)
+            } else if let Some(&position) = other_case(c).filter(|_| has_case).and_then(|other| positions.get(&other)) {
+                other_case(rotate(position)).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+// This function gives the one letter of the other case for a letter, like "A" for "a" or "a" for "A", when
+// changing its case and back again gets the same letter. Anything else, like "ß" (whose upper case is "SS") or
+// characters without a case, has None.
+fn other_case(c: char) -> Option<char> {
+    let swap = |c: char| -> String {
+        if c.is_lowercase() {
+            c.to_uppercase().collect()
+        } else {
+            c.to_lowercase().collect()
+        }
+    };
+    let swapped = swap(c);
+    let mut letters = swapped.chars();
+    let other = letters.next().filter(|&other| other != c && letters.next().is_none())?;
+    (swap(other) == c.to_string()).then_some(other)
+}
+
+/// How often each letter from A to Z turns up in English text, as a percentage of all the letters.
+const ENGLISH_FREQUENCIES: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+/// Scores how much the letters in a string look like English, by how far the number of times each letter
+/// turns up is from what English text of the same length would have (the chi-squared statistic).
+///
+/// The lower the score, the more English-looking the text. Case and everything that isn't an ASCII letter are
+/// ignored, and text without any letters scores infinity, since there's nothing to go on.
+///
+/// ```
+/// use rust_project::english_score;
+///
+/// assert!(english_score("Meet me by the old oak tree") < english_score("Zrrg zr ol gur byq bnx gerr"));
+/// assert_eq!(english_score("1234"), f64::INFINITY);
+/// ```
+pub fn english_score(text: &str) -> f64 {
+    let mut counts = [0usize; 26];
+    for byte in text.bytes().filter(u8::is_ascii_alphabetic) {
+        counts[(byte.to_ascii_lowercase() - b'a') as usize] += 1;
+    }
+    let letters: usize = counts.iter().sum();
+    if letters == 0 {
+        return f64::INFINITY;
+    }
+    counts.iter().zip(ENGLISH_FREQUENCIES).map(|(&count, frequency)| {
+        let expected = letters as f64 * frequency / 100.0;
+        (count as f64 - expected).powi(2) / expected
+    }).sum()
+}
+
+/// One way of undoing a Caesar cipher, found by [`crack_caesar`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaesarCandidate {
+    /// The shift the text would have been encrypted with, from 1 to 25.
+    pub shift: i32,
+    /// The text decrypted with that shift.
+    pub text: String,
+    /// How English-looking the decrypted text is, from [`english_score`]. Lower is better.
+    pub score: f64,
+}
+
+/// Decrypts text encrypted with an unknown Caesar shift every possible way, giving back all 25 candidates
+/// (shift 0 would leave the text as it is) in order of shift.
+///
+/// Sorting them by [`CaesarCandidate::score`] puts the most likely ones first. With too little text, or text
+/// that isn't English, the right one may not be the best scoring one.
+///
+/// ```
+/// use rust_project::{crack_caesar, rotate_by};
+///
+/// let mut candidates = crack_caesar(&rotate_by("Meet me by the old oak tree at seven", 7));
+/// assert_eq!(candidates.len(), 25);
+/// candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+/// assert_eq!(candidates[0].shift, 7);
+/// assert_eq!(candidates[0].text, "Meet me by the old oak tree at seven");
+/// ```
+pub fn crack_caesar(text: &str) -> Vec<CaesarCandidate> {
+    (1..26).map(|shift| {
+        let text = rotate_by(text, -shift);
+        let score = english_score(&text);
+        CaesarCandidate { shift, text, score }
+    }).collect()
+}
+
+/// Encrypts a string with the affine cipher, which turns each letter's position in the alphabet `x`
+/// into `(a * x + b) mod 26`, ignoring non-alphabetical characters.
+///
+/// Letters keep their case. `a` must be coprime with 26 (odd, and not 13) or the encryption couldn't be undone,
+/// so any other value is rejected with [`CipherError::InvalidKey`].
+///
+/// ```
+/// use rust_project::{affine_decrypt, affine_encrypt};
+///
+/// assert_eq!(affine_encrypt("Affine Cipher", 5, 8).unwrap(), "Ihhwvc Swfrcp");
+/// assert_eq!(affine_decrypt("Ihhwvc Swfrcp", 5, 8).unwrap(), "Affine Cipher");
+/// assert!(affine_encrypt("Affine Cipher", 13, 8).is_err());
+/// ```
+pub fn affine_encrypt(text: &str, a: i32, b: i32) -> Result<String, CipherError> {
+    // Make sure the encryption can be undone before doing it.
+    modular_inverse(a)?;
+    Ok(map_letters(text, |x| (a.rem_euclid(26) * x + b.rem_euclid(26)) % 26))
+}
+
+/// Decrypts a string encrypted by [`affine_encrypt`] with the same `a` and `b`.
+///
+/// This turns each letter's position `y` back into `a⁻¹ * (y - b) mod 26`, where `a⁻¹` is the number that
+/// `a` multiplies with to give 1 (mod 26).
+pub fn affine_decrypt(text: &str, a: i32, b: i32) -> Result<String, CipherError> {
+    let a_inverse = modular_inverse(a)?;
+    Ok(map_letters(text, |y| (a_inverse * (y - b.rem_euclid(26))).rem_euclid(26)))
+}
+
+// This function finds the number that `a` multiplies with to give 1 (mod 26), which only exists when
+// `a` has no factors in common with 26. There are only 26 possibilities, so just try them all.
+fn modular_inverse(a: i32) -> Result<i32, CipherError> {
+    (1..26).find(|&x| (a.rem_euclid(26) * x) % 26 == 1).ok_or_else(|| {
+        CipherError::InvalidKey(format!("a = {a} is not coprime with 26, so the affine cipher can't be undone"))
+    })
+}
+
+// This function moves every ASCII letter in a string to a new position in the alphabet, keeping its case.
+// The letter's position (0 to 25) is given to `f`, which returns its new position (also 0 to 25).
+fn map_letters(text: &str, f: impl Fn(i32) -> i32) -> String {
+    text.chars().map(|c| {
+        match c {
+            'A'..='Z' => (b'A' + f((c as u8 - b'A') as i32) as u8) as char,
+            'a'..='z' => (b'a' + f((c as u8 - b'a') as i32) as u8) as char,
+            _ => c
+        }
+    }).collect()
+}
+
+/// Checks that a string is a substitution alphabet for [`substitute`]: all 26 letters, each exactly once, in
+/// the order they replace "A" to "Z".
+///
+/// Either case is accepted. Anything else, like a repeated or missing letter, is rejected with
+/// [`CipherError::InvalidKey`].
+///
+/// ```
+/// use rust_project::{parse_alphabet, substitute};
+///
+/// let mapping = parse_alphabet("QWERTYUIOPASDFGHJKLZXCVBNM").unwrap();
+/// assert_eq!(substitute("Hello, World!", &mapping, false), "Itssg, Vgksr!");
+/// assert_eq!(substitute("Itssg, Vgksr!", &mapping, true), "Hello, World!");
+/// assert!(parse_alphabet("QWERTYUIOPASDFGHJKLZXCVBNQ").is_err());
+/// assert!(parse_alphabet("QWERTY").is_err());
+/// ```
+pub fn parse_alphabet(alphabet: &str) -> Result<[char; 26], CipherError> {
+    let letters: Vec<char> = alphabet.chars().map(|c| c.to_ascii_uppercase()).collect();
+    if letters.len() != 26 {
+        return Err(CipherError::InvalidKey(format!(
+            "the alphabet has {} characters, but it needs all 26 letters",
+            letters.len()
+        )));
+    }
+    let mut mapping = ['A'; 26];
+    for (i, &c) in letters.iter().enumerate() {
+        if !c.is_ascii_uppercase() {
+            return Err(CipherError::InvalidKey(format!("'{c}' in the alphabet isn't a letter")));
+        }
+        if letters[..i].contains(&c) {
+            return Err(CipherError::InvalidKey(format!("'{c}' is in the alphabet more than once")));
+        }
+        mapping[i] = c;
+    }
+    Ok(mapping)
+}
+
+/// Encrypts/decrypts a string with a substitution cipher, replacing every ASCII letter with the one in the same
+/// place in `mapping` (so `mapping[0]` replaces "A"), ignoring non-alphabetical characters.
+///
+/// Letters keep their case. Decrypting replaces the letters in `mapping` with the ones they stand for instead.
+/// Use [`parse_alphabet`] to make sure a mapping can be undone before using it.
+pub fn substitute(text: &str, mapping: &[char; 26], decrypt: bool) -> String {
+    // Both ways are just a lookup, so when decrypting, build the opposite mapping first.
+    let mut positions = [0; 26];
+    for (i, c) in mapping.iter().enumerate() {
+        let position = (c.to_ascii_uppercase() as u8).wrapping_sub(b'A') as usize;
+        if decrypt {
+            positions[position % 26] = i as i32;
+        } else {
+            positions[i] = position as i32;
+        }
+    }
+    map_letters(text, |x| positions[x as usize])
+}
+
+/// Encrypts a string with the rail fence cipher, writing its characters in a zig-zag down and up across
+/// `rails` rows and then reading the rows off one after another.
+///
+/// Unlike the other ciphers this moves every character around, including spaces and punctuation, rather than
+/// replacing letters. One rail leaves the text as it is, and zero rails is rejected with
+/// [`CipherError::InvalidKey`].
+///
+/// ```
+/// use rust_project::{rail_fence_decrypt, rail_fence_encrypt};
+///
+/// assert_eq!(rail_fence_encrypt("WEAREDISCOVERED", 3).unwrap(), "WECRERDSOEEAIVD");
+/// assert_eq!(rail_fence_decrypt("WECRERDSOEEAIVD", 3).unwrap(), "WEAREDISCOVERED");
+/// ```
+pub fn rail_fence_encrypt(text: &str, rails: usize) -> Result<String, CipherError> {
+    let pattern = rail_pattern(text.chars().count(), rails)?;
+    // Read the characters off one rail at a time, keeping them in their original order along each rail.
+    let mut rows = vec![String::new(); rails];
+    for (c, rail) in text.chars().zip(pattern) {
+        rows[rail].push(c);
+    }
+    Ok(rows.concat())
+}
+
+/// Decrypts a string encrypted by [`rail_fence_encrypt`] with the same number of rails.
+pub fn rail_fence_decrypt(text: &str, rails: usize) -> Result<String, CipherError> {
+    let chars: Vec<char> = text.chars().collect();
+    let pattern = rail_pattern(chars.len(), rails)?;
+    // The ciphertext is every position on the first rail, then every position on the second rail, and so on.
+    // Sorting the positions by their rail (keeping them in order along each rail) gives the position each
+    // character of the ciphertext came from.
+    let mut positions: Vec<usize> = (0..chars.len()).collect();
+    positions.sort_by_key(|&position| pattern[position]);
+    let mut plain = vec!['\0'; chars.len()];
+    for (c, position) in chars.into_iter().zip(positions) {
+        plain[position] = c;
+    }
+    Ok(plain.into_iter().collect())
+}
+
+// This function works out which rail each of `len` characters lands on as the zig-zag goes down from the top
+// rail to the bottom one and back up again.
+fn rail_pattern(len: usize, rails: usize) -> Result<Vec<usize>, CipherError> {
+    if rails == 0 {
+        return Err(CipherError::InvalidKey(String::from("the rail fence cipher needs at least one rail")));
+    }
+    // With one rail there's no zig-zag, so everything stays on it.
+    if rails == 1 {
+        return Ok(vec![0; len]);
+    }
+    // Going down and back up again takes this many characters, after which the pattern repeats.
+    let cycle = 2 * (rails - 1);
+    Ok((0..len).map(|i| {
+        let step = i % cycle;
+        if step < rails { step } else { cycle - step }
+    }).collect())
+}
+
+/// Encrypts a string with the columnar transposition cipher, writing its characters into a grid row by row
+/// (one column per character of `key`) and then reading the columns out in the alphabetical order of the key.
+///
+/// Like the rail fence cipher this moves every character around rather than replacing letters. Letters in
+/// the key are compared ignoring case, and repeated letters are read left to right, so the order is always the
+/// same. An empty key is rejected with [`CipherError::InvalidKey`].
+///
+/// ```
+/// use rust_project::{columnar_decrypt, columnar_encrypt};
+///
+/// assert_eq!(columnar_encrypt("WEAREDISCOVERED", "ZEBRAS").unwrap(), "EVACDESERODEWIR");
+/// assert_eq!(columnar_decrypt("EVACDESERODEWIR", "ZEBRAS").unwrap(), "WEAREDISCOVERED");
+/// ```
+pub fn columnar_encrypt(text: &str, key: &str) -> Result<String, CipherError> {
+    let order = column_order(key)?;
+    let columns = order.len();
+    let chars: Vec<char> = text.chars().collect();
+    // Reading down a column means taking every `columns`th character, starting at that column.
+    Ok(order.iter().flat_map(|&column| chars.iter().skip(column).step_by(columns)).collect())
+}
+
+/// Decrypts a string encrypted by [`columnar_encrypt`] with the same key.
+pub fn columnar_decrypt(text: &str, key: &str) -> Result<String, CipherError> {
+    let order = column_order(key)?;
+    let columns = order.len();
+    let chars: Vec<char> = text.chars().collect();
+    // The last row of the grid is short unless the text filled it exactly, so only the first few columns
+    // have a character in it.
+    let full_rows = chars.len() / columns;
+    let long_columns = chars.len() % columns;
+
+    // Cut the ciphertext back up into its columns, in the order they were read out.
+    let mut grid = vec![Vec::new(); columns];
+    let mut rest = chars.as_slice();
+    for &column in &order {
+        let length = full_rows + if column < long_columns { 1 } else { 0 };
+        let (taken, remaining) = rest.split_at(length);
+        grid[column] = taken.to_vec();
+        rest = remaining;
+    }
+
+    // Then read the grid back out row by row.
+    let mut plain = String::with_capacity(text.len());
+    for row in 0..=full_rows {
+        for column in &grid {
+            if let Some(&c) = column.get(row) {
+                plain.push(c);
+            }
+        }
+    }
+    Ok(plain)
+}
+
+// This function works out which order the columns are read in, from the alphabetical order of the key's letters.
+// Equal letters keep their left-to-right order, since the sort is stable.
+fn column_order(key: &str) -> Result<Vec<usize>, CipherError> {
+    let key: Vec<char> = key.chars().map(|c| c.to_ascii_lowercase()).collect();
+    if key.is_empty() {
+        return Err(CipherError::InvalidKey(String::from("the columnar cipher needs a key with at least one letter")));
+    }
+    let mut order: Vec<usize> = (0..key.len()).collect();
+    order.sort_by_key(|&column| key[column]);
+    Ok(order)
+}
+
+// The 32 characters Base32 uses, as given in RFC 4648. Each one stands for 5 bits.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Encodes bytes as Base32 text, following RFC 4648.
+///
+/// Every 5 bytes become 8 characters from `A`–`Z` and `2`–`7`, and the end is padded with `=` to a whole
+/// number of 8 character groups.
+///
+/// ```
+/// use rust_project::{base32_decode, base32_encode};
+///
+/// assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI======");
+/// assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar");
+/// ```
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+    // Bytes are fed into the bottom of `buffer` and taken out of the top 5 bits at a time.
+    // `bits` is how many bits are waiting in it.
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+        // Forget the bits that have been used, so the buffer doesn't overflow.
+        buffer &= (1 << bits) - 1;
+    }
+    // Any bits left over get filled out to 5 with zeros.
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    while !encoded.len().is_multiple_of(8) {
+        encoded.push('=');
+    }
+    encoded
+}
+
+/// Decodes Base32 text back into bytes, following RFC 4648.
+///
+/// Lowercase letters are accepted, whitespace (like line breaks) is ignored, and the `=` padding at the end is
+/// optional. Any other character is rejected with [`CipherError::InvalidInput`].
+pub fn base32_decode(text: &str) -> Result<Vec<u8>, CipherError> {
+    let mut decoded = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+    let mut characters = 0;
+    let mut padding = false;
+    for (position, c) in text.chars().enumerate() {
+        if c.is_whitespace() {
+            continue;
+        }
+        // Padding can only come at the very end.
+        if c == '=' {
+            padding = true;
+            continue;
+        }
+        if padding {
+            return Err(CipherError::InvalidInput(format!(
+                "Base32 character '{c}' at position {position} comes after the '=' padding"
+            )));
+        }
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&letter| letter as char == c.to_ascii_uppercase())
+            .ok_or_else(|| CipherError::InvalidInput(format!("'{c}' at position {position} isn't a Base32 character")))?;
+        buffer = (buffer << 5) | value as u16;
+        bits += 5;
+        characters += 1;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // Encoding never leaves 1, 3, or 6 characters in the last group, so those can't have come from it.
+    if matches!(characters % 8, 1 | 3 | 6) {
+        return Err(CipherError::InvalidInput(String::from("the Base32 text has been cut short")));
+    }
+    Ok(decoded)
+}
+
+/// Whether a [`Cipher`] is encrypting or decrypting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// A cipher that works on raw bytes, so the same code can read, encrypt/decrypt, and write with any of them.
+///
+/// Each cipher above has a struct holding its settings that implements this trait. Ciphers that only work on
+/// text reject bytes that aren't UTF-8 with [`CipherError::InvalidInput`]. Ciphers that are given a key and
+/// nonce with each call instead, like [`ChaCha20Poly1305`], are used through [`Cipher::encrypt`] and
+/// [`Cipher::decrypt`], which work for every cipher.
+///
+/// ```
+/// use rust_project::{
+///     named_alphabet, parse_alphabet, Affine, Base32, Caesar, Cipher, Columnar, Direction, RailFence, Rot13,
+///     Rot13Rot5, Rot13Unicode, Substitution, Zeroizing,
+/// };
+///
+/// let ciphers: Vec<Box<dyn Cipher>> = vec![
+///     Box::new(Rot13),
+///     Box::new(Affine { a: 5, b: 8 }),
+///     Box::new(RailFence { rails: 3 }),
+///     Box::new(Columnar { key: Zeroizing::new(String::from("ZEBRAS")) }),
+///     Box::new(Substitution { mapping: Zeroizing::new(parse_alphabet("QWERTYUIOPASDFGHJKLZXCVBNM").unwrap()) }),
+///     Box::new(Base32),
+///     Box::new(Rot13Rot5),
+///     Box::new(Rot13Unicode),
+///     Box::new(Caesar { shift: 3, alphabet: named_alphabet("spanish").unwrap() }),
+/// ];
+/// let plain = "We are discovered. Flee at once!".as_bytes();
+/// for cipher in ciphers {
+///     let encrypted = cipher.process(plain, Direction::Encrypt).unwrap();
+///     assert_eq!(cipher.process(&encrypted, Direction::Decrypt).unwrap(), plain, "{}", cipher.name());
+/// }
+/// ```
+pub trait Cipher: Send + Sync {
+    /// The cipher's name, like "rot13".
+    fn name(&self) -> &'static str;
+
+    /// Encrypts or decrypts `data`.
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError>;
+
+    /// Whether every letter is encrypted/decrypted on its own, without looking at the rest of the text.
+    /// If so, the text can be cut into pieces between any two characters and each piece processed separately,
+    /// giving the same result.
+    fn works_letter_by_letter(&self) -> bool {
+        false
+    }
+
+    /// How many bytes of key [`Cipher::encrypt`] and [`Cipher::decrypt`] need. Ciphers that hold their own
+    /// settings, like all the classical ones, don't take one, so it's 0.
+    fn key_len(&self) -> usize {
+        0
+    }
+
+    /// How many bytes of nonce [`Cipher::encrypt`] and [`Cipher::decrypt`] need, or 0 if the cipher doesn't use
+    /// one.
+    fn nonce_len(&self) -> usize {
+        0
+    }
+
+    /// Encrypts `plaintext` with a key and nonce, which have to be [`Cipher::key_len`] and [`Cipher::nonce_len`]
+    /// bytes long. An authenticated cipher also authenticates `aad` (data that isn't encrypted but mustn't be
+    /// changed either, like a header), so decrypting fails unless it's given the same `aad`. Other ciphers
+    /// ignore it.
+    ///
+    /// By default, this checks the key and nonce are empty and encrypts with [`Cipher::process`].
+    fn encrypt(&self, key: &[u8], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let _ = aad;
+        check_key_and_nonce(self, key, nonce)?;
+        self.process(plaintext, Direction::Encrypt)
+    }
+
+    /// Decrypts data encrypted by [`Cipher::encrypt`] with the same key, nonce, and `aad`.
+    ///
+    /// By default, this checks the key and nonce are empty and decrypts with [`Cipher::process`].
+    fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let _ = aad;
+        check_key_and_nonce(self, key, nonce)?;
+        self.process(ciphertext, Direction::Decrypt)
+    }
+}
+
+// This function checks the key and nonce given to a cipher are the lengths it needs.
+fn check_key_and_nonce(cipher: &(impl Cipher + ?Sized), key: &[u8], nonce: &[u8]) -> Result<(), CipherError> {
+    let name = cipher.name();
+    if key.len() != cipher.key_len() {
+        return Err(CipherError::InvalidKey(format!("{name} needs a {}-byte key, not {}", cipher.key_len(), key.len())));
+    }
+    if nonce.len() != cipher.nonce_len() {
+        let expected = cipher.nonce_len();
+        return Err(CipherError::InvalidInput(format!("{name} needs a {expected}-byte nonce, not {}", nonce.len())));
+    }
+    Ok(())
+}
+
+// This function checks that bytes given to a text-only cipher really are text.
+fn as_text(data: &[u8]) -> Result<&str, CipherError> {
+    std::str::from_utf8(data).map_err(|e| CipherError::InvalidInput(format!("the text isn't valid UTF-8 ({e})")))
+}
+
+/// [`rot13`] as a [`Cipher`].
+pub struct Rot13;
+
+impl Cipher for Rot13 {
+    fn name(&self) -> &'static str {
+        "rot13"
+    }
+
+    fn process(&self, data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+        // ROT13 is its own inverse, so encrypting and decrypting are the same.
+        Ok(rot13(as_text(data)?).into_bytes())
+    }
+
+    fn works_letter_by_letter(&self) -> bool {
+        true
+    }
+}
+
+/// [`rot13_5`] as a [`Cipher`].
+pub struct Rot13Rot5;
+
+impl Cipher for Rot13Rot5 {
+    fn name(&self) -> &'static str {
+        "rot13-5"
+    }
+
+    fn process(&self, data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+        // Like ROT13, this is its own inverse.
+        Ok(rot13_5(as_text(data)?).into_bytes())
+    }
+
+    fn works_letter_by_letter(&self) -> bool {
+        true
+    }
+}
+
+/// [`rot13_unicode`] as a [`Cipher`].
+pub struct Rot13Unicode;
+
+impl Cipher for Rot13Unicode {
+    fn name(&self) -> &'static str {
+        "rot13-unicode"
+    }
+
+    fn process(&self, data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+        // Like ROT13, this is its own inverse.
+        Ok(rot13_unicode(as_text(data)?).into_bytes())
+    }
+
+    fn works_letter_by_letter(&self) -> bool {
+        true
+    }
+}
+
+/// [`rotate_alphabet`] as a [`Cipher`], rotating forwards by `shift` to encrypt and back to decrypt.
+pub struct Caesar {
+    /// How many places each letter is rotated.
+    pub shift: i32,
+    /// The letters rotated, in order, like one of the [`NAMED_ALPHABETS`].
+    pub alphabet: Vec<char>,
+}
+
+impl Cipher for Caesar {
+    fn name(&self) -> &'static str {
+        "caesar"
+    }
+
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError> {
+        let shift = match direction {
+            Direction::Encrypt => self.shift,
+            // Taken down to less than the size of the alphabet first, since the smallest i32 can't be negated.
+            Direction::Decrypt => -self.shift.rem_euclid(self.alphabet.len().max(1) as i32),
+        };
+        Ok(rotate_alphabet(as_text(data)?, shift, &self.alphabet).into_bytes())
+    }
+
+    fn works_letter_by_letter(&self) -> bool {
+        true
+    }
+}
+
+/// [`affine_encrypt`] and [`affine_decrypt`] as a [`Cipher`].
+pub struct Affine {
+    pub a: i32,
+    pub b: i32,
+}
+
+impl Cipher for Affine {
+    fn name(&self) -> &'static str {
+        "affine"
+    }
+
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError> {
+        let text = as_text(data)?;
+        let result = match direction {
+            Direction::Encrypt => affine_encrypt(text, self.a, self.b)?,
+            Direction::Decrypt => affine_decrypt(text, self.a, self.b)?,
+        };
+        Ok(result.into_bytes())
+    }
+
+    fn works_letter_by_letter(&self) -> bool {
+        true
+    }
+}
+
+/// [`substitute`] as a [`Cipher`].
+pub struct Substitution {
+    pub mapping: Zeroizing<[char; 26]>,
+}
+
+impl Cipher for Substitution {
+    fn name(&self) -> &'static str {
+        "substitution"
+    }
+
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError> {
+        Ok(substitute(as_text(data)?, &self.mapping, direction == Direction::Decrypt).into_bytes())
+    }
+
+    fn works_letter_by_letter(&self) -> bool {
+        true
+    }
+}
+
+/// [`rail_fence_encrypt`] and [`rail_fence_decrypt`] as a [`Cipher`].
+pub struct RailFence {
+    pub rails: usize,
+}
+
+impl Cipher for RailFence {
+    fn name(&self) -> &'static str {
+        "rail-fence"
+    }
+
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError> {
+        let text = as_text(data)?;
+        let result = match direction {
+            Direction::Encrypt => rail_fence_encrypt(text, self.rails)?,
+            Direction::Decrypt => rail_fence_decrypt(text, self.rails)?,
+        };
+        Ok(result.into_bytes())
+    }
+}
+
+/// [`columnar_encrypt`] and [`columnar_decrypt`] as a [`Cipher`].
+pub struct Columnar {
+    pub key: Zeroizing<String>,
+}
+
+impl Cipher for Columnar {
+    fn name(&self) -> &'static str {
+        "columnar"
+    }
+
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError> {
+        let text = as_text(data)?;
+        let result = match direction {
+            Direction::Encrypt => columnar_encrypt(text, &self.key)?,
+            Direction::Decrypt => columnar_decrypt(text, &self.key)?,
+        };
+        Ok(result.into_bytes())
+    }
+}
+
+/// [`base32_encode`] and [`base32_decode`] as a [`Cipher`]. Unlike the others, this encodes any bytes at all.
+pub struct Base32;
+
+impl Cipher for Base32 {
+    fn name(&self) -> &'static str {
+        "base32"
+    }
+
+    fn process(&self, data: &[u8], direction: Direction) -> Result<Vec<u8>, CipherError> {
+        match direction {
+            Direction::Encrypt => Ok(base32_encode(data).into_bytes()),
+            Direction::Decrypt => base32_decode(as_text(data)?),
+        }
+    }
+}
+
+/// ChaCha20-Poly1305 (RFC 8439) as a [`Cipher`]: an authenticated cipher, so decrypting fails if the encrypted
+/// data (or the `aad` given with it) has been changed at all, rather than giving back something garbled.
+///
+/// It takes a 32-byte key and a 12-byte nonce with every call to [`Cipher::encrypt`] and [`Cipher::decrypt`],
+/// rather than holding a key of its own, so [`Cipher::process`] always fails. The encrypted data is as long as
+/// the plaintext, plus a 16-byte authentication tag at the end. A nonce must never be used twice with the same
+/// key; see [`nonce`](crate::nonce).
+///
+/// ```
+/// use rust_project::{ChaCha20Poly1305, Cipher};
+///
+/// let (key, nonce) = ([7; 32], [9; 12]);
+/// let sealed = ChaCha20Poly1305.encrypt(&key, &nonce, b"attack at dawn", b"header").unwrap();
+/// assert_eq!(sealed.len(), 14 + 16);
+/// assert_eq!(ChaCha20Poly1305.decrypt(&key, &nonce, &sealed, b"header").unwrap(), b"attack at dawn");
+/// assert!(ChaCha20Poly1305.decrypt(&key, &nonce, &sealed, b"changed").is_err());
+/// assert!(ChaCha20Poly1305.decrypt(&[8; 32], &nonce, &sealed, b"header").is_err());
+/// ```
+pub struct ChaCha20Poly1305;
+
+impl Cipher for ChaCha20Poly1305 {
+    fn name(&self) -> &'static str {
+        "chacha20-poly1305"
+    }
+
+    fn process(&self, _data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+        Err(CipherError::InvalidKey(String::from("chacha20-poly1305 needs a key and nonce; use encrypt or decrypt")))
+    }
+
+    fn key_len(&self) -> usize {
+        32
+    }
+
+    fn nonce_len(&self) -> usize {
+        nonce::NONCE_LEN
+    }
+
+    fn encrypt(&self, key: &[u8], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        check_key_and_nonce(self, key, nonce)?;
+        ChaCha20Poly1305Aead::new(key.into())
+            .encrypt(nonce.into(), Payload { msg: plaintext, aad })
+            .map_err(|_| CipherError::InvalidInput(String::from("the data is too big to encrypt")))
+    }
+
+    fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        check_key_and_nonce(self, key, nonce)?;
+        ChaCha20Poly1305Aead::new(key.into())
+            .decrypt(nonce.into(), Payload { msg: ciphertext, aad })
+            .map_err(|_| CipherError::InvalidInput(String::from("the key is wrong, or the data has been changed")))
+    }
+}
+
+/// AES-256-GCM (NIST SP 800-38D) as a [`Cipher`]: an authenticated cipher like [`ChaCha20Poly1305`], and the
+/// one JWE calls "A256GCM" (see [`jwe`](crate::jwe)).
+///
+/// It takes a 32-byte key and a 12-byte nonce the same way, and the encrypted data is the plaintext's length
+/// plus a 16-byte authentication tag at the end. The tag is checked (in constant time) before anything is
+/// decrypted.
+///
+/// ```
+/// use rust_project::{Aes256Gcm, Cipher};
+///
+/// // NIST's GCM test case 14: a key of zeros, and one block of zeros.
+/// let sealed = Aes256Gcm.encrypt(&[0; 32], &[0; 12], &[0; 16], b"").unwrap();
+/// let hex: String = sealed.iter().map(|byte| format!("{byte:02x}")).collect();
+/// assert_eq!(hex, "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919");
+/// assert_eq!(Aes256Gcm.decrypt(&[0; 32], &[0; 12], &sealed, b"").unwrap(), [0; 16]);
+/// assert!(Aes256Gcm.decrypt(&[0; 32], &[0; 12], &sealed, b"aad").is_err());
+/// ```
+pub struct Aes256Gcm;
+
+impl Aes256Gcm {
+    /// The size of the authentication tag, in bytes.
+    pub const TAG_LEN: usize = 16;
+}
+
+impl Cipher for Aes256Gcm {
+    fn name(&self) -> &'static str {
+        "aes-256-gcm"
+    }
+
+    fn process(&self, _data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+        Err(CipherError::InvalidKey(String::from("aes-256-gcm needs a key and nonce; use encrypt or decrypt")))
+    }
+
+    fn key_len(&self) -> usize {
+        32
+    }
+
+    fn nonce_len(&self) -> usize {
+        nonce::NONCE_LEN
+    }
+
+    // GCM can only encrypt up to 2^36 - 32 bytes with one nonce before its counter runs out, so anything
+    // bigger is refused rather than reusing the counter.
+    fn encrypt(&self, key: &[u8], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        check_key_and_nonce(self, key, nonce)?;
+        Aes256GcmAead::new(key.into())
+            .encrypt(nonce.into(), Payload { msg: plaintext, aad })
+            .map_err(|_| CipherError::InvalidInput(String::from("the data is too big to encrypt")))
+    }
+
+    fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        check_key_and_nonce(self, key, nonce)?;
+        Aes256GcmAead::new(key.into())
+            .decrypt(nonce.into(), Payload { msg: ciphertext, aad })
+            .map_err(|_| CipherError::InvalidInput(String::from("the key is wrong, or the data has been changed")))
+    }
+}
+
+/// A set of [`Cipher`]s that can be looked up by name, so a program can offer ciphers it doesn't know about
+/// ahead of time, including ones from other crates.
+///
+/// A cipher is registered already holding any key or settings it needs, so it can be used straight away.
+///
+/// ```
+/// use rust_project::{Cipher, CipherError, CipherRegistry, Direction};
+///
+/// // A cipher from another crate: it just reverses the bytes.
+/// struct Reverse;
+///
+/// impl Cipher for Reverse {
+///     fn name(&self) -> &'static str {
+///         "reverse"
+///     }
+///
+///     fn process(&self, data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+///         Ok(data.iter().rev().copied().collect())
+///     }
+/// }
+///
+/// let mut registry = CipherRegistry::with_builtins();
+/// registry.register(Box::new(Reverse));
+/// let names = registry.names();
+/// assert_eq!(names, ["aes-256-gcm", "base32", "chacha20-poly1305", "reverse", "rot13", "rot13-5", "rot13-unicode"]);
+///
+/// let cipher = registry.get("reverse").unwrap();
+/// assert_eq!(cipher.process(b"abc", Direction::Encrypt).unwrap(), b"cba");
+/// assert!(registry.get("enigma").is_none());
+///
+/// // Registered with the registry the whole program shares, it can be found from anywhere.
+/// CipherRegistry::register_global(Box::new(Reverse));
+/// let cipher = CipherRegistry::global().get("reverse").unwrap().encrypt(&[], &[], b"abc", &[]).unwrap();
+/// assert_eq!(cipher, b"cba");
+/// ```
+#[derive(Default)]
+pub struct CipherRegistry {
+    ciphers: HashMap<&'static str, Box<dyn Cipher>>,
+}
+
+// The registry the whole program shares, starting with the built-in ciphers.
+static GLOBAL_CIPHERS: LazyLock<RwLock<CipherRegistry>> =
+    LazyLock::new(|| RwLock::new(CipherRegistry::with_builtins()));
+
+impl CipherRegistry {
+    /// Makes a registry with no ciphers in it.
+    pub fn new() -> CipherRegistry {
+        CipherRegistry::default()
+    }
+
+    /// Makes a registry holding the built-in ciphers that don't hold a key or any settings of their own:
+    /// [`Rot13`], [`Rot13Rot5`], [`Rot13Unicode`], [`Base32`], [`ChaCha20Poly1305`], and [`Aes256Gcm`] (the last
+    /// two are given their key with each call). The others need settings only the caller knows, so they have to
+    /// be registered by hand.
+    pub fn with_builtins() -> CipherRegistry {
+        let mut registry = CipherRegistry::new();
+        registry.register(Box::new(Rot13));
+        registry.register(Box::new(Rot13Rot5));
+        registry.register(Box::new(Rot13Unicode));
+        registry.register(Box::new(Base32));
+        registry.register(Box::new(ChaCha20Poly1305));
+        registry.register(Box::new(Aes256Gcm));
+        registry
+    }
+
+    /// The registry shared by the whole program, which starts out like [`CipherRegistry::with_builtins`].
+    ///
+    /// Other code can't change it while the returned guard is held, so hold it only as long as it's needed.
+    pub fn global() -> RwLockReadGuard<'static, CipherRegistry> {
+        // A thread that panicked while registering a cipher can't have left the map half-changed.
+        GLOBAL_CIPHERS.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds a cipher to the registry shared by the whole program, so a crate can make its own ciphers available
+    /// to code that looks them up with [`CipherRegistry::global`]. Like [`CipherRegistry::register`], a cipher
+    /// already registered under the same name is replaced, and given back.
+    pub fn register_global(cipher: Box<dyn Cipher>) -> Option<Box<dyn Cipher>> {
+        GLOBAL_CIPHERS.write().unwrap_or_else(PoisonError::into_inner).register(cipher)
+    }
+
+    /// Adds a cipher under its [`Cipher::name`]. A cipher already registered under that name is replaced, and
+    /// given back.
+    pub fn register(&mut self, cipher: Box<dyn Cipher>) -> Option<Box<dyn Cipher>> {
+        self.ciphers.insert(cipher.name(), cipher)
+    }
+
+    /// Finds the cipher registered under a name.
+    pub fn get(&self, name: &str) -> Option<&dyn Cipher> {
+        self.ciphers.get(name).map(|cipher| cipher.as_ref())
+    }
+
+    /// The names of all the registered ciphers, in alphabetical order.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.ciphers.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// A named preset of a cipher and how its key is derived from a password, so users can pick one word instead
+/// of a cipher, a KDF, and the KDF's settings.
+///
+/// | profile | cipher | KDF |
+/// |---------|--------|-----|
+/// | [`FAST`](CipherProfile::FAST) | ChaCha20-Poly1305 | scrypt, N = 2^14, r = 8, p = 1 |
+/// | [`BALANCED`](CipherProfile::BALANCED) | ChaCha20-Poly1305 | Argon2id, 64 MiB, 3 passes, 4 lanes |
+/// | [`PARANOID`](CipherProfile::PARANOID) | ChaCha20-Poly1305 | Argon2id, 256 MiB, 4 passes, 4 lanes |
+/// | [`LEGACY`](CipherProfile::LEGACY) | ROT13 | none |
+///
+/// ```
+/// use rust_project::kdf::{KdfParams, ARGON2ID_DEFAULT};
+/// use rust_project::CipherProfile;
+///
+/// let profile = CipherProfile::named("balanced").unwrap();
+/// assert_eq!(profile.cipher, "chacha20-poly1305");
+/// assert_eq!(profile.kdf, Some(KdfParams::Argon2id(ARGON2ID_DEFAULT)));
+/// assert_eq!(CipherProfile::named("legacy").unwrap().kdf, None);
+/// assert!(CipherProfile::named("quick").is_none());
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CipherProfile {
+    /// The profile's name, like `"balanced"`.
+    pub name: &'static str,
+    /// The cipher's name in the [`CipherRegistry`].
+    pub cipher: &'static str,
+    /// The KDF and its settings, or `None` for a cipher whose key doesn't come from a password.
+    pub kdf: Option<KdfParams>,
+}
+
+impl CipherProfile {
+    /// Quick to derive the key on slow computers, at the cost of being cheaper to guess passwords against.
+    pub const FAST: CipherProfile = CipherProfile {
+        name: "fast",
+        cipher: "chacha20-poly1305",
+        kdf: Some(KdfParams::Scrypt(ScryptKdf { log_n: 14, r: 8, p: 1 })),
+    };
+    /// The settings RFC 9106 recommends, and what ChaCha20-Poly1305 uses unless told otherwise.
+    pub const BALANCED: CipherProfile = CipherProfile {
+        name: "balanced",
+        cipher: "chacha20-poly1305",
+        kdf: Some(KdfParams::Argon2id(ARGON2ID_DEFAULT)),
+    };
+    /// Four times the memory of [`CipherProfile::BALANCED`], and an extra pass, for files worth the wait.
+    pub const PARANOID: CipherProfile = CipherProfile {
+        name: "paranoid",
+        cipher: "chacha20-poly1305",
+        kdf: Some(KdfParams::Argon2id(Argon2idKdf { memory_kib: 256 * 1024, iterations: 4, parallelism: 4 })),
+    };
+    /// ROT13, for files that have to be read by older versions. It hides nothing from anyone who tries.
+    pub const LEGACY: CipherProfile = CipherProfile { name: "legacy", cipher: "rot13", kdf: None };
+
+    /// Every profile, in order from fastest to slowest, with legacy last.
+    pub const ALL: [CipherProfile; 4] =
+        [CipherProfile::FAST, CipherProfile::BALANCED, CipherProfile::PARANOID, CipherProfile::LEGACY];
+
+    /// Finds the profile with the given name.
+    pub fn named(name: &str) -> Option<CipherProfile> {
+        CipherProfile::ALL.into_iter().find(|profile| profile.name == name)
+    }
+}
+
+/// A small header put at the start of encrypted data, saying how it was encrypted so it can be decrypted
+/// without having to remember. It never holds any part of a key.
+///
+/// There are two versions of the format. Version 1 is 6 bytes: the magic string `FENC`, the version, and the
+/// cipher's ID, which is all a cipher with its own key (like the classical ones) needs. Version 2 adds what a
+/// cipher whose key comes from a password needs to get the same key again, and a byte of flags:
+///
+/// | bytes | what |
+/// |-------|------|
+/// | 4 | the magic string `FENC` |
+/// | 1 | the version, 2 |
+/// | 1 | the cipher's ID |
+/// | 1 | flags: 1 if compressed before it was encrypted, 2 if armored (written as text) after, 4 if a MAC follows |
+/// | 1 | the ID of the key derivation function (KDF), or 0 for none |
+/// | 1 + n | the length of the KDF's settings, then the settings |
+/// | 1 + n | the length of the salt, then the salt |
+/// | 1 + n | the length of the nonce, then the nonce |
+///
+/// [`FileHeader::new`] makes a version 1 header, and the `with_` functions move it to version 2 as they add to
+/// it, so data that doesn't need the extra fields can still be read by older versions. The IDs themselves are up
+/// to whoever writes the header.
+///
+/// ```
+/// use rust_project::{CipherError, FileHeader};
+///
+/// let mut data = FileHeader::new(3).to_bytes();
+/// data.extend_from_slice(b"encrypted text");
+/// let (header, rest) = FileHeader::parse(&data).unwrap().unwrap();
+/// assert_eq!(header.cipher_id, 3);
+/// assert_eq!(rest, b"encrypted text");
+///
+/// // Data without a header is left to the caller to deal with.
+/// assert_eq!(FileHeader::parse(b"Uryyb, Jbeyq!").unwrap(), None);
+///
+/// // Data from a newer version of the format is refused.
+/// assert_eq!(FileHeader::parse(b"FENC\x63\x01..."), Err(CipherError::UnsupportedVersion(0x63)));
+/// ```
+///
+/// A version 2 header carries the KDF's settings, salt, and nonce along:
+///
+/// ```
+/// use rust_project::FileHeader;
+///
+/// let header = FileHeader::new(12).with_kdf(3, vec![14, 0, 0, 0, 8, 0, 0, 0, 1], vec![7; 16]).with_nonce(vec![9; 12]);
+/// assert_eq!(header.version, 2);
+/// let bytes = header.to_bytes();
+/// assert_eq!(bytes.len(), header.len());
+/// assert_eq!(&bytes[..9], b"FENC\x02\x0c\x00\x03\x09");
+///
+/// let (read, rest) = FileHeader::parse(&bytes).unwrap().unwrap();
+/// assert_eq!(read, header);
+/// assert!(rest.is_empty());
+///
+/// // A header that's cut short is an error, rather than data without a header.
+/// assert!(FileHeader::parse(&bytes[..20]).is_err());
+/// ```
+///
+/// It can also be written to and read from a stream, and (with serde) turned into other formats like JSON:
+///
+/// ```
+/// use rust_project::FileHeader;
+///
+/// let mut file = Vec::new();
+/// FileHeader::new(5).write_to(&mut file).unwrap();
+/// assert_eq!(file, b"FENC\x01\x05");
+/// assert_eq!(FileHeader::read_from(&mut file.as_slice()).unwrap(), FileHeader::new(5));
+/// assert!(FileHeader::read_from(&mut &b"FEN"[..]).is_err());
+///
+/// let json = serde_json::to_string(&FileHeader::new(5)).unwrap();
+/// assert!(json.starts_with(r#"{"magic":[70,69,78,67],"version":1,"cipher_id":5,"kdf_id":0,"#));
+/// assert_eq!(serde_json::from_str::<FileHeader>(&json).unwrap(), FileHeader::new(5));
+/// ```
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FileHeader {
+    /// The magic string, which is always [`FileHeader::MAGIC`].
+    pub magic: [u8; 4],
+    /// The version of the format.
+    pub version: u8,
+    /// The ID of the cipher that encrypted the data.
+    pub cipher_id: u8,
+    /// The ID of the KDF that turned a password into the key, or 0 if the key didn't come from a password.
+    pub kdf_id: u8,
+    /// The KDF's cost settings, as the KDF writes them.
+    pub kdf_params: Vec<u8>,
+    /// The random salt the password was mixed with.
+    pub salt: Vec<u8>,
+    /// The nonce the data was encrypted with.
+    pub nonce: Vec<u8>,
+    /// Whether the data was compressed before it was encrypted.
+    pub compressed: bool,
+    /// Whether the encrypted data was armored, written out as text, after it was encrypted.
+    pub armored: bool,
+    /// Whether the encrypted data is followed by an HMAC-SHA256 tag (see [`compute_hmac`]) covering the header and
+    /// the encrypted data.
+    pub mac: bool,
+}
+
+impl FileHeader {
+    /// The bytes every header starts with.
+    pub const MAGIC: &'static [u8; 4] = b"FENC";
+    /// The newest version of the format this version of the library can read and write.
+    pub const VERSION: u8 = 2;
+    /// The size of a version 1 header in bytes, which is the smallest a header can be.
+    pub const LEN: usize = 6;
+
+    // The bits of the flags byte.
+    const COMPRESSED: u8 = 1;
+    const ARMORED: u8 = 2;
+    const MAC: u8 = 4;
+
+    /// Makes a version 1 header, which only names the cipher.
+    pub fn new(cipher_id: u8) -> FileHeader {
+        FileHeader {
+            magic: *FileHeader::MAGIC,
+            version: 1,
+            cipher_id,
+            kdf_id: 0,
+            kdf_params: Vec::new(),
+            salt: Vec::new(),
+            nonce: Vec::new(),
+            compressed: false,
+            armored: false,
+            mac: false,
+        }
+    }
+
+    /// Adds the KDF that turned a password into the key, with its settings and salt, moving the header to
+    /// version 2.
+    pub fn with_kdf(self, kdf_id: u8, kdf_params: Vec<u8>, salt: Vec<u8>) -> FileHeader {
+        FileHeader { version: 2, kdf_id, kdf_params, salt, ..self }
+    }
+
+    /// Adds the nonce the data was encrypted with, moving the header to version 2.
+    pub fn with_nonce(self, nonce: Vec<u8>) -> FileHeader {
+        FileHeader { version: 2, nonce, ..self }
+    }
+
+    /// Sets whether the data was compressed and armored, moving the header to version 2.
+    pub fn with_flags(self, compressed: bool, armored: bool) -> FileHeader {
+        FileHeader { version: 2, compressed, armored, ..self }
+    }
+
+    /// Sets whether the encrypted data is followed by a MAC, moving the header to version 2.
+    pub fn with_mac(self, mac: bool) -> FileHeader {
+        FileHeader { version: 2, mac, ..self }
+    }
+
+    /// The size of the header in bytes, once it's turned into bytes.
+    ///
+    /// A version 1 header only has room for the cipher's ID, so it's always [`FileHeader::LEN`].
+    pub fn len(&self) -> usize {
+        match self.version {
+            1 => FileHeader::LEN,
+            _ => {
+                let fields = [&self.kdf_params, &self.salt, &self.nonce];
+                FileHeader::LEN + 2 + fields.iter().map(|field| 1 + field.len()).sum::<usize>()
+            }
+        }
+    }
+
+    /// Whether the header is empty, which it never is. This is only here to go with [`FileHeader::len`].
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Turns the header into the bytes that go at the start of the data.
+    ///
+    /// # Panics
+    ///
+    /// If the KDF's settings, the salt, or the nonce is longer than 255 bytes, since their lengths are stored
+    /// in a single byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        bytes.extend_from_slice(FileHeader::MAGIC);
+        bytes.extend([self.version, self.cipher_id]);
+        if self.version == 1 {
+            return bytes;
+        }
+        bytes.extend([self.flags(), self.kdf_id]);
+        for field in [&self.kdf_params, &self.salt, &self.nonce] {
+            bytes.push(u8::try_from(field.len()).expect("header fields are at most 255 bytes"));
+            bytes.extend_from_slice(field);
+        }
+        bytes
+    }
+
+    // This function packs the header's flags into the byte they're stored as.
+    fn flags(&self) -> u8 {
+        [(self.compressed, FileHeader::COMPRESSED), (self.armored, FileHeader::ARMORED), (self.mac, FileHeader::MAC)]
+            .iter()
+            .filter(|(set, _)| *set)
+            .fold(0, |flags, (_, bit)| flags | bit)
+    }
+
+    /// Reads the header from the start of some data, returning it along with the rest of the data.
+    ///
+    /// Data that doesn't start with the magic string has no header, so this gives `None`. A header from a
+    /// version of the format this version doesn't know is rejected with [`CipherError::UnsupportedVersion`], and
+    /// one that's cut short or has flags this version doesn't know with [`CipherError::InvalidInput`].
+    pub fn parse(data: &[u8]) -> Result<Option<(FileHeader, &[u8])>, CipherError> {
+        if data.len() < FileHeader::LEN || !data.starts_with(FileHeader::MAGIC) {
+            return Ok(None);
+        }
+        let mut rest = data;
+        match FileHeader::read_from(&mut rest) {
+            Ok(header) => Ok(Some((header, rest))),
+            // Every header error is a CipherError, and reading from a slice can't fail any other way than by
+            // running out of data.
+            Err(e) => Err(match e.into_inner().and_then(|inner| inner.downcast::<CipherError>().ok()) {
+                Some(e) => *e,
+                None => CipherError::InvalidInput(String::from("the header has been cut short")),
+            }),
+        }
+    }
+
+    /// Writes the header to a stream, like a file about to have the encrypted data written after it.
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&self.to_bytes())
+    }
+
+    /// Reads a header from a stream, leaving it at the start of the encrypted data. This is the only place
+    /// headers are read; [`FileHeader::parse`] uses it too.
+    ///
+    /// Unlike [`FileHeader::parse`], the stream has to start with a header. A stream that ends too soon gives
+    /// an [`io::ErrorKind::UnexpectedEof`] error, and one that doesn't start with the magic string (or is from
+    /// an unknown version of the format) gives an [`io::ErrorKind::InvalidData`] error.
+    pub fn read_from(r: &mut impl Read) -> io::Result<FileHeader> {
+        let invalid = |e: CipherError| io::Error::new(io::ErrorKind::InvalidData, e);
+        let [m0, m1, m2, m3, version, cipher_id] = read_array(r)?;
+        if [m0, m1, m2, m3] != *FileHeader::MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the data doesn't start with a header"));
+        }
+        let mut header = FileHeader::new(cipher_id);
+        match version {
+            1 => {}
+            2 => {
+                let [flags, kdf_id] = read_array(r)?;
+                if flags & !(FileHeader::COMPRESSED | FileHeader::ARMORED | FileHeader::MAC) != 0 {
+                    let message = format!("the header has flags this version doesn't know ({flags:#04x})");
+                    return Err(invalid(CipherError::InvalidInput(message)));
+                }
+                header.version = 2;
+                header.compressed = flags & FileHeader::COMPRESSED != 0;
+                header.armored = flags & FileHeader::ARMORED != 0;
+                header.mac = flags & FileHeader::MAC != 0;
+                header.kdf_id = kdf_id;
+                header.kdf_params = read_field(r)?;
+                header.salt = read_field(r)?;
+                header.nonce = read_field(r)?;
+            }
+            _ => return Err(invalid(CipherError::UnsupportedVersion(version))),
+        }
+        Ok(header)
+    }
+}
+
+// This function reads exactly N bytes from a stream.
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// This function reads one of a header's variable-length fields: a byte giving its length, then that many bytes.
+fn read_field(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let [len] = read_array(r)?;
+    let mut field = vec![0; usize::from(len)];
+    r.read_exact(&mut field)?;
+    Ok(field)
+}
+
+/// The size of an HMAC-SHA256 tag from [`compute_hmac`], in bytes.
+pub const MAC_LEN: usize = 32;
+
+/// Computes the HMAC-SHA256 tag (RFC 2104) of some data, which only someone with the key can make. Ciphers that
+/// don't authenticate what they encrypt, like the classical ones, can have the tag added after their output,
+/// so changes to the file are noticed when it's decrypted.
+///
+/// The key should be one used for nothing else, like one from [`kdf::derive_subkeys`]. Tags must be checked
+/// with [`verify_mac_constant_time`], never `==`.
+///
+/// ```
+/// use rust_project::{compute_hmac, verify_mac_constant_time};
+///
+/// // RFC 4231, test case 2.
+/// let tag = compute_hmac(b"Jefe", b"what do ya want for nothing?");
+/// let hex: String = tag.iter().map(|byte| format!("{byte:02x}")).collect();
+/// assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+/// assert!(verify_mac_constant_time(&tag, &compute_hmac(b"Jefe", b"what do ya want for nothing?")));
+/// assert!(!verify_mac_constant_time(&tag, &compute_hmac(b"Jefe", b"what do ya want for nothing!")));
+/// ```
+pub fn compute_hmac(key: &[u8], data: &[u8]) -> [u8; MAC_LEN] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Checks whether a message authentication code (MAC) tag matches the one that was expected, taking the same
+/// amount of time no matter where (or whether) the tags differ.
+///
+/// Comparing tags with `==` stops at the first byte that differs, so it returns sooner the earlier the
+/// mismatch is. An attacker who can time many attempts can use that to guess a valid tag for a forged message
+/// one byte at a time, without ever knowing the key. Any cipher that authenticates its output must compare tags
+/// with this function rather than `==`.
+///
+/// Tags of different lengths never match. Their lengths aren't secret, so that check isn't constant-time.
+///
+/// ```
+/// use rust_project::verify_mac_constant_time;
+///
+/// let expected = [0x3a, 0x91, 0x5c, 0x07];
+/// assert!(verify_mac_constant_time(&expected, &[0x3a, 0x91, 0x5c, 0x07]));
+/// assert!(!verify_mac_constant_time(&expected, &[0x3b, 0x91, 0x5c, 0x07]));
+/// assert!(!verify_mac_constant_time(&expected, &[0x3a, 0x91, 0x5c]));
+/// ```
+pub fn verify_mac_constant_time(expected: &[u8], actual: &[u8]) -> bool {
+    expected.ct_eq(actual).into()
+}
+
+/// Gives the SHA-256 hash of some data as lowercase hex, the way `sha256sum` prints it, so a file can be
+/// checked against a copy made somewhere else.
+///
+/// ```
+/// use rust_project::sha256_hex;
+///
+/// assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+/// assert_eq!(sha256_hex(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+/// ```
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Formats bytes as a classic hexdump, like `hexdump -C` prints: each line has the offset, 16 bytes in hex, and
+/// the same bytes as ASCII, with anything that isn't a printable ASCII character shown as a dot.
+///
+/// Only the first and last `max_len` bytes are shown (rounded out to whole lines), with a line saying how many
+/// were skipped in between. Data no longer than twice `max_len` is shown in full.
+///
+/// ```
+/// use rust_project::hexdump;
+///
+/// assert_eq!(
+///     hexdump(b"Hello, World!\n\x00\xff\x7f", 256),
+///     "00000000  48 65 6c 6c 6f 2c 20 57  6f 72 6c 64 21 0a 00 ff  |Hello, World!...|\n\
+///      00000010  7f                                                |.|"
+/// );
+/// assert_eq!(hexdump(b"", 256), "");
+///
+/// let dump = hexdump(&[b'a'; 100], 16);
+/// assert_eq!(
+///     dump,
+///     "00000000  61 61 61 61 61 61 61 61  61 61 61 61 61 61 61 61  |aaaaaaaaaaaaaaaa|\n\
+///      ... 64 bytes not shown ...\n\
+///      00000050  61 61 61 61 61 61 61 61  61 61 61 61 61 61 61 61  |aaaaaaaaaaaaaaaa|\n\
+///      00000060  61 61 61 61                                       |aaaa|"
+/// );
+/// ```
+pub fn hexdump(bytes: &[u8], max_len: usize) -> String {
+    let mut lines = Vec::new();
+    if bytes.len() <= max_len.saturating_mul(2) {
+        hexdump_lines(bytes, 0, &mut lines);
+    } else {
+        // Show whole lines at both ends, with the end's offsets lined up the same as the start's.
+        let head_len = max_len.div_ceil(16) * 16;
+        let tail_start = ((bytes.len() - max_len) / 16 * 16).max(head_len);
+        hexdump_lines(&bytes[..head_len], 0, &mut lines);
+        lines.push(format!("... {} bytes not shown ...", tail_start - head_len));
+        hexdump_lines(&bytes[tail_start..], tail_start, &mut lines);
+    }
+    lines.join("\n")
+}
+
+/// Adds the hexdump lines for some bytes that start at `offset` in the data.
+fn hexdump_lines(bytes: &[u8], offset: usize, lines: &mut Vec<String>) {
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let mut hex = String::with_capacity(49);
+        for column in 0..16 {
+            // An extra space splits the line into two groups of eight, to make it easier to count along.
+            if column == 8 {
+                hex.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => hex.push_str(&format!("{byte:02x} ")),
+                None => hex.push_str("   "),
+            }
+        }
+        let ascii: String =
+            line.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }).collect();
+        lines.push(format!("{:08x}  {hex} |{ascii}|", offset + i * 16));
+    }
+}
+
+/// Known answers for [`rot13`]: each plaintext paired with the ciphertext it must encrypt to.
+pub const ROT13_VECTORS: [(&str, &str); 3] = [
+    ("Hello", "Uryyb"),
+    ("Hello, World!", "Uryyb, Jbeyq!"),
+    ("The Quick Brown Fox Jumps Over The Lazy Dog", "Gur Dhvpx Oebja Sbk Whzcf Bire Gur Ynml Qbt"),
+];
+
+/// Known answers for [`rot13_5`]: each plaintext paired with the ciphertext it must encrypt to.
+pub const ROT13_5_VECTORS: [(&str, &str); 3] = [
+    ("abc123", "nop678"),
+    ("0123456789", "5678901234"),
+    ("Room 101, 9:45pm", "Ebbz 656, 4:90cz"),
+];
+
+/// Known answers for [`rot13_unicode`]: each plaintext paired with the ciphertext it must encrypt to.
+pub const ROT13_UNICODE_VECTORS: [(&str, &str); 4] = [
+    ("Привет, мир!", "Яаштхв, ьша!"),
+    ("Γεια σου κόσμε", "Ορφν ζγθ χόζωρ"),
+    ("שלום", "לשעא"),
+    ("Hello, Мир", "Uryyb, Ьша"),
+];
+
+/// Known answers for [`rotate_by`]: each plaintext and shift paired with the ciphertext it must encrypt to.
+pub const CAESAR_VECTORS: [(&str, i32, &str); 3] = [
+    ("Hello", 3, "Khoor"),
+    ("xyz XYZ", 3, "abc ABC"),
+    ("Attack at dawn!", 25, "Zsszbj zs czvm!"),
+];
+
+/// Known answers for [`Caesar`] over the other [`NAMED_ALPHABETS`]: each plaintext, alphabet name, and shift
+/// paired with the ciphertext it must encrypt to.
+pub const CAESAR_ALPHABET_VECTORS: [(&str, &str, i32, &str); 2] = [
+    ("Привет, мир!", "cyrillic", 3, "Тулезх, плу!"),
+    ("Mañana, señor", "spanish", 2, "Ñbóbob, ufópt"),
+];
+
+/// Known answers for [`affine_encrypt`]: each plaintext and pair of `a` and `b` values paired with the
+/// ciphertext it must encrypt to.
+pub const AFFINE_VECTORS: [(&str, i32, i32, &str); 2] = [
+    ("AFFINE CIPHER", 5, 8, "IHHWVC SWFRCP"),
+    ("Hello, World!", 7, 3, "Afccx, Bxscy!"),
+];
+
+/// Known answers for [`rail_fence_encrypt`]: each plaintext and number of rails paired with the ciphertext it
+/// must encrypt to.
+pub const RAIL_FENCE_VECTORS: [(&str, usize, &str); 2] = [
+    ("WEAREDISCOVEREDFLEEATONCE", 3, "WECRLTEERDSOEEFEAOCAIVDEN"),
+    ("Hello, World!", 4, "H !e,Wdloollr"),
+];
+
+/// Known answers for [`columnar_encrypt`]: each plaintext and key paired with the ciphertext it must encrypt to.
+pub const COLUMNAR_VECTORS: [(&str, &str, &str); 2] = [
+    ("WEAREDISCOVEREDFLEEATONCE", "ZEBRAS", "EVLNACDTESEAROFODEECWIREE"),
+    ("Hello, World!", "BOOKKEEPER", "Hl, oloedl!Wr"),
+];
+
+/// Known answers for [`substitute`]: each plaintext paired with its substitution alphabet and the ciphertext
+/// it must encrypt to.
+pub const SUBSTITUTION_VECTORS: [(&str, &str, &str); 2] = [
+    ("Hello, World!", "QWERTYUIOPASDFGHJKLZXCVBNM", "Itssg, Vgksr!"),
+    (
+        "The quick brown fox jumps over the lazy dog.",
+        "QWERTYUIOPASDFGHJKLZXCVBNM",
+        "Zit jxoea wkgvf ygb pxdhl gctk zit sqmn rgu.",
+    ),
+];
+
+/// Known answers for [`base32_encode`], from RFC 4648: each input paired with the text it must encode to.
+pub const BASE32_VECTORS: [(&[u8], &str); 7] = [
+    (b"", ""),
+    (b"f", "MY======"),
+    (b"fo", "MZXQ===="),
+    (b"foo", "MZXW6==="),
+    (b"foob", "MZXW6YQ="),
+    (b"fooba", "MZXW6YTB"),
+    (b"foobar", "MZXW6YTBOI======"),
+];
+
+/// Known answers for [`Aes256Gcm`], from NIST's GCM test cases 14 and 16: each key, nonce, additional data, and
+/// plaintext paired with the ciphertext and tag it must encrypt to, all in hex.
+pub const AES_256_GCM_VECTORS: [(&str, &str, &str, &str, &str); 2] = [
+    (
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "000000000000000000000000",
+        "",
+        "00000000000000000000000000000000",
+        "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919",
+    ),
+    (
+        "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+        "cafebabefacedbaddecaf888",
+        "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+        "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+         8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662\
+         76fc6ece0f4e1768cddf8853bb2d551b",
+    ),
+];
+
+/// Known answers for [`ChaCha20Poly1305`], from RFC 8439, section 2.8.2: the key, nonce, additional data, and
+/// plaintext paired with the ciphertext and tag it must encrypt to, all in hex.
+pub const CHACHA20_POLY1305_VECTORS: [(&str, &str, &str, &str, &str); 1] = [(
+    "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f",
+    "070000004041424344454647",
+    "50515253c0c1c2c3c4c5c6c7",
+    "4c616469657320616e642047656e746c656d656e206f662074686520636c6173\
+     73206f66202739393a204966204920636f756c64206f6666657220796f75206f\
+     6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73\
+     637265656e20776f756c642062652069742e",
+    "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+     3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+     92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+     3ff4def08e4b7a9de576d26586cec64b6116\
+     1ae10b594f09e26a7e902ecbd0600691",
+)];
+
+/// Checks every cipher against its known answers, both encrypting and decrypting.
+///
+/// Returns the name of each cipher paired with whether all of its answers came out right.
+pub fn run_self_tests() -> Vec<(String, bool)> {
+    let rot13_passed = ROT13_VECTORS.iter().all(|&(plain, cipher)| {
+        rot13(plain) == cipher && rot13(cipher) == plain
+    });
+    let rot13_5_passed = ROT13_5_VECTORS.iter().all(|&(plain, cipher)| {
+        rot13_5(plain) == cipher && rot13_5(cipher) == plain
+    });
+    let rot13_unicode_passed = ROT13_UNICODE_VECTORS.iter().all(|&(plain, cipher)| {
+        rot13_unicode(plain) == cipher && rot13_unicode(cipher) == plain
+    });
+    // "--cipher caesar" goes through Caesar rather than rotate_by, so both are checked.
+    let caesar_passes = |plain: &str, alphabet: &str, shift: i32, cipher: &str| {
+        let caesar = Caesar { shift, alphabet: named_alphabet(alphabet).unwrap_or_default() };
+        caesar.process(plain.as_bytes(), Direction::Encrypt).is_ok_and(|result| result == cipher.as_bytes())
+            && caesar.process(cipher.as_bytes(), Direction::Decrypt).is_ok_and(|result| result == plain.as_bytes())
+    };
+    let caesar_passed = CAESAR_VECTORS.iter().all(|&(plain, shift, cipher)| {
+        rotate_by(plain, shift) == cipher
+            && rotate_by(cipher, -shift) == plain
+            && caesar_passes(plain, "latin", shift, cipher)
+    }) && CAESAR_ALPHABET_VECTORS.iter().all(|&(plain, alphabet, shift, cipher)| {
+        caesar_passes(plain, alphabet, shift, cipher)
+    });
+    let affine_passed = AFFINE_VECTORS.iter().all(|&(plain, a, b, cipher)| {
+        affine_encrypt(plain, a, b).as_deref() == Ok(cipher) && affine_decrypt(cipher, a, b).as_deref() == Ok(plain)
+    });
+    let rail_fence_passed = RAIL_FENCE_VECTORS.iter().all(|&(plain, rails, cipher)| {
+        rail_fence_encrypt(plain, rails).as_deref() == Ok(cipher)
+            && rail_fence_decrypt(cipher, rails).as_deref() == Ok(plain)
+    });
+    let columnar_passed = COLUMNAR_VECTORS.iter().all(|&(plain, key, cipher)| {
+        columnar_encrypt(plain, key).as_deref() == Ok(cipher) && columnar_decrypt(cipher, key).as_deref() == Ok(plain)
+    });
+    let substitution_passed = SUBSTITUTION_VECTORS.iter().all(|&(plain, alphabet, cipher)| {
+        parse_alphabet(alphabet).is_ok_and(|mapping| {
+            substitute(plain, &mapping, false) == cipher && substitute(cipher, &mapping, true) == plain
+        })
+    });
+    let base32_passed = BASE32_VECTORS.iter().all(|&(data, encoded)| {
+        base32_encode(data) == encoded && base32_decode(encoded).as_deref() == Ok(data)
+    });
+    let aes_256_gcm_passed = AES_256_GCM_VECTORS.iter().all(|vector| aead_passes(&Aes256Gcm, vector));
+    let chacha20_poly1305_passed =
+        CHACHA20_POLY1305_VECTORS.iter().all(|vector| aead_passes(&ChaCha20Poly1305, vector));
+    vec![
+        (String::from("rot13"), rot13_passed),
+        (String::from("rot13-5"), rot13_5_passed),
+        (String::from("rot13-unicode"), rot13_unicode_passed),
+        (String::from("caesar"), caesar_passed),
+        (String::from("affine"), affine_passed),
+        (String::from("rail-fence"), rail_fence_passed),
+        (String::from("columnar"), columnar_passed),
+        (String::from("substitution"), substitution_passed),
+        (String::from("base32"), base32_passed),
+        (String::from("aes-256-gcm"), aes_256_gcm_passed),
+        (String::from("chacha20-poly1305"), chacha20_poly1305_passed),
+    ]
+}
+
+// This function checks an authenticated cipher against one known answer: the plaintext has to encrypt to the
+// ciphertext and tag, and they have to decrypt back to it.
+fn aead_passes(cipher: &dyn Cipher, &(key, nonce, aad, plain, sealed): &(&str, &str, &str, &str, &str)) -> bool {
+    let [key, nonce, aad, plain, sealed] = [key, nonce, aad, plain, sealed].map(hex_decode);
+    cipher.encrypt(&key, &nonce, &plain, &aad).is_ok_and(|result| result == sealed)
+        && cipher.decrypt(&key, &nonce, &sealed, &aad).is_ok_and(|result| result == plain)
+}
+
+// This function turns hex into bytes. It's only used on the known answers above, which are all valid hex.
+fn hex_decode(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("the known answers are valid hex"))
+        .collect()
+}
@@ -0,0 +1,3611 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use benchmark::run_benchmark;
+use color::{paint, Color, ColorChoice, Stream};
+use progress::{format_bytes, format_duration, Progress, StatusLine};
+use rayon::prelude::*;
+use recipient::{decrypt_with_private_key, encrypt_for_recipient};
+use x25519::{decrypt_x25519, encrypt_x25519, is_age_key, parse_public_key, parse_secret_key};
+use memmap2::Mmap;
+use config_file::FileDefaults;
+use std::ops::Deref;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde_json::json;
+use log::{debug, info, LevelFilter};
+use rust_project::kdf::{
+    derive_subkeys, kdf_from_bytes, kdf_name, KdfParams, ScryptKdf, ARGON2ID_DEFAULT, PBKDF2_DEFAULT,
+    SCRYPT_INTERACTIVE, SCRYPT_SENSITIVE,
+};
+use rust_project::jwe;
+use rust_project::nonce::NonceRegistry;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rust_project::{
+    compute_hmac, crack_caesar, hexdump, named_alphabet, parse_alphabet, run_self_tests, sha256_hex,
+    verify_mac_constant_time, Affine, Base32, Caesar, ChaCha20Poly1305, Cipher, CipherError, CipherProfile, Columnar,
+    Direction, FileHeader, RailFence, Rot13, Rot13Rot5, Rot13Unicode, SecretKey, Substitution, Zeroizing, MAC_LEN,
+    NAMED_ALPHABETS,
+};
+
+mod benchmark;
+mod color;
+mod completions;
+mod config_file;
+mod progress;
+mod recipient;
+mod wizard;
+mod x25519;
+
+// This struct describes one of the ciphers the app knows, for "--cipher" and "--list-ciphers".
+struct CipherInfo {
+    // The cipher's name, as used by "--cipher" and in the JSON output.
+    name: &'static str,
+    // The number saved in the header of files encrypted with the cipher. This must never change, or older
+    // files won't decrypt properly.
+    id: u8,
+    // A short description of what the cipher does.
+    description: &'static str,
+    // The options that give the cipher its key, if it needs one.
+    key: Option<&'static str>,
+}
+
+// The cipher IDs saved in the header of files encrypted for someone else with "--recipient-key", with an RSA
+// key or an X25519 one. They're kept apart from CIPHERS because they need key files rather than a cipher name.
+const RSA_CIPHER_ID: u8 = 7;
+const X25519_CIPHER_ID: u8 = 8;
+
+// Every cipher the app knows. A new cipher needs to be added here as well as to select_cipher.
+const CIPHERS: [CipherInfo; 10] = [
+    CipherInfo {
+        name: "rot13",
+        id: 1,
+        description: "Rotates every letter 13 places, so it's its own inverse.",
+        key: None,
+    },
+    CipherInfo {
+        name: "affine",
+        id: 2,
+        description: "Turns each letter x into (A * x + B) mod 26.",
+        key: Some("--affine-a A --affine-b B"),
+    },
+    CipherInfo {
+        name: "rail-fence",
+        id: 3,
+        description: "Writes the text in a zig-zag across N rows and reads the rows off.",
+        key: Some("--rails N"),
+    },
+    CipherInfo {
+        name: "columnar",
+        id: 4,
+        description: "Writes the text in rows and reads the columns off in key order.",
+        key: Some("--key KEY"),
+    },
+    CipherInfo {
+        name: "substitution",
+        id: 5,
+        description: "Replaces A to Z with the 26 given letters, in order.",
+        key: Some("--alphabet LETTERS"),
+    },
+    CipherInfo {
+        name: "base32",
+        id: 6,
+        description: "Encodes any file (not just text) as Base32 text.",
+        key: None,
+    },
+    CipherInfo {
+        name: "rot13-5",
+        id: 9,
+        description: "ROT13 for letters and ROT5 for digits, so numbers are hidden too.",
+        key: None,
+    },
+    CipherInfo {
+        name: "rot13-unicode",
+        id: 10,
+        description: "Like rot13, but also rotates Greek, Cyrillic, and Hebrew letters.",
+        key: None,
+    },
+    CipherInfo {
+        name: "caesar",
+        id: 11,
+        description: "Rotates every letter N places through a Latin, Spanish, Cyrillic, or Greek alphabet.",
+        key: Some("--shift N"),
+    },
+    CipherInfo {
+        name: "chacha20-poly1305",
+        id: 12,
+        description: "Real, authenticated encryption, with a key derived from a password.",
+        key: Some("--key PASSWORD"),
+    },
+];
+
+// How many random bytes "gen-key" generates when no "--bytes" is given.
+const DEFAULT_KEY_LENGTH: usize = 32;
+
+// The size of the random salt a password is mixed with, in bytes.
+const SALT_LEN: usize = 16;
+
+// The cipher whose key is derived from a password.
+const PASSWORD_CIPHER: &str = "chacha20-poly1305";
+
+// How big a file "--detect-shift" needs to be for the shift it finds to be trusted, when no "--detect-min-size"
+// is given. With less text than this, the letter frequencies are too uneven to go by.
+const DEFAULT_DETECT_MIN_SIZE: usize = 100;
+
+// How much random text "--benchmark" encrypts and decrypts when no "--size" is given.
+const DEFAULT_BENCHMARK_SIZE: usize = 100 * 1000 * 1000;
+
+// The help text printed by "--help", and when the app is run without any arguments (and not on a terminal).
+const HELP: &str = "\
+Encrypts and decrypts text files with classical ciphers (ROT13 unless another one is picked).
+
+Usage:
+  rust_project [OPTIONS] INFILE OUTFILE
+  rust_project [OPTIONS] INFILE
+  rust_project [OPTIONS] INFILE... OUTDIR
+  rust_project [OPTIONS] --output-dir DIR INFILE...
+  rust_project [OPTIONS] --text TEXT [OUTFILE]
+  rust_project verify [OPTIONS] ENCRYPTED ORIGINAL
+  rust_project gen-key [--bytes N] [--format base64|hex] [--key-file FILE]
+  rust_project completions bash|zsh|fish|powershell
+  rust_project crack [--top N] [--output-dir DIR] INFILE
+  rust_project info [--json] FILE...
+  rust_project --self-test
+  rust_project --benchmark [--size SIZE] [--cipher NAME|all]
+  rust_project           On a terminal, asks for the file to encrypt, where to save it, and the cipher.
+
+Arguments:
+  INFILE                 A file (or with --recursive, a directory) to encrypt/decrypt. Patterns like *.txt work too.
+  OUTFILE                The file to save to, or the directory to save into when there are several input files
+                         or it ends in a /. The directory is made if it isn't there yet.
+                         Without one, a single INFILE is saved next to itself: a name ending in .enc (or the
+                         --suffix EXT) is decrypted with that removed, and anything else is encrypted with it
+                         added. -e or -d picks the direction instead.
+
+Commands:
+  verify                 Decrypt ENCRYPTED in memory and check it matches ORIGINAL byte for byte, without saving
+                         anything. Takes the same cipher and key options as decrypting.
+  gen-key                Print a random key of N bytes (32 unless --bytes is given) from the operating system's
+                         secure random number generator, in Base64 or, with --format hex, in hex. With
+                         --key-file FILE, save it to FILE (readable only by you) instead, for --key-file to read.
+  completions            Print a tab completion script for bash, zsh, fish, or powershell. For bash, add
+                         source <(rust_project completions bash) to ~/.bashrc.
+  crack                  Decrypt INFILE (or --text TEXT) with every Caesar shift from 1 to 25 and print each
+                         result under its shift. With --top N, only print the N that look most like English,
+                         best first. With --output-dir DIR, save each one to DIR/shift-NN-INFILE instead.
+  info                   Show how each FILE was encrypted, from its header: the cipher, the KDF, whether it has
+                         a MAC, and which --profile that is, or custom if it isn't one of them.
+
+Options:
+  -h, --help             Print this help and exit.
+  -V, --version          Print the version and exit.
+      --list-ciphers     List the ciphers, with what each one does and whether it needs a key, and exit.
+  -e, --encrypt          Encrypt the files (the default). --encode does the same.
+  -d, --decrypt          Decrypt the files, with the cipher named in their header if they have one.
+                         --decode does the same.
+      --recipient-key FILE
+                         Encrypt for the holder of a public key instead of with a cipher. The key can be
+                         an X25519 key written like age's (age1...), or an RSA key in PEM format.
+      --private-key FILE Decrypt files encrypted with --recipient-key, using the matching private key.
+      --passphrase-file FILE
+                         The passphrase of an encrypted RSA --private-key, read from FILE.
+  -t, --text TEXT        Encrypt/decrypt TEXT instead of an input file, printing the result if there's no OUTFILE.
+      --newline STYLE    How line endings in the plain text are written: preserve (the default) leaves them as
+                         they are, while lf or crlf changes every one to LF or CRLF. Text is changed before it's
+                         encrypted and after it's decrypted. A lone CR isn't a line ending, and no line ending is
+                         added after a last line that doesn't have one.
+      --no-header        Don't start encrypted files with a header naming the cipher (or look for one).
+      --profile fast|balanced|paranoid|legacy
+                         Pick the cipher and KDF together. fast, balanced, and paranoid are chacha20-poly1305
+                         with scrypt (N = 16384), Argon2id (64 MiB), and Argon2id (256 MiB, 4 passes), and
+                         legacy is rot13. balanced is what chacha20-poly1305 uses when --kdf isn't given.
+                         Replaces --cipher and --kdf if they're given too, with a warning.
+      --output-format FORMAT
+                         binary (the default) writes the app's own format. jwe writes a JSON Web Encryption
+                         compact token (RFC 7516) instead, encrypted with AES-256-GCM (alg dir, enc A256GCM)
+                         using the --key or --key-file as the key itself: 32 bytes in Base64 or hex, like gen-key
+                         makes.
+                         Decrypting a token needs --output-format jwe too.
+      --mac hmac-sha256  Add an HMAC-SHA256 tag to the end of each encrypted file, keyed with the password given
+                         with --key or --key-file, so a changed file is refused when it's decrypted instead of
+                         decrypting to nonsense. Decrypting needs the same --key. Not for columnar, which uses
+                         --key itself, or chacha20-poly1305, which does this already.
+      --kdf argon2id|pbkdf2|scrypt
+                         How chacha20-poly1305 or --mac derives its key from the password (argon2id if not given).
+                         The KDF and its settings go in the header, so decrypting doesn't need this.
+      --scrypt-preset interactive|sensitive
+                         Start from scrypt's settings for logging in (N = 16384, the default) or for files that
+                         need more protection (N = 1048576, which takes about 1 GiB of memory). Implies
+                         --kdf scrypt, like the options below.
+      --scrypt-n N       scrypt's cost, a power of two like 16384. Higher is slower to guess passwords against.
+      --scrypt-r R       scrypt's block size (8 if not given).
+      --scrypt-p P       How many times scrypt does its work in parallel (1 if not given). N × r × p can be at
+                         most that of the sensitive preset.
+      --detect-shift     Decrypt text encrypted with an unknown Caesar shift, picking the shift whose result
+                         looks most like English, and show which shift it was. Implies --decrypt.
+      --detect-min-size SIZE
+                         Warn that the detected shift may be wrong for files smaller than SIZE, like 1KB.
+                         Defaults to 100 bytes.
+  -v, --verbose          Print every step, with the sizes of the files and how long each step took.
+  -vv                    Also show a hexdump of the start and end of each file, and the cipher's settings.
+                         These details are logged to stderr. RUST_LOG (like RUST_LOG=debug) overrides -v.
+      --show-contents    Print the whole of each input and output file. Control characters and bytes that
+                         aren't UTF-8 are escaped.
+  -q, --quiet            Print nothing unless something goes wrong. Can't be used with -v.
+      --color WHEN       Color errors, warnings, and successes: auto (the default, only on a terminal and
+                         without NO_COLOR set), always, or never.
+      --json             Print a JSON object for each file (one per line) instead of text, errors included.
+  -f, --force            Overwrite output files that already exist. Without it, an output file that's already
+                         there is an error.
+      --no-overwrite     Refuse to overwrite output files (the default), even if the config file sets force.
+      --skip-existing    Leave out input files whose output file already exists, instead of failing.
+      --backup           Before overwriting an output file (with --force or --in-place), copy it to the same
+                         name with .bak added. A .bak file that's already there is only replaced with --force.
+      --dry-run          Check every file and show where it would be saved, without reading or writing any.
+      --in-place         Save each file over itself instead of to another file. Every path is a file to read.
+      --shred            Once each output file is saved, overwrite its input file with random bytes and delete it.
+      --shred-passes N   Overwrite each input file N times before deleting it, instead of once. Implies --shred.
+      --fail-fast        Stop at the first file that fails instead of carrying on.
+  -j, --jobs N           Process up to N files at once (defaults to the number of CPUs). --threads does the same.
+  -p, --preserve, --preserve-time
+                         Give each output file the same modification time as its input file.
+      --no-preserve-perms
+                         Don't give each output file the same permissions as its input file.
+      --checksum         Show the SHA-256 hash of each input and output file.
+      --config FILE      Read default options from FILE instead of the usual config file (see below).
+      --no-config        Don't read any config file.
+      --log PATH         Add a line to PATH for every file (or --text) processed, failures included, as a JSON
+                         object with the time, operation, cipher, paths, sizes, and result. --log-file PATH
+                         does the same.
+      --max-file-size SIZE
+                         Refuse to read an input file bigger than SIZE, like 1GB. For something without a size,
+                         like a pipe or /dev/random, reading stops as soon as more than SIZE has been read.
+      --mmap             Map every input file into memory instead of reading it, when possible.
+      --no-mmap          Never map input files into memory. By default, files of 256 MiB or more are mapped.
+  -w, --watch            Keep running, processing the input file again every time it changes.
+      --self-test        Check every cipher against known answers instead of processing files.
+      --no-glob          Treat * and ? in input paths as ordinary characters.
+  -r, --recursive        Process every file inside input directories.
+      --exclude PATTERN  Skip files found in input directories that match PATTERN. Can be given more than once.
+      --include PATTERN  Only use files found in input directories that match PATTERN. Can be given more than once.
+      --exclude-hidden   Skip files and directories whose names start with a dot.
+      --output-dir DIR   Save every output file into DIR, named after its input file.
+      --ext EXT          The extension added when encrypting (and removed when decrypting) in DIR. Defaults to enc.
+      --suffix EXT       Like --ext, but output files are saved next to their input files if no OUTFILE is given.
+      --benchmark        Measure how fast the ciphers are on random text instead of processing files.
+      --size SIZE        How much random text to benchmark on, like 100MB or 64KiB. Defaults to 100MB.
+  -c, --cipher NAME      The cipher to use (see below). When benchmarking, all benchmarks every cipher.
+  Options can go before, after, or between the files. Short options that don't take a value can share one dash,
+  and everything after -- is a file, even if it starts with a dash.
+
+Ciphers (picked with --cipher NAME, or just by giving their settings):
+  rot13                  The default. Rotates every letter 13 places, so it's its own inverse.
+  affine                 --affine-a A --affine-b B. Turns each letter x into (A * x + B) mod 26.
+  rail-fence             --rails N. Writes the text in a zig-zag across N rows and reads the rows off.
+  columnar               --key KEY, or --key-file FILE to read the key from FILE. Without either, the key is
+                         asked for on the terminal. Writes the text in rows and reads the columns off in key
+                         order. --columnar is short for --cipher columnar.
+  substitution           --alphabet LETTERS. Replaces A to Z with the 26 LETTERS, in order.
+  base32                 Encodes any file (not just text) as Base32 text. --base32 is short for --cipher base32.
+  rot13-5                Like rot13, but also rotates every digit 5 places, so numbers are hidden too.
+  rot13-unicode          Like rot13, but also rotates Greek, Cyrillic (А to Я), and Hebrew letters halfway
+                         through their own alphabets. Hebrew has 27 letters, so its last letter, ת, is left as it
+                         is. Other characters, like accents and Ё, are left alone. --unicode is short for
+                         --cipher rot13-unicode.
+  caesar                 --shift N, and optionally --alphabet-name latin|spanish|cyrillic|greek (latin if not
+                         given). Rotates every letter of the alphabet N places through it, keeping its case. The
+                         spanish alphabet has ñ and the accented vowels, and the cyrillic one has ё.
+  chacha20-poly1305      --key PASSWORD, or --key-file FILE. Without either, the password is asked for on the
+                         terminal. Real encryption: the key is derived from the password (see --kdf), and
+                         changing any byte of the file makes decrypting fail. Needs the header.
+
+Examples:
+  rust_project notes.txt notes.txt.enc
+  rust_project notes.txt.enc
+  rust_project --decrypt --affine-a 5 --affine-b 8 secret.enc secret.txt
+  rust_project --cipher columnar --key ZEBRAS notes.txt notes.txt.enc
+  rust_project --recursive --exclude '*.log' --output-dir encrypted documents
+
+Config file:
+  Options used every time can go in a TOML file, rust_project/config.toml in the user's config directory
+  ($XDG_CONFIG_HOME or ~/.config, or %APPDATA% on Windows). Each key is an option's long name, like
+  cipher = \"base32\", suffix = \"enc\", or quiet = true. Options on the command line take priority. The keys are
+  cipher, ext, suffix, quiet, verbose, force, fail-fast, threads, color, json, checksum, preserve,
+  preserve-perms, header, exclude-hidden, and log.
+
+Environment variables:
+  RUST_PROJECT_CIPHER    The cipher to use, like --cipher.
+  RUST_PROJECT_SUFFIX    The extension to add, like --suffix.
+  RUST_PROJECT_KEY       The key, like --key, but without it showing up in the list of running processes.
+  RUST_PROJECT_VERBOSE   Set to true (or 1) to print every step, like -v.
+  These take priority over the config file, and options on the command line take priority over them.
+
+Exit codes:
+  0                      Everything worked.
+  1                      Something else went wrong, or verify found the files don't match.
+  2                      The options or arguments were wrong, or a setting the file needs wasn't given.
+  3                      An input file couldn't be read, or changed while it was being read.
+  4                      An output file couldn't be written, or already exists.
+  5                      Encrypting/decrypting failed, like with the wrong key or a damaged file, or --self-test
+                         found a cipher giving wrong answers.
+  130                    Interrupted with Ctrl-C. Output files that were only partly written are removed.
+  With several files, the exit code is the one for the first file that failed.
+";
+
+// The codes the app exits with, so scripts can tell what kind of problem there was. They're listed in HELP.
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_INPUT: i32 = 3;
+const EXIT_OUTPUT: i32 = 4;
+const EXIT_CIPHER: i32 = 5;
+// Shells report a program killed by Ctrl-C (SIGINT, signal 2) as 128 + 2, so the app does the same.
+const EXIT_INTERRUPTED: i32 = 130;
+
+// Files are read and written this many bytes at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Input files at least this big are mapped into memory instead of read, unless "--no-mmap" is given.
+const MMAP_THRESHOLD: u64 = 256 * 1024 * 1024;
+
+// Text at least this big is split into pieces that are encrypted/decrypted on several threads at once, when
+// the cipher allows it. Smaller text isn't worth the trouble of starting the threads.
+const PARALLEL_THRESHOLD: usize = 8 * 1024 * 1024;
+
+// The size of each piece when text is split up to be encrypted/decrypted on several threads.
+const PARALLEL_CHUNK_SIZE: usize = 1024 * 1024;
+
+// In watch mode, the input file has to stop changing for this long before it gets processed again.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+// How much of the start and the end of each file "-vv" shows, in bytes.
+const PREVIEW_SIZE: usize = 256;
+
+// Every nonce used to encrypt anything while the program runs comes from here, so none can be used twice, even
+// across a batch of files on several threads.
+static NONCES: LazyLock<NonceRegistry> = LazyLock::new(NonceRegistry::new);
+
+// The temporary files being written right now (several at once with several threads), so they can be removed
+// if the user presses Ctrl-C before they're finished.
+static PARTIAL_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// Where to tell the watch loop to stop when the user presses Ctrl-C, while there is one.
+static WATCH_STOP: Mutex<Option<mpsc::Sender<WatchEvent>>> = Mutex::new(None);
+
+// This enum lists how much detail is printed about each file. The levels are in order, so a level shows
+// everything the ones before it show.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Verbosity {
+    // A one line summary for each file.
+    Normal,
+    // Each step as it happens, with the sizes of the files and how long each step took ("-v").
+    Verbose,
+    // The start of each file's contents and the cipher's settings as well ("-vv").
+    VeryVerbose,
+}
+
+// This enum lists the ways "gen-key" can write out the key it generates.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum KeyFormat {
+    // Standard Base64, with padding.
+    Base64,
+    // Two lowercase hex digits for each byte.
+    Hex,
+}
+
+// This enum lists what "--newline" can do to the line endings in the plain text.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Newline {
+    // Leave every line ending as it is, even when they're mixed.
+    Preserve,
+    // Change every line ending to "\n".
+    Lf,
+    // Change every line ending to "\r\n".
+    Crlf,
+}
+
+// This enum lists the formats "--output-format" can write encrypted files in (and read them back from).
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum OutputFormat {
+    // The app's own format: a header, then whatever the cipher gives.
+    Binary,
+    // A JSON Web Encryption (RFC 7516) compact token, encrypted with AES-256-GCM and a key given directly.
+    Jwe,
+}
+
+// This struct holds the options given to the app on the command line.
+#[derive(Clone)]
+struct Config {
+    // The files to encrypt/decrypt, after any glob patterns have been expanded.
+    in_file_paths: Vec<String>,
+    // Text to encrypt/decrypt instead of reading any files.
+    text: Option<String>,
+    // The file to save to, or the directory to save into when there are several input files.
+    // This is empty when an output directory was given instead.
+    out_file_path: Option<String>,
+    // The directory to save into, naming each output file after its input file plus an extension.
+    output_dir: Option<String>,
+    // The extension added to encrypted files (and removed from decrypted files) in the output directory.
+    ext: String,
+    // Whether output files are named by adding or removing the extension even outside an output directory.
+    // With no output path given, each output file is then saved next to its input file.
+    suffix: bool,
+    // Whether each file is saved over itself instead of to another file.
+    in_place: bool,
+    // How many times to overwrite each input file with random bytes before deleting it, once its output has been
+    // saved, or None to leave the input files alone.
+    shred_passes: Option<usize>,
+    // Whether to only check and show what would be done, without changing anything.
+    dry_run: bool,
+    // Whether the files are being encrypted or decrypted.
+    // ROT13 is its own inverse so the text comes out the same either way, but the output file names differ.
+    mode: Direction,
+    // How much detail to print about each file.
+    verbosity: Verbosity,
+    // Whether to print nothing at all unless something goes wrong.
+    quiet: bool,
+    // Whether to expand glob patterns (like "*.txt") in the input paths.
+    glob: bool,
+    // Whether input directories are searched for files to encrypt/decrypt.
+    recursive: bool,
+    // Glob patterns for files found in input directories that should be skipped.
+    exclude: Vec<String>,
+    // Glob patterns for files found in input directories that should be used. If there are none, every file is.
+    include: Vec<String>,
+    // Whether files and directories found in input directories are skipped when their name starts with a dot.
+    exclude_hidden: bool,
+    // Whether existing output files may be overwritten.
+    force: bool,
+    // Whether files whose output file already exists are left alone instead of failing.
+    skip_existing: bool,
+    // Whether an output file that's about to be overwritten is copied to a ".bak" file first.
+    backup: bool,
+    // Whether to stop at the first file that fails instead of carrying on with the rest.
+    fail_fast: bool,
+    // How many threads can process files (or pieces of a big file) at the same time.
+    threads: usize,
+    // When to color what's printed.
+    color: ColorChoice,
+    // Whether to describe each file as a JSON object for scripts, instead of printing text for people.
+    json: bool,
+    // Whether to print the whole of each input and output file.
+    show_contents: bool,
+    // Whether to give the output file the same modification time as the input file.
+    preserve: bool,
+    // Whether to give the output file the same permissions as the input file.
+    preserve_perms: bool,
+    // Whether to work out the SHA-256 hash of each input and output file, so they can be checked later.
+    checksum: bool,
+    // The file to add a line to for every file processed, for auditing.
+    log: Option<String>,
+    // The size from which input files are mapped into memory instead of read, or None to never map them.
+    mmap_threshold: Option<u64>,
+    // The biggest input file that's read, or None for no limit.
+    max_file_size: Option<u64>,
+    // What to do to the line endings in the plain text.
+    newline: Newline,
+    // The format encrypted files are written in, and read back from.
+    output_format: OutputFormat,
+    // The name of the cipher to use, like "rot13". When benchmarking, this can also be "all".
+    cipher: String,
+    // The multiplier and shift for the affine cipher.
+    affine_a: Option<i32>,
+    affine_b: Option<i32>,
+    // The number of rails for the rail fence cipher.
+    rails: Option<usize>,
+    // How many places the Caesar cipher rotates each letter.
+    shift: Option<i32>,
+    // The name of the alphabet the Caesar cipher rotates letters through, like "spanish", or None for "latin".
+    alphabet_name: Option<String>,
+    // The letters that replace "A" to "Z" in the substitution cipher.
+    alphabet: Option<Zeroizing<[char; 26]>>,
+    // The key for ciphers that need one, or the password a key is derived from.
+    key: Option<Zeroizing<String>>,
+    // How a key is derived from the password, for ciphers whose key comes from one.
+    kdf: KdfParams,
+    // The RSA public key file to encrypt files for, instead of using a cipher.
+    recipient_key: Option<String>,
+    // The RSA private key file to decrypt files encrypted with "--recipient-key".
+    private_key: Option<String>,
+    // The passphrase the RSA private key file is encrypted with, if it is.
+    passphrase: Option<Zeroizing<String>>,
+    // Whether encrypted files start with a header naming the cipher, so decrypting can pick it automatically.
+    header: bool,
+    // Whether encrypted files end with an HMAC-SHA256 tag, keyed with the password, so changes to them are noticed.
+    mac: bool,
+    // Whether to keep running after the file is processed, processing it again every time it changes.
+    watch: bool,
+    // Whether to print the help text instead of encrypting/decrypting any files.
+    help: bool,
+    // Whether to print the version instead of encrypting/decrypting any files.
+    version: bool,
+    // Whether to list the ciphers instead of encrypting/decrypting any files.
+    list_ciphers: bool,
+    // Whether to check the ciphers against known answers instead of encrypting/decrypting any files.
+    self_test: bool,
+    // Whether to measure how fast the ciphers are instead of encrypting/decrypting any files.
+    benchmark: bool,
+    // How many bytes of random text to benchmark the ciphers on.
+    benchmark_size: usize,
+    // Whether to check that the first input file decrypts to the second instead of writing any files.
+    verify: bool,
+    // Whether to generate a random key instead of encrypting/decrypting any files.
+    gen_key: bool,
+    // How many random bytes "gen-key" generates.
+    key_length: usize,
+    // How "gen-key" writes out the key.
+    key_format: KeyFormat,
+    // The file "gen-key" saves the key to, instead of printing it.
+    key_out: Option<String>,
+    // The shell to print a completion script for instead of encrypting/decrypting any files.
+    completions: Option<String>,
+    // Whether to decrypt with whichever Caesar shift makes the input look most like English, instead of a cipher.
+    detect_shift: bool,
+    // How many bytes of input "--detect-shift" needs before the shift it finds is trusted without a warning.
+    detect_min_size: usize,
+    // Whether to try every Caesar shift on the input instead of encrypting/decrypting it.
+    crack: bool,
+    // How many of the most likely shifts "crack" keeps, or None to keep all of them in order.
+    top: Option<usize>,
+    // Whether to show how the input files were encrypted instead of encrypting/decrypting them.
+    info: bool,
+    // Warnings about the options, like options that were overridden, to show before doing anything.
+    warnings: Vec<String>,
+}
+
+// This struct holds some facts about a file that was encrypted/decrypted, for reporting back to the user.
+struct Stats {
+    // The size of the file that was read, in bytes.
+    bytes_in: usize,
+    // The size of the file that was written, in bytes.
+    bytes_out: usize,
+    // The SHA-256 hashes of the file that was read and the file that was written, in hex, if --checksum was given.
+    checksum_in: Option<String>,
+    checksum_out: Option<String>,
+    // How long reading the input, encrypting/decrypting it, and writing the output each took.
+    timings: Timings,
+    // The Caesar shift the input was decrypted with, if it was found with --detect-shift.
+    shift: Option<i32>,
+}
+
+// This struct is how long each phase of processing a file took.
+#[derive(Clone, Copy)]
+struct Timings {
+    read: Duration,
+    transform: Duration,
+    write: Duration,
+}
+
+impl Timings {
+    // This function gives how long all the phases took together.
+    fn total(&self) -> Duration {
+        self.read + self.transform + self.write
+    }
+}
+
+// This enum lists the ways encrypting/decrypting a single file can fail.
+#[derive(Debug)]
+enum EncryptError {
+    // Reading or writing a file failed.
+    Io(io::Error),
+    // Reading the input file at the path failed.
+    Read(PathBuf, io::Error),
+    // The input file is bigger than "--max-file-size" allows. For something read as a stream, `size` is how much
+    // was read before giving up.
+    FileTooLarge { path: PathBuf, size: u64, limit: u64 },
+    // Writing the output file at the path failed.
+    Write(PathBuf, io::Error),
+    // The output file is already there, and overwriting it wasn't allowed.
+    OutputAlreadyExists(PathBuf),
+    // The cipher couldn't encrypt/decrypt the text, for example because of a bad key.
+    Cipher(CipherError),
+    // Watching the input file for changes failed.
+    Watch(notify::Error),
+    // The file's header names a cipher this version of the app doesn't know.
+    UnknownCipher(u8),
+    // The file's header names a cipher, but the settings it needs (like a key) weren't given.
+    MissingSettings { cipher: &'static str, missing: &'static str },
+    // The input file was changed by something else while it was mapped into memory and being processed.
+    InputChanged(PathBuf),
+    // The input and output paths lead to the same file, but "--in-place" wasn't given.
+    SameFile(PathBuf),
+    // The file's MAC doesn't match its contents, so either the password is wrong or the file has been changed.
+    MacMismatch,
+}
+
+// This lets the error be printed for the user with "{}".
+impl fmt::Display for EncryptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncryptError::Io(e) => write!(f, "{e}"),
+            EncryptError::Read(path, e) => write!(f, "could not read {}: {e}", path.display()),
+            EncryptError::Write(path, e) => write!(f, "could not write {}: {e}", path.display()),
+            EncryptError::OutputAlreadyExists(path) => {
+                write!(f, "Output file {} already exists; use --force to overwrite.", path.display())
+            }
+            EncryptError::FileTooLarge { path, size, limit } => {
+                let path = path.display();
+                write!(f, "{path} is at least {size} bytes, more than --max-file-size allows ({limit} bytes)")
+            }
+            EncryptError::Cipher(e) => write!(f, "{e}"),
+            EncryptError::Watch(e) => write!(f, "could not watch for changes: {e}"),
+            EncryptError::UnknownCipher(id) => write!(f, "the file was encrypted with an unknown cipher (ID {id})"),
+            EncryptError::MissingSettings { cipher, missing } => {
+                write!(f, "the file was encrypted with the {cipher} cipher. {missing}")
+            }
+            EncryptError::InputChanged(path) => {
+                write!(f, "{} was changed while it was being read, so nothing was saved; try again.", path.display())
+            }
+            EncryptError::SameFile(path) => {
+                write!(f, "input and output are the same file ({}); use --in-place to overwrite it.", path.display())
+            }
+            EncryptError::MacMismatch => {
+                write!(f, "the file's MAC doesn't match, so the password is wrong or the file has been changed")
+            }
+        }
+    }
+}
+
+impl EncryptError {
+    // This function gives a short name for the kind of error, for scripts reading the JSON output.
+    fn kind(&self) -> &'static str {
+        match self {
+            EncryptError::Io(_) | EncryptError::Read(..) | EncryptError::Write(..) => "io",
+            EncryptError::OutputAlreadyExists(_) => "output_exists",
+            EncryptError::FileTooLarge { .. } => "file_too_large",
+            EncryptError::Cipher(_) => "cipher",
+            EncryptError::Watch(_) => "watch",
+            EncryptError::UnknownCipher(_) => "unknown_cipher",
+            EncryptError::MissingSettings { .. } => "missing_settings",
+            EncryptError::InputChanged(_) => "input_changed",
+            EncryptError::SameFile(_) => "same_file",
+            EncryptError::MacMismatch => "mac_mismatch",
+        }
+    }
+
+    // This function gives the code the app should exit with because of the error.
+    fn exit_code(&self) -> i32 {
+        match self {
+            EncryptError::Io(_) | EncryptError::Watch(_) => EXIT_FAILURE,
+            EncryptError::MissingSettings { .. } | EncryptError::SameFile(_) => EXIT_USAGE,
+            EncryptError::Read(..) | EncryptError::InputChanged(_) | EncryptError::FileTooLarge { .. } => EXIT_INPUT,
+            EncryptError::Write(..) | EncryptError::OutputAlreadyExists(_) => EXIT_OUTPUT,
+            EncryptError::Cipher(_) | EncryptError::UnknownCipher(_) | EncryptError::MacMismatch => EXIT_CIPHER,
+        }
+    }
+
+    // These functions give back a function for "map_err" that turns an I/O error into one from reading or
+    // writing the file at `path`, so the message says which file it was.
+    fn reading(path: impl AsRef<Path>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        move |e| EncryptError::Read(path, e)
+    }
+
+    fn writing(path: impl AsRef<Path>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        move |e| EncryptError::Write(path, e)
+    }
+
+    // These functions mark an I/O error as having happened while reading the input file or writing the output
+    // file at `path`, so the app exits with the right code. Any other error is left as it is.
+    fn while_reading(self, path: &str) -> Self {
+        match self {
+            EncryptError::Io(e) => EncryptError::Read(PathBuf::from(path), e),
+            other => other,
+        }
+    }
+
+    fn while_writing(self, path: &str) -> Self {
+        match self {
+            EncryptError::Io(e) => EncryptError::Write(PathBuf::from(path), e),
+            other => other,
+        }
+    }
+}
+
+impl Error for EncryptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EncryptError::Io(e) | EncryptError::Read(_, e) | EncryptError::Write(_, e) => Some(e),
+            EncryptError::OutputAlreadyExists(_) => None,
+            EncryptError::FileTooLarge { .. } => None,
+            EncryptError::Cipher(e) => Some(e),
+            EncryptError::Watch(e) => Some(e),
+            EncryptError::UnknownCipher(_) => None,
+            EncryptError::MissingSettings { .. } => None,
+            EncryptError::InputChanged(_) => None,
+            EncryptError::SameFile(_) => None,
+            EncryptError::MacMismatch => None,
+        }
+    }
+}
+
+// This lets "?" turn I/O errors into an EncryptError automatically.
+impl From<io::Error> for EncryptError {
+    fn from(e: io::Error) -> Self {
+        EncryptError::Io(e)
+    }
+}
+
+// This lets "?" turn cipher errors into an EncryptError automatically.
+impl From<CipherError> for EncryptError {
+    fn from(e: CipherError) -> Self {
+        EncryptError::Cipher(e)
+    }
+}
+
+// This is the function that will run on start-up.
+fn main() {
+    // Collect the arguments given to the app on the command line.
+    let mut args: Vec<String> = env::args().collect();
+
+    // Without any arguments (the name of the app is always the first one), the user probably doesn't know
+    // how to run the app. On a terminal, ask them what to do instead. Otherwise, show them how to run it, since
+    // a script waiting on questions nobody will answer would hang. This still counts as a failure, since
+    // nothing got done.
+    if args.len() < 2 {
+        if !io::stdin().is_terminal() {
+            eprint!("{HELP}");
+            process::exit(EXIT_USAGE);
+        }
+        let answers = wizard::ask(&mut io::stdin().lock(), &mut io::stderr()).unwrap_or_else(|e| {
+            print_error(false, "usage", &format!("Could not read the answer: {e}"));
+            process::exit(EXIT_USAGE);
+        });
+        args.extend(answers);
+    }
+
+    // Parse the arguments into the files to encrypt/decrypt, an output path, and the option flags.
+    // If there is an error, print it to stderr and exit the process with an error code.
+    // The arguments haven't been parsed yet, so look for the JSON flag by hand in case they can't be.
+    // Options from the config file are read first, so the command line can override them.
+    let parsed = config_file::load(&args).and_then(|defaults| parse_args(&args, &defaults));
+    let mut config = parsed.unwrap_or_else(|err| {
+        print_error(args.iter().any(|arg| arg == "--json"), "usage", &err);
+        process::exit(EXIT_USAGE);
+    });
+    color::set_choice(config.color);
+    init_logging(&config);
+    for warning in &config.warnings {
+        eprintln!("{} {warning}", paint("Warning:", Color::Yellow, Stream::Stderr));
+    }
+    handle_interrupts();
+
+    // Show the user how to run the app.
+    if config.help {
+        print!("{HELP}");
+        return;
+    }
+
+    // Tell the user which build of the app this is, including the git commit if it was built from git.
+    if config.version {
+        match option_env!("GIT_HASH") {
+            Some(hash) => println!("{} {} ({hash})", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            None => println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        }
+        return;
+    }
+
+    // Show the user which ciphers they can pick from.
+    if config.list_ciphers {
+        for cipher in &CIPHERS {
+            if config.json {
+                println!("{}", json!({
+                    "name": cipher.name,
+                    "description": cipher.description,
+                    "requires_key": cipher.key.is_some(),
+                    "key_options": cipher.key,
+                }));
+            } else {
+                let key = cipher.key.map_or(String::from("no key"), |options| format!("key: {options}"));
+                println!("{:<19}{:<34}{}", cipher.name, key, cipher.description);
+            }
+        }
+        return;
+    }
+
+    // Check every cipher against its known answers, so the user knows the program can be trusted.
+    // If any of them are wrong, exit the process with the code for a cipher failing.
+    if config.self_test {
+        let mut all_passed = true;
+        for (cipher, passed) in run_self_tests() {
+            // With --quiet, only failures are printed, and they go to stderr like any other problem.
+            if passed {
+                if !config.quiet {
+                    println!("{} {cipher}", paint("PASS", Color::Green, Stream::Stdout));
+                }
+            } else if config.quiet {
+                eprintln!("{} {cipher}", paint("FAIL", Color::Red, Stream::Stderr));
+                all_passed = false;
+            } else {
+                println!("{} {cipher}", paint("FAIL", Color::Red, Stream::Stdout));
+                all_passed = false;
+            }
+        }
+        process::exit(if all_passed { 0 } else { EXIT_CIPHER });
+    }
+
+    // Measure how fast the ciphers are on this computer, without touching any files.
+    if config.benchmark {
+        if let Err(err) = run_benchmark(&config) {
+            print_error(config.json, "benchmark", &err);
+            process::exit(EXIT_FAILURE);
+        }
+        return;
+    }
+
+    // Print a tab completion script for the user's shell.
+    if let Some(shell) = &config.completions {
+        print!("{}", completions::script(shell).expect("the shell was checked when parsing the arguments"));
+        return;
+    }
+
+    // Make a random key instead of touching any files.
+    if config.gen_key {
+        if let Err(e) = run_gen_key(&config) {
+            print_error(config.json, e.kind(), &e.to_string());
+            process::exit(e.exit_code());
+        }
+        return;
+    }
+
+    // Check an encrypted file against its original without writing anything.
+    if config.verify {
+        match run_verify(&config.in_file_paths[0], &config.in_file_paths[1], &config) {
+            Ok(true) => return,
+            Ok(false) => process::exit(EXIT_FAILURE),
+            Err(e) => {
+                print_error(config.json, e.kind(), &e.to_string());
+                process::exit(e.exit_code());
+            }
+        }
+    }
+
+    // Try every Caesar shift on a file encrypted with an unknown one.
+    if config.crack {
+        if let Err(e) = run_crack(&config) {
+            print_error(config.json, e.kind(), &e.to_string());
+            process::exit(e.exit_code());
+        }
+        return;
+    }
+
+    // Show how files were encrypted, without decrypting them.
+    if config.info {
+        if let Err(e) = run_info(&config) {
+            print_error(config.json, e.kind(), &e.to_string());
+            process::exit(e.exit_code());
+        }
+        return;
+    }
+
+    // Encrypt/decrypt the text given on the command line instead of reading a file.
+    if let Some(text) = &config.text {
+        let result = run_literal(text, config.out_file_path.as_deref(), &config);
+        append_log(None, config.out_file_path.as_deref(), &result, &config);
+        if let Err(e) = result {
+            print_error(config.json, e.kind(), &e.to_string());
+            process::exit(e.exit_code());
+        }
+        return;
+    }
+
+    // Shells like cmd on Windows do not expand patterns like "*.txt", so we do it ourselves.
+    if config.glob {
+        config.in_file_paths = expand_globs(&config.in_file_paths).unwrap_or_else(|err| {
+            print_error(config.json, "usage", &err.to_string());
+            process::exit(EXIT_USAGE);
+        });
+    }
+
+    // Pair up every input file with the path it should be saved to.
+    let mut plan = plan_jobs(&config).unwrap_or_else(|err| {
+        print_error(config.json, "usage", &err.to_string());
+        process::exit(EXIT_USAGE);
+    });
+    let planned = plan.jobs.len();
+    if config.skip_existing {
+        skip_existing(&mut plan, &config);
+    }
+
+    // Watching only makes sense for a single file.
+    if config.watch && plan.jobs.len() != 1 {
+        print_error(config.json, "usage", "--watch only works with a single input file.");
+        process::exit(EXIT_USAGE);
+    }
+
+    // Show what would be done instead of doing it, if asked to.
+    if config.dry_run {
+        if let Some(code) = dry_run(&plan, &config) {
+            process::exit(code);
+        }
+        return;
+    }
+
+    // Recreate the directory structure on the output side before writing anything into it.
+    for dir in &plan.dirs {
+        if let Err(e) = fs::create_dir_all(dir) {
+            print_error(config.json, "io", &format!("could not create {}: {e}", dir.display()));
+            process::exit(EXIT_OUTPUT);
+        }
+    }
+
+    // Keep count of how many files worked, so a problem with one file doesn't stop the others. Files left out
+    // by "--skip-existing" count as skipped.
+    let mut processed = 0;
+    let mut skipped = planned - plan.jobs.len();
+    // The exit code is the one for the first file that failed, if any did.
+    let mut exit_code = None;
+
+    // Several files can be processed at once on different threads. Verbose mode (and --show-contents) prints
+    // every step as it happens, which would be a jumble from several threads at once, so it sticks to one file
+    // at a time.
+    let threads = if config.verbosity >= Verbosity::Verbose || config.show_contents { 1 } else { config.threads.min(plan.jobs.len()) };
+    if threads > 1 {
+        // Nothing gets printed while the threads are working. Instead, everything gets printed at the end.
+        let silent_config = Config { quiet: true, ..config.clone() };
+        let outcomes = run_parallel(&plan.jobs, &silent_config, threads);
+        // The files finish in whatever order the threads get to them, so sort them by path to always print
+        // the summary the same way.
+        let mut finished: Vec<_> = plan.jobs.iter().zip(outcomes).collect();
+        finished.sort_by(|((a, _), _), ((b, _), _)| a.cmp(b));
+        for ((in_file_path, out_file_path), outcome) in finished {
+            // With --fail-fast, files that weren't started because another one failed are left out.
+            let Some((result, elapsed)) = outcome else { continue };
+            let out_file_path = out_file_path.to_string_lossy();
+            announce(in_file_path, &out_file_path, &config);
+            append_log(Some(in_file_path), Some(&out_file_path), &result, &config);
+            if report(in_file_path, &out_file_path, &result, elapsed, &config) {
+                processed += 1;
+            } else {
+                skipped += 1;
+                exit_code = exit_code.or(result.err().map(|e| e.exit_code()));
+            }
+        }
+    } else {
+        for (in_file_path, out_file_path) in &plan.jobs {
+            let out_file_path = out_file_path.to_string_lossy();
+            announce(in_file_path, &out_file_path, &config);
+
+            // Encrypt/decrypt the file and write it to the output file.
+            // If there is an error, print it to stderr and move on to the next file (unless failing fast).
+            let start = Instant::now();
+            let result = run(in_file_path, &out_file_path, &config);
+            append_log(Some(in_file_path), Some(&out_file_path), &result, &config);
+            if report(in_file_path, &out_file_path, &result, start.elapsed(), &config) {
+                processed += 1;
+            } else {
+                skipped += 1;
+                exit_code = exit_code.or(result.err().map(|e| e.exit_code()));
+                if config.fail_fast {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Print some output for the user to know the program has completed.
+    if !config.quiet && (config.recursive || planned > 1) {
+        println!("{processed} files processed, {skipped} skipped.")
+    }
+    info!("Program completed.");
+
+    // If anything went wrong, exit the process with an error code.
+    if let Some(code) = exit_code {
+        process::exit(code);
+    }
+
+    // Keep the output up to date with the input until the user presses Ctrl-C.
+    if config.watch {
+        let (in_file_path, out_file_path) = &plan.jobs[0];
+        if let Err(e) = watch_and_encrypt(&config, in_file_path, out_file_path) {
+            print_error(config.json, e.kind(), &e.to_string());
+            process::exit(e.exit_code());
+        }
+    }
+}
+
+// This function sets up logging, which is how the details of what's going on are printed (to stderr). RUST_LOG
+// picks which messages are shown, like "RUST_LOG=debug". Without it, "-v" shows what each step did and "-vv"
+// shows what went into it as well, and other crates only show their warnings.
+fn init_logging(config: &Config) {
+    let level = match config.verbosity {
+        _ if config.quiet => LevelFilter::Error,
+        Verbosity::Normal => LevelFilter::Warn,
+        Verbosity::Verbose => LevelFilter::Info,
+        Verbosity::VeryVerbose => LevelFilter::Debug,
+    };
+    let default_filter = format!("warn,{}={level}", module_path!());
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .format(|out, record| writeln!(out, "{}", record.args()))
+        .init();
+}
+
+// This function sets up what happens when the user presses Ctrl-C. While watching, the watch stops once the file
+// being processed (if any) is done. Otherwise, the app exits straight away, first removing any output files that
+// were only partly written, so they can't be mistaken for finished ones.
+fn handle_interrupts() {
+    let result = ctrlc::set_handler(|| {
+        if let Some(sender) = &*WATCH_STOP.lock().unwrap_or_else(PoisonError::into_inner) {
+            let _ = sender.send(WatchEvent::Stop);
+            return;
+        }
+        // The list stays locked until the app exits, so no other thread can start a new file in the meantime.
+        let partial_files = PARTIAL_FILES.lock().unwrap_or_else(PoisonError::into_inner);
+        if !partial_files.is_empty() {
+            eprintln!("Interrupted; removing partial output.");
+            for path in partial_files.iter() {
+                let _ = fs::remove_file(path);
+            }
+        }
+        process::exit(EXIT_INTERRUPTED);
+    });
+    // Without the handler, Ctrl-C still stops the app, just without tidying up.
+    if let Err(e) = result {
+        debug!("Could not handle Ctrl-C: {e}");
+    }
+}
+
+// This struct keeps a temporary file on the list of partly written files for as long as it's around.
+struct PartialFile(PathBuf);
+
+impl PartialFile {
+    fn track(path: &Path) -> PartialFile {
+        PARTIAL_FILES.lock().unwrap_or_else(PoisonError::into_inner).push(path.to_path_buf());
+        PartialFile(path.to_path_buf())
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        let mut partial_files = PARTIAL_FILES.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(i) = partial_files.iter().position(|path| *path == self.0) {
+            partial_files.swap_remove(i);
+        }
+    }
+}
+
+// This enum is sent to the watch loop to tell it what happened.
+enum WatchEvent {
+    // The input file changed.
+    Changed,
+    // The user pressed Ctrl-C.
+    Stop,
+}
+
+// This function watches an input file for changes, encrypting/decrypting it to the output file again every
+// time it's saved, until the user presses Ctrl-C.
+fn watch_and_encrypt(config: &Config, in_file_path: &str, out_file_path: &Path) -> Result<(), EncryptError> {
+    // The output file is ours to replace now.
+    let config = Config { force: true, ..config.clone() };
+    let in_path = Path::new(in_file_path);
+    let in_file_name = in_path.file_name().map(|name| name.to_os_string());
+
+    // Both the file watcher and the Ctrl-C handler send their news down the same channel.
+    let (sender, receiver) = mpsc::channel();
+    *WATCH_STOP.lock().unwrap_or_else(PoisonError::into_inner) = Some(sender.clone());
+
+    // Editors often save a file by writing a new one and renaming it over the old one, which a watch on the file
+    // itself would miss. Watching the directory it's in catches that too, as long as we only pay attention
+    // to changes to the input file.
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let Ok(event) = result else { return };
+        let relevant = matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+            && event.paths.iter().any(|path| path.file_name() == in_file_name.as_deref());
+        if relevant {
+            let _ = sender.send(WatchEvent::Changed);
+        }
+    }).map_err(EncryptError::Watch)?;
+    let dir = match in_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive).map_err(EncryptError::Watch)?;
+
+    if !config.quiet {
+        println!("Watching {in_file_path} for changes. Press Ctrl-C to stop.");
+    }
+    let verb = match config.mode {
+        Direction::Encrypt => "Re-encrypted",
+        Direction::Decrypt => "Re-decrypted",
+    };
+    while let Ok(WatchEvent::Changed) = receiver.recv() {
+        // A single save can show up as several changes in quick succession, so wait until things have been
+        // quiet for a moment before doing anything.
+        loop {
+            match receiver.recv_timeout(WATCH_DEBOUNCE) {
+                Ok(WatchEvent::Changed) => continue,
+                Ok(WatchEvent::Stop) | Err(RecvTimeoutError::Disconnected) => {
+                    if !config.quiet {
+                        println!("Stopping watch.");
+                    }
+                    return Ok(());
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+
+        // A failure (like the file being halfway through a save) shouldn't stop the watch.
+        let result = run(in_file_path, &out_file_path.to_string_lossy(), &config);
+        append_log(Some(in_file_path), Some(&out_file_path.to_string_lossy()), &result, &config);
+        match result {
+            Ok(_) => {
+                if !config.quiet {
+                    let now = format_timestamp(SystemTime::now());
+                    println!("{now} {verb} {in_file_path} \u{2192} {}", out_file_path.display());
+                }
+            }
+            Err(e) => print_error(config.json, e.kind(), &e.to_string()),
+        }
+    }
+
+    if !config.quiet {
+        println!("Stopping watch.");
+    }
+    Ok(())
+}
+
+// This function formats a time as an ISO 8601 timestamp in UTC, like "2024-01-01T12:00:00Z".
+fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    let (days, seconds) = ((seconds / 86400) as i64, seconds % 86400);
+    // Turn the number of days since 1970 into a year, month, and day.
+    // This is Howard Hinnant's "civil_from_days" algorithm, which counts in 400 year eras starting from March,
+    // so that leap days fall at the end of each year.
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+// This function prints some output for the user displaying which files are about to be used.
+fn announce(in_file_path: &str, out_file_path: &str, config: &Config) {
+    if config.verbosity >= Verbosity::Verbose {
+        info!("File to encrypt/decrypt: {in_file_path}");
+        info!("File to save to: {out_file_path}");
+    } else if !config.quiet {
+        print!("Encrypting/decrypting {in_file_path} to {out_file_path}...")
+    }
+}
+
+// This function prints some output for the user to know how processing a file went.
+// It returns whether the file was processed successfully.
+fn report(
+    in_file_path: &str,
+    out_file_path: &str,
+    result: &Result<Stats, EncryptError>,
+    elapsed: Duration,
+    config: &Config,
+) -> bool {
+    match result {
+        Ok(stats) => {
+            let warning =
+                stats.shift.and_then(|_| short_input_warning(in_file_path, in_file_path, stats.bytes_in, config));
+            if config.json {
+                println!("{}", json!({
+                    "status": "ok",
+                    "input": in_file_path,
+                    "output": out_file_path,
+                    "cipher": cipher_name(config),
+                    "detected_shift": stats.shift,
+                    "warning": warning,
+                    "bytes_in": stats.bytes_in,
+                    "bytes_out": stats.bytes_out,
+                    "duration_ms": elapsed.as_millis() as u64,
+                    "read_ms": stats.timings.read.as_secs_f64() * 1000.0,
+                    "transform_ms": stats.timings.transform.as_secs_f64() * 1000.0,
+                    "write_ms": stats.timings.write.as_secs_f64() * 1000.0,
+                    "bytes_per_second": throughput(stats.bytes_in as u64, stats.timings.total()),
+                    "sha256_in": stats.checksum_in,
+                    "sha256_out": stats.checksum_out,
+                }));
+            } else {
+                if config.verbosity == Verbosity::Normal && !config.quiet {
+                    println!("{}", paint("success.", Color::Green, Stream::Stdout));
+                    if let Some(shift) = stats.shift {
+                        println!("  Detected shift: {shift}");
+                    }
+                    if let (Some(checksum_in), Some(checksum_out)) = (&stats.checksum_in, &stats.checksum_out) {
+                        println!("  SHA-256 of {in_file_path}: {checksum_in}");
+                        println!("  SHA-256 of {out_file_path}: {checksum_out}");
+                    }
+                }
+                if let Some(warning) = warning {
+                    eprintln!("{} {warning}", paint("Warning:", Color::Yellow, Stream::Stderr));
+                }
+            }
+            true
+        }
+        Err(e) => {
+            if config.json {
+                println!("{}", json!({
+                    "status": "error",
+                    "kind": e.kind(),
+                    "message": e.to_string(),
+                    "input": in_file_path,
+                    "output": out_file_path,
+                }));
+            } else {
+                // An output file that's already there was only skipped, so it's a warning more than an error.
+                let color = match e {
+                    EncryptError::OutputAlreadyExists(_) => Color::Yellow,
+                    _ => Color::Red,
+                };
+                if config.verbosity == Verbosity::Normal && !config.quiet {
+                    println!("{}", paint("failed.", color, Stream::Stdout))
+                }
+                eprintln!("{} {e}", paint("Error:", color, Stream::Stderr));
+            }
+            false
+        }
+    }
+}
+
+// This function shows what would be done to every file without doing it: which directories would be created,
+// and where each file would be saved. Each file is checked the same way it would be for real (it has to be
+// there and readable, and its output can't be overwritten without "--force"), but the input is only opened
+// and nothing gets written. It returns the exit code for the first file that would fail, if any would.
+fn dry_run(plan: &Plan, config: &Config) -> Option<i32> {
+    let verb = match config.mode {
+        Direction::Encrypt => "encrypt",
+        Direction::Decrypt => "decrypt",
+    };
+    if !config.quiet {
+        // The same directory can be in the plan twice, spelled with and without a slash on the end.
+        let mut shown: Vec<&PathBuf> = Vec::new();
+        for dir in plan.dirs.iter().filter(|dir| !dir.is_dir()) {
+            if !shown.iter().any(|other| other.components().eq(dir.components())) {
+                println!("Would create directory {}", dir.display());
+                shown.push(dir);
+            }
+        }
+    }
+    let mut exit_code = None;
+    for (in_file_path, out_file_path) in &plan.jobs {
+        let out_file_path = out_file_path.to_string_lossy();
+        let checked = fs::File::open(in_file_path)
+            .and_then(|file| file.metadata())
+            .and_then(|metadata| match metadata.is_dir() {
+                true => Err(io::Error::from(io::ErrorKind::IsADirectory)),
+                false => Ok(()),
+            })
+            .map_err(EncryptError::reading(in_file_path))
+            .and_then(|()| check_output(in_file_path, &out_file_path, config));
+        match checked {
+            Ok(_) if config.json => println!("{}", json!({
+                "status": "planned",
+                "operation": verb,
+                "input": in_file_path,
+                "output": out_file_path,
+            })),
+            Ok(_) if !config.quiet => {
+                println!("Would {verb} {in_file_path} -> {out_file_path}");
+                if config.backup && Path::new(&*out_file_path).exists() {
+                    println!("Would back up {out_file_path} to {}", backup_path_for(&out_file_path).display());
+                }
+                if config.shred_passes.is_some() {
+                    println!("Would shred {in_file_path}");
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if config.json {
+                    println!("{}", json!({
+                        "status": "error",
+                        "kind": e.kind(),
+                        "message": e.to_string(),
+                        "input": in_file_path,
+                        "output": out_file_path,
+                    }));
+                } else {
+                    eprintln!("{} {in_file_path}: {e}", paint("Error:", Color::Red, Stream::Stderr));
+                }
+                exit_code = exit_code.or(Some(e.exit_code()));
+            }
+        }
+    }
+    exit_code
+}
+
+// This function adds a line about a processed file to the log file given with "--log" (or "--log-file"), so
+// there's a record of everything that was done. Each line is a JSON object (the JSON Lines format) with the
+// time, whether the file was encrypted or decrypted, the cipher, both paths, and either the sizes of the files
+// or what went wrong. Text given with "--text" has no input path, and neither does a printed result have an
+// output path, so they're logged as null. Not being able to write to the log doesn't undo the work that was
+// done, so it's only warned about.
+fn append_log(
+    in_file_path: Option<&str>,
+    out_file_path: Option<&str>,
+    result: &Result<Stats, EncryptError>,
+    config: &Config,
+) {
+    let Some(log_path) = &config.log else { return };
+    let mut entry = json!({
+        "timestamp": format_timestamp(SystemTime::now()),
+        "operation": match config.mode {
+            Direction::Encrypt => "encrypt",
+            Direction::Decrypt => "decrypt",
+        },
+        "cipher": cipher_name(config),
+        "input": in_file_path,
+        "output": out_file_path,
+    });
+    match result {
+        Ok(stats) => {
+            entry["result"] = json!("ok");
+            entry["bytes_in"] = json!(stats.bytes_in);
+            entry["bytes_out"] = json!(stats.bytes_out);
+        }
+        Err(e) => {
+            entry["result"] = json!("error");
+            entry["kind"] = json!(e.kind());
+            entry["message"] = json!(e.to_string());
+        }
+    }
+    // The whole line is written at once, so lines from several files (or copies of the app) don't get mixed up.
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .and_then(|mut file| file.write_all(format!("{entry}\n").as_bytes()));
+    if let Err(e) = written {
+        eprintln!("{} could not write to the log file {log_path}: {e}", paint("Warning:", Color::Yellow, Stream::Stderr));
+    }
+}
+
+// This function processes files on a pool of several threads at once. Each thread keeps taking the next file
+// nobody has started on yet until there are none left, so a thread that gets a small file doesn't sit around
+// waiting. The results come back in the same order as the files. A file that was never started (because
+// another one failed and --fail-fast was given) has no result.
+fn run_parallel(
+    jobs: &[(String, PathBuf)],
+    config: &Config,
+    threads: usize,
+) -> Vec<Option<(Result<Stats, EncryptError>, Duration)>> {
+    let any_failed = AtomicBool::new(false);
+    let process = |(in_file_path, out_file_path): &(String, PathBuf)| {
+        if config.fail_fast && any_failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let start = Instant::now();
+        let result = run(in_file_path, &out_file_path.to_string_lossy(), config);
+        if result.is_err() {
+            any_failed.store(true, Ordering::Relaxed);
+        }
+        Some((result, start.elapsed()))
+    };
+    // If the pool can't be started, the files still get processed, just on rayon's shared pool instead.
+    match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => pool.install(|| jobs.par_iter().map(process).collect()),
+        Err(_) => jobs.par_iter().map(process).collect(),
+    }
+}
+
+// This function prints an error message to stderr. When JSON output was asked for, it's printed to stdout as a
+// JSON object instead, with a short name for the kind of error, so scripts find it with the rest of the output.
+fn print_error(json: bool, kind: &str, message: &str) {
+    if json {
+        println!("{}", json!({ "status": "error", "kind": kind, "message": message }));
+    } else {
+        eprintln!("{} {message}", paint("Error:", Color::Red, Stream::Stderr));
+    }
+}
+
+// This function parses the arguments given on the command line into the files to read,
+// the path to write to, and the option flags.
+// Options not given on the command line come from `defaults` (read from the config file) when they're there.
+fn parse_args(args: &[String], defaults: &FileDefaults) -> Result<Config, String> {
+    // Anything starting with a dash is an option flag, everything else is a path. They can come in any order.
+    let mut paths = Vec::new();
+    let mut text = None;
+    let mut verbosity = Verbosity::Normal;
+    let mut quiet = false;
+    let mut glob = true;
+    let mut recursive = false;
+    let mut exclude = Vec::new();
+    let mut include = Vec::new();
+    let mut exclude_hidden = defaults.exclude_hidden.unwrap_or(false);
+    let mut force = defaults.force.unwrap_or(false);
+    let mut skip_existing = false;
+    let mut backup = false;
+    let mut fail_fast = defaults.fail_fast.unwrap_or(false);
+    // By default, use as many threads as the computer has logical CPUs.
+    let mut threads = defaults.threads.unwrap_or_else(num_cpus::get);
+    let mut color = defaults.color.unwrap_or(ColorChoice::Auto);
+    let mut json = defaults.json.unwrap_or(false);
+    let mut help = false;
+    let mut version = false;
+    let mut list_ciphers = false;
+    let mut self_test = false;
+    let mut benchmark = false;
+    let mut benchmark_size = DEFAULT_BENCHMARK_SIZE;
+    let mut cipher = None;
+    let mut preserve = defaults.preserve.unwrap_or(false);
+    let mut preserve_perms = defaults.preserve_perms.unwrap_or(true);
+    let mut show_contents = false;
+    let mut checksum = defaults.checksum.unwrap_or(false);
+    let mut log = defaults.log.clone();
+    let mut mmap_threshold = Some(MMAP_THRESHOLD);
+    let mut max_file_size = None;
+    let mut newline = Newline::Preserve;
+    let mut output_format = OutputFormat::Binary;
+    let mut affine_a = None;
+    let mut affine_b = None;
+    let mut rails = None;
+    let mut shift = None;
+    let mut alphabet_name = None;
+    let mut alphabet = None;
+    let mut key = None;
+    let mut key_file = false;
+    let mut header = defaults.header.unwrap_or(true);
+    let mut kdf = None;
+    let mut scrypt_preset = None;
+    let mut mac = false;
+    let mut profile = None;
+    let mut scrypt_n = None;
+    let mut scrypt_r = None;
+    let mut scrypt_p = None;
+    let mut recipient_key = None;
+    let mut private_key = None;
+    let mut passphrase = None;
+    let mut watch = false;
+    let mut output_dir = None;
+    let mut ext = defaults.suffix.clone().or_else(|| defaults.ext.clone()).unwrap_or_else(|| String::from("enc"));
+    let mut suffix = defaults.suffix.is_some();
+    let mut in_place = false;
+    let mut shred_passes = None;
+    let mut dry_run = false;
+    let mut mode = Direction::Encrypt;
+    let mut mode_given = false;
+    // "verify", "gen-key", "completions" and "crack" are commands rather than options, so they have to come first.
+    let verify = args.get(1).is_some_and(|arg| arg == "verify");
+    let gen_key = args.get(1).is_some_and(|arg| arg == "gen-key");
+    let completions_command = args.get(1).is_some_and(|arg| arg == "completions");
+    let crack = args.get(1).is_some_and(|arg| arg == "crack");
+    let info = args.get(1).is_some_and(|arg| arg == "info");
+    let mut completions = None;
+    let mut key_length = DEFAULT_KEY_LENGTH;
+    let mut key_format = KeyFormat::Base64;
+    let mut key_out = None;
+    let mut top = None;
+    let mut detect_shift = false;
+    let mut detect_min_size = DEFAULT_DETECT_MIN_SIZE;
+    let first = if verify || gen_key || completions_command || crack || info { 2 } else { 1 };
+    let mut args: VecDeque<String> = args[first..].iter().cloned().collect();
+    while let Some(arg) = args.pop_front() {
+        if arg == "--" {
+            // Everything after "--" is a path, even if it starts with a dash.
+            paths.extend(args.drain(..));
+            break;
+        } else if arg == "-t" || arg == "--text" {
+            // The text to encrypt/decrypt is given as the next argument.
+            text = Some(args.pop_front().ok_or("--text needs some text.")?);
+        } else if arg == "--output-dir" {
+            // The directory is given as the next argument.
+            output_dir = Some(args.pop_front().ok_or("--output-dir needs a directory.")?);
+        } else if arg == "--ext" {
+            // The extension is given as the next argument, with or without its leading dot.
+            ext = args.pop_front().ok_or("--ext needs an extension.")?.trim_start_matches('.').to_string();
+            if ext.is_empty() {
+                return Err(String::from("--ext needs an extension."));
+            }
+        } else if arg == "--config" {
+            // The config file was already read before parsing the rest of the arguments.
+            args.pop_front();
+        } else if arg == "--no-config" {
+            // The config file was already skipped.
+        } else if arg == "--dry-run" {
+            // Only show what would be done.
+            dry_run = true;
+        } else if arg == "--in-place" {
+            // Save each file over itself.
+            in_place = true;
+        } else if arg == "--shred" {
+            // Destroy each input file once its output is saved, overwriting it once unless told otherwise.
+            shred_passes = shred_passes.or(Some(1));
+        } else if arg == "--shred-passes" {
+            // The number of passes is given as the next argument, and implies "--shred".
+            let value = &args.pop_front().ok_or("--shred-passes needs a number.")?;
+            match value.parse() {
+                Ok(0) | Err(_) => return Err(String::from("--shred-passes needs a whole number of at least 1.")),
+                Ok(passes) => shred_passes = Some(passes),
+            }
+        } else if arg == "--suffix" {
+            // Like "--ext", but the output files don't have to go in an output directory.
+            ext = args.pop_front().ok_or("--suffix needs an extension.")?.trim_start_matches('.').to_string();
+            if ext.is_empty() {
+                return Err(String::from("--suffix needs an extension."));
+            }
+            suffix = true;
+        } else if arg == "--affine-a" {
+            // The affine cipher's multiplier is given as the next argument.
+            let value = &args.pop_front().ok_or("--affine-a needs a number.")?;
+            affine_a = Some(value.parse().map_err(|_| "--affine-a needs a whole number.")?);
+        } else if arg == "--affine-b" {
+            // The affine cipher's shift is given as the next argument.
+            let value = &args.pop_front().ok_or("--affine-b needs a number.")?;
+            affine_b = Some(value.parse().map_err(|_| "--affine-b needs a whole number.")?);
+        } else if arg == "--rails" {
+            // The number of rails for the rail fence cipher is given as the next argument.
+            let value = &args.pop_front().ok_or("--rails needs a number.")?;
+            rails = Some(value.parse().map_err(|_| "--rails needs a whole number.")?);
+        } else if arg == "--shift" {
+            // How many places the Caesar cipher rotates each letter is given as the next argument.
+            let value = &args.pop_front().ok_or("--shift needs a number.")?;
+            shift = Some(value.parse().map_err(|_| "--shift needs a whole number.")?);
+        } else if arg == "--alphabet-name" {
+            // The name of the alphabet for the Caesar cipher is given as the next argument.
+            let value = &args.pop_front().ok_or("--alphabet-name needs the name of an alphabet.")?;
+            if named_alphabet(value).is_none() {
+                let names: Vec<&str> = NAMED_ALPHABETS.iter().map(|(name, _)| *name).collect();
+                return Err(format!("--alphabet-name needs one of: {}.", names.join(", ")));
+            }
+            alphabet_name = Some(value.clone());
+        } else if arg == "--columnar" {
+            // Short for "--cipher columnar".
+            cipher = Some(String::from("columnar"));
+        } else if arg == "--alphabet" {
+            // The substitution alphabet is given as the next argument.
+            let value = &args.pop_front().ok_or("--alphabet needs 26 letters.")?;
+            let mapping = parse_alphabet(value).map_err(|_| "--alphabet needs all 26 letters, each exactly once.")?;
+            alphabet = Some(Zeroizing::new(mapping));
+        } else if arg == "--base32" {
+            // Short for "--cipher base32".
+            cipher = Some(String::from("base32"));
+        } else if arg == "--unicode" {
+            // Short for "--cipher rot13-unicode".
+            cipher = Some(String::from("rot13-unicode"));
+        } else if arg == "-k" || arg == "--key" {
+            // The key is given as the next argument.
+            if key_file {
+                return Err(String::from("--key and --key-file can't be used together; pick one."));
+            }
+            key = Some(Zeroizing::new(args.pop_front().ok_or("--key needs a key.")?));
+        } else if arg == "--key-file" {
+            // The file the key is in is given as the next argument.
+            if key.is_some() {
+                return Err(String::from("--key and --key-file can't be used together; pick one."));
+            }
+            let path = &args.pop_front().ok_or("--key-file needs a file.")?;
+            // A generated key is saved to the file instead.
+            if gen_key {
+                key_out = Some(path.clone());
+            } else {
+                key = Some(read_key_file(path)?);
+            }
+            key_file = true;
+        } else if arg == "--detect-shift" {
+            // Work out the Caesar shift to decrypt with.
+            detect_shift = true;
+        } else if arg == "--detect-min-size" {
+            // The size is given as the next argument, like "1KB".
+            let value = &args.pop_front().ok_or("--detect-min-size needs a size.")?;
+            detect_min_size = parse_size(value).ok_or("--detect-min-size needs a size, like 100 or 1KB.")?;
+        } else if arg == "--top" {
+            // How many of the most likely shifts "crack" keeps is given as the next argument.
+            let value = &args.pop_front().ok_or("--top needs a number.")?;
+            match value.parse() {
+                Ok(0) | Err(_) => return Err(String::from("--top needs a whole number of at least 1.")),
+                Ok(n) => top = Some(n),
+            }
+        } else if arg == "--bytes" {
+            // How many random bytes "gen-key" generates is given as the next argument.
+            let value = &args.pop_front().ok_or("--bytes needs a number.")?;
+            key_length = value.parse().map_err(|_| "--bytes needs a whole number.")?;
+            if key_length == 0 {
+                return Err(String::from("--bytes needs to be at least 1."));
+            }
+        } else if arg == "--newline" {
+            // What to do to line endings is given as the next argument.
+            newline = match args.pop_front().ok_or("--newline needs preserve, lf, or crlf.")?.as_str() {
+                "preserve" => Newline::Preserve,
+                "lf" => Newline::Lf,
+                "crlf" => Newline::Crlf,
+                value => return Err(format!("Unknown --newline {value}; use preserve, lf, or crlf.")),
+            };
+        } else if arg == "--format" {
+            // How "gen-key" writes out the key is given as the next argument.
+            key_format = match args.pop_front().ok_or("--format needs base64 or hex.")?.as_str() {
+                "base64" => KeyFormat::Base64,
+                "hex" => KeyFormat::Hex,
+                value => return Err(format!("Unknown --format {value}; use base64 or hex.")),
+            };
+        } else if arg == "--output-format" {
+            // The format is given as the next argument.
+            output_format = match args.pop_front().ok_or("--output-format needs binary or jwe.")?.as_str() {
+                "binary" => OutputFormat::Binary,
+                "jwe" => OutputFormat::Jwe,
+                value => return Err(format!("Unknown --output-format {value}; use binary or jwe.")),
+            };
+        } else if arg == "--recipient-key" {
+            // The public key file is given as the next argument.
+            recipient_key = Some(args.pop_front().ok_or("--recipient-key needs a public key file.")?);
+        } else if arg == "--private-key" {
+            // The private key file is given as the next argument.
+            private_key = Some(args.pop_front().ok_or("--private-key needs a private key file.")?);
+        } else if arg == "--passphrase-file" {
+            // The file the private key's passphrase is in is given as the next argument.
+            passphrase = Some(read_key_file(&args.pop_front().ok_or("--passphrase-file needs a file.")?)?);
+        } else if arg == "--kdf" {
+            // How to derive a key from the password is given as the next argument.
+            kdf = match args.pop_front().ok_or("--kdf needs one of: argon2id, pbkdf2, scrypt.")?.as_str() {
+                "argon2id" => Some(KdfParams::Argon2id(ARGON2ID_DEFAULT)),
+                "pbkdf2" => Some(KdfParams::Pbkdf2(PBKDF2_DEFAULT)),
+                "scrypt" => Some(KdfParams::Scrypt(scrypt_settings(SCRYPT_INTERACTIVE))),
+                value => return Err(format!("Unknown --kdf {value}; use argon2id, pbkdf2, or scrypt.")),
+            };
+        } else if arg == "--scrypt-preset" {
+            // The preset is given as the next argument.
+            scrypt_preset = match args.pop_front().ok_or("--scrypt-preset needs interactive or sensitive.")?.as_str() {
+                "interactive" => Some(SCRYPT_INTERACTIVE),
+                "sensitive" => Some(SCRYPT_SENSITIVE),
+                value => return Err(format!("Unknown --scrypt-preset {value}; use interactive or sensitive.")),
+            };
+        } else if arg == "--scrypt-n" {
+            // scrypt's cost is given as the next argument. scrypt only works with powers of two.
+            let value = &args.pop_front().ok_or("--scrypt-n needs a number.")?;
+            match value.parse::<u64>() {
+                Ok(n) if n > 1 && n.is_power_of_two() => scrypt_n = Some(n),
+                _ => return Err(String::from("--scrypt-n needs a power of two of at least 2, like 16384.")),
+            }
+        } else if arg == "--scrypt-r" || arg == "--scrypt-p" {
+            // scrypt's block size or parallelism is given as the next argument.
+            let value = &args.pop_front().ok_or_else(|| format!("{arg} needs a number."))?;
+            let number = match value.parse::<u32>() {
+                Ok(0) | Err(_) => return Err(format!("{arg} needs a whole number of at least 1.")),
+                Ok(number) => number,
+            };
+            if arg == "--scrypt-r" {
+                scrypt_r = Some(number);
+            } else {
+                scrypt_p = Some(number);
+            }
+        } else if arg == "--profile" {
+            // The profile's name is given as the next argument.
+            let value = &args.pop_front().ok_or("--profile needs one of: fast, balanced, paranoid, legacy.")?;
+            let unknown = || format!("Unknown --profile {value}; use fast, balanced, paranoid, or legacy.");
+            profile = Some(CipherProfile::named(value).ok_or_else(unknown)?);
+        } else if arg == "--mac" {
+            // The kind of MAC is given as the next argument. HMAC-SHA256 is the only one so far.
+            match args.pop_front().ok_or("--mac needs hmac-sha256.")?.as_str() {
+                "hmac-sha256" => mac = true,
+                value => return Err(format!("Unknown --mac {value}; use hmac-sha256.")),
+            }
+        } else if arg == "--no-header" {
+            // Leave the header off encrypted files, and don't look for one when decrypting.
+            header = false;
+        } else if arg == "-e" || arg == "--encrypt" || arg == "--encode" {
+            mode = Direction::Encrypt;
+            mode_given = true;
+        } else if arg == "-d" || arg == "--decrypt" || arg == "--decode" {
+            mode = Direction::Decrypt;
+            mode_given = true;
+        } else if arg == "-q" || arg == "--quiet" {
+            // Only print errors.
+            quiet = true;
+        } else if arg == "-r" || arg == "--recursive" {
+            // Accept directories as inputs.
+            recursive = true;
+        } else if arg == "--exclude" {
+            // The pattern is given as the next argument. This flag can be given more than once.
+            exclude.push(args.pop_front().ok_or("--exclude needs a pattern.")?);
+        } else if arg == "--include" {
+            // The pattern is given as the next argument. This flag can be given more than once.
+            include.push(args.pop_front().ok_or("--include needs a pattern.")?);
+        } else if arg == "--exclude-hidden" {
+            // Skip dotfiles and dot directories.
+            exclude_hidden = true;
+        } else if arg == "-f" || arg == "--force" {
+            // Allow existing output files to be overwritten.
+            force = true;
+        } else if arg == "--no-overwrite" {
+            // Refuse to overwrite existing output files, even if the config file says otherwise.
+            force = false;
+        } else if arg == "--skip-existing" {
+            // Leave out files whose output file is already there.
+            skip_existing = true;
+        } else if arg == "--backup" {
+            // Keep a copy of every output file that gets overwritten.
+            backup = true;
+        } else if arg == "-j" || arg == "--threads" || arg == "--jobs" {
+            // The number of threads to use is given as the next argument.
+            let value = &args.pop_front().ok_or("--jobs needs a number.")?;
+            threads = value.parse().map_err(|_| "--jobs needs a whole number.")?;
+            if threads == 0 {
+                return Err(String::from("--jobs needs to be at least 1."));
+            }
+        } else if arg == "--fail-fast" {
+            // Stop at the first file that fails.
+            fail_fast = true;
+        } else if arg == "--color" {
+            // When to use colors is given as the next argument.
+            let value = &args.pop_front().ok_or("--color needs auto, always, or never.")?;
+            color = ColorChoice::parse(value)
+                .ok_or_else(|| format!("Unknown --color choice {value}; use auto, always, or never."))?;
+        } else if arg == "--json" {
+            // Print a JSON object for each file instead of text.
+            json = true;
+        } else if arg == "-p" || arg == "--preserve" || arg == "--preserve-time" {
+            // Keep the input file's modification time.
+            preserve = true;
+        } else if arg == "--no-preserve-perms" {
+            // Leave the output file with the usual permissions for a new file.
+            preserve_perms = false;
+        } else if arg == "--show-contents" {
+            // Print the files in full.
+            show_contents = true;
+        } else if arg == "--checksum" {
+            // Hash the input and output files.
+            checksum = true;
+        } else if arg == "--log" || arg == "--log-file" {
+            // The log file is given as the next argument.
+            log = Some(args.pop_front().ok_or_else(|| format!("{arg} needs a file."))?);
+        } else if arg == "--max-file-size" {
+            // The size is given as the next argument, like "1GB".
+            let value = &args.pop_front().ok_or("--max-file-size needs a size.")?;
+            let size = parse_size(value).ok_or("--max-file-size needs a size, like 1GB or 500MiB.")?;
+            max_file_size = Some(size as u64);
+        } else if arg == "--mmap" {
+            // Map every input file into memory instead of reading it, however small.
+            mmap_threshold = Some(0);
+        } else if arg == "--no-mmap" {
+            // Never map input files into memory, for filesystems where that's unreliable.
+            mmap_threshold = None;
+        } else if arg == "-w" || arg == "--watch" {
+            // Keep processing the input file every time it changes.
+            watch = true;
+        } else if arg == "-h" || arg == "--help" {
+            // Print the help text instead of doing anything else.
+            help = true;
+        } else if arg == "-V" || arg == "--version" {
+            // Print the version instead of doing anything else.
+            version = true;
+        } else if arg == "--list-ciphers" {
+            // List the ciphers instead of doing anything else.
+            list_ciphers = true;
+        } else if arg == "--self-test" {
+            // Check the ciphers work instead of reading any files.
+            self_test = true;
+        } else if arg == "--benchmark" {
+            // Measure how fast the ciphers are instead of reading any files.
+            benchmark = true;
+        } else if arg == "--size" {
+            // The amount of text to benchmark on is given as the next argument, like "100MB".
+            let value = &args.pop_front().ok_or("--size needs a size.")?;
+            benchmark_size = parse_size(value).ok_or("--size needs a size, like 100MB or 64KiB.")?;
+        } else if arg == "-c" || arg == "--cipher" {
+            // The name of the cipher to use is given as the next argument.
+            let value = &args.pop_front().ok_or("--cipher needs a cipher name.")?;
+            if value != "all" && !CIPHERS.iter().any(|cipher| cipher.name == value) {
+                let names: Vec<&str> = CIPHERS.iter().map(|cipher| cipher.name).collect();
+                return Err(format!("Unknown cipher {value}; --cipher needs one of {}.", names.join(", ")));
+            }
+            cipher = Some(value.clone());
+        } else if arg == "--no-glob" {
+            // Treat wildcard characters in the input paths literally.
+            glob = false;
+        } else if arg == "-v" || arg == "--verbose" {
+            // Print every step. Giving it twice is the same as -vv.
+            verbosity = if verbosity == Verbosity::Normal { Verbosity::Verbose } else { Verbosity::VeryVerbose };
+        } else if arg == "-vv" {
+            // Print every step and what went into it.
+            verbosity = Verbosity::VeryVerbose;
+        } else if arg.len() > 2 && arg.starts_with('-') && arg[1..].chars().all(|flag| "edqrfpwhVv".contains(flag)) {
+            // Short options that don't take a value can be given together, like "-rf" for "-r -f".
+            for flag in arg[1..].chars().rev() {
+                args.push_front(format!("-{flag}"));
+            }
+        } else if arg.is_empty() {
+            // An empty argument is usually a shell variable that wasn't set, not a path anyone meant.
+            return Err(String::from("Empty argument; expected a path or an option."));
+        } else if arg.starts_with('-') {
+            // A mistyped flag would otherwise be quietly ignored, or taken for a path. A lone "-" would usually
+            // mean stdin or stdout, which aren't supported, so it's rejected the same way.
+            return Err(format!("Unknown option {arg}; see --help. Paths starting with - go after --."));
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+
+    // The scrypt settings start from the preset, with any of N, r and p given on their own replacing the preset's.
+    // Giving any of them picks scrypt, so they can't go with a different "--kdf".
+    if scrypt_preset.is_some() || scrypt_n.is_some() || scrypt_r.is_some() || scrypt_p.is_some() {
+        if kdf.is_some_and(|kdf| !matches!(kdf, KdfParams::Scrypt(_))) {
+            return Err(String::from("The --scrypt options only work with --kdf scrypt."));
+        }
+        let (n, r, p) = scrypt_preset.unwrap_or(SCRYPT_INTERACTIVE);
+        let settings = (scrypt_n.unwrap_or(n), scrypt_r.unwrap_or(r), scrypt_p.unwrap_or(p));
+        let settings = scrypt_settings(settings);
+        scrypt::Params::new(settings.log_n, settings.r, settings.p, SecretKey::LEN)
+            .map_err(|e| format!("Those --scrypt settings can't be used together ({e})."))?;
+        settings
+            .check_cost()
+            .map_err(|_| String::from("Those --scrypt settings cost too much; N × r × p can be at most 2^23."))?;
+        kdf = Some(KdfParams::Scrypt(settings));
+    }
+
+    // A JWE token is always encrypted with AES-256-GCM, using the key itself.
+    let picks_cipher = cipher.is_some() || profile.is_some() || kdf.is_some();
+    if output_format == OutputFormat::Jwe && (picks_cipher || mac || recipient_key.is_some()) {
+        return Err(String::from(
+            "--output-format jwe always uses AES-256-GCM, so it can't be used with --cipher, --profile, --kdf, \
+             --mac, or --recipient-key.",
+        ));
+    }
+
+    // A profile picks the cipher and the KDF, replacing any that were picked on their own.
+    let kdf_given = kdf.is_some() && profile.is_none();
+    let mut warnings = Vec::new();
+    if let Some(profile) = profile {
+        if cipher.is_some() || kdf.is_some() {
+            warnings.push(format!("--profile {} replaces --cipher, --kdf, and the --scrypt options.", profile.name));
+        }
+        cipher = Some(String::from(profile.cipher));
+        kdf = profile.kdf;
+    }
+
+    // A key on the command line takes priority over one from the environment.
+    let key = key.or_else(|| defaults.key.clone());
+
+    // Without "--cipher", giving a cipher's settings is enough to pick it. Otherwise, the config file's cipher is
+    // used, and ROT13 is the default.
+    let cipher = cipher.unwrap_or_else(|| {
+        let name = if alphabet.is_some() {
+            "substitution"
+        } else if rails.is_some() {
+            "rail-fence"
+        } else if shift.is_some() || alphabet_name.is_some() {
+            "caesar"
+        } else if affine_a.is_some() || affine_b.is_some() {
+            "affine"
+        } else {
+            defaults.cipher.as_deref().unwrap_or("rot13")
+        };
+        String::from(name)
+    });
+
+    // Being quiet or verbose on the command line replaces whatever the config file says about either.
+    if !quiet && verbosity == Verbosity::Normal {
+        quiet = defaults.quiet.unwrap_or(false);
+        if defaults.verbose == Some(true) {
+            verbosity = Verbosity::Verbose;
+        }
+    }
+
+    // Being quiet and verbose at the same time doesn't make sense.
+    if quiet && verbosity > Verbosity::Normal {
+        return Err(String::from("-q/--quiet and -v/--verbose cannot be used together; pick one."));
+    }
+    // A dry run is only for files, and only checks them once.
+    if dry_run && (text.is_some() || watch || verify) {
+        return Err(String::from("--dry-run can't be used with --text, --watch, or verify."));
+    }
+    // A shift can only be detected in text that was encrypted with one.
+    if detect_shift {
+        if verify || crack || (mode_given && mode == Direction::Encrypt) {
+            return Err(String::from("--detect-shift only works when decrypting, and not with verify or crack."));
+        }
+        mode = Direction::Decrypt;
+        mode_given = true;
+    }
+    // Skipping files whose output is there already can't go with overwriting them, or with saving over the input.
+    if skip_existing && (force || in_place || text.is_some() || watch) {
+        return Err(String::from("--skip-existing can't be used with --force, --in-place, --text, or --watch."));
+    }
+    // Shredding destroys the input file, so it only makes sense when there's a separate output file to keep.
+    if shred_passes.is_some() && (text.is_some() || watch || verify || in_place) {
+        return Err(String::from("--shred can't be used with --text, --watch, --in-place, or verify."));
+    }
+    // The JSON objects take the place of all the usual text, so none of it should be printed.
+    if json {
+        verbosity = Verbosity::Normal;
+        quiet = true;
+    }
+
+    // With an output directory, a single path, or "--in-place", every path is a file to read. Otherwise, the last
+    // path is where to write to, and every path before it is a file to read.
+    // Help, the version, the list of ciphers, a self-test, or a benchmark doesn't need any paths at all.
+    // With "--text", the text takes the place of the input file, so the only path is where to write to. If there
+    // isn't one, the result is printed instead.
+    let out_file_path = if help || version || list_ciphers || self_test || benchmark {
+        None
+    } else if completions_command {
+        // The only path is the name of the shell.
+        let shell = match paths.as_slice() {
+            [shell] if completions::SHELLS.contains(&shell.as_str()) => shell.clone(),
+            _ => return Err(format!("completions needs a shell: {}.", completions::SHELLS.join(", "))),
+        };
+        paths.clear();
+        completions = Some(shell);
+        None
+    } else if info {
+        // Every path is a file to show, and nothing is written.
+        if paths.is_empty() || text.is_some() {
+            return Err(String::from("info needs at least one encrypted file."));
+        }
+        None
+    } else if crack {
+        // The only path is the file to crack, unless the text was given instead.
+        if paths.len() != usize::from(text.is_none()) {
+            return Err(String::from("crack needs a file to crack, or --text."));
+        }
+        None
+    } else if gen_key {
+        // The key is printed, or saved with "--key-file".
+        if !paths.is_empty() || text.is_some() {
+            return Err(String::from("gen-key doesn't take any files; use --key-file to save the key to one."));
+        }
+        None
+    } else if verify {
+        // Verifying reads the encrypted file and the original, and writes nothing.
+        if paths.len() != 2 || text.is_some() {
+            return Err(String::from("verify needs an encrypted file and the original file to compare it to."));
+        }
+        mode = Direction::Decrypt;
+        None
+    } else if text.is_some() {
+        if paths.len() > 1 {
+            return Err(String::from("--text can't be used with an input file."));
+        }
+        paths.pop()
+    } else if in_place {
+        // Each file is saved over itself, so there's nowhere else to save to.
+        if output_dir.is_some() || suffix {
+            return Err(String::from("--in-place can't be used with --output-dir or --suffix."));
+        }
+        if watch {
+            return Err(String::from("--in-place can't be used with --watch."));
+        }
+        if paths.is_empty() {
+            return Err(String::from("Not enough arguments."));
+        }
+        None
+    } else if output_dir.is_some() {
+        if paths.is_empty() {
+            return Err(String::from("Not enough arguments."));
+        }
+        None
+    } else if paths.len() == 1 {
+        // A single file is saved next to itself, with the extension added when encrypting and removed when
+        // decrypting. Unless "-e" or "-d" says which, a file whose name ends in the extension is decrypted (with
+        // the cipher its header names), and any other file is encrypted.
+        suffix = true;
+        if !mode_given && paths[0].ends_with(&format!(".{ext}")) {
+            mode = Direction::Decrypt;
+        }
+        None
+    } else {
+        // If no paths were provided, raise an error.
+        if paths.is_empty() {
+            return Err(String::from("Not enough arguments."));
+        }
+        paths.pop()
+    };
+
+    let mut config = Config {
+        in_file_paths: paths,
+        text,
+        out_file_path,
+        output_dir,
+        ext,
+        suffix,
+        in_place,
+        shred_passes,
+        dry_run,
+        mode,
+        verbosity,
+        quiet,
+        glob,
+        recursive,
+        exclude,
+        include,
+        exclude_hidden,
+        force,
+        skip_existing,
+        backup,
+        fail_fast,
+        threads,
+        color,
+        json,
+        show_contents,
+        preserve,
+        preserve_perms,
+        checksum,
+        log,
+        mmap_threshold,
+        max_file_size,
+        newline,
+        output_format,
+        cipher,
+        affine_a,
+        affine_b,
+        rails,
+        shift,
+        alphabet_name,
+        alphabet,
+        key,
+        kdf: kdf.unwrap_or(KdfParams::Argon2id(ARGON2ID_DEFAULT)),
+        recipient_key,
+        private_key,
+        passphrase,
+        header,
+        mac,
+        watch,
+        help,
+        version,
+        list_ciphers,
+        self_test,
+        benchmark,
+        benchmark_size,
+        verify,
+        gen_key,
+        key_length,
+        key_format,
+        key_out,
+        completions,
+        detect_shift,
+        detect_min_size,
+        crack,
+        top,
+        info,
+        warnings,
+    };
+
+    if config.top.is_some() && !config.crack {
+        return Err(String::from("--top only works with crack."));
+    }
+    if config.passphrase.is_some() && config.private_key.is_none() {
+        return Err(String::from("--passphrase-file only works with --private-key."));
+    }
+    if config.output_format == OutputFormat::Jwe && config.key.is_none() {
+        return Err(String::from("--output-format jwe needs a 256-bit key, from --key or --key-file."));
+    }
+    // Decrypting reads the KDF and its settings from the header, so they're only chosen when encrypting.
+    let from_password = config.cipher == PASSWORD_CIPHER || config.mac;
+    if kdf_given && (!from_password || config.mode != Direction::Encrypt || config.verify) {
+        return Err(format!(
+            "--kdf and the --scrypt options only work when encrypting with {PASSWORD_CIPHER} or --mac."
+        ));
+    }
+    // The MAC is keyed with the password given with "--key", so it can't go with a cipher that needs "--key"
+    // for itself, or one that authenticates the file already. Its key comes from the header, so it needs one.
+    if config.mac {
+        if config.mode != Direction::Encrypt || config.verify {
+            return Err(String::from("--mac only works when encrypting; decrypting checks the MAC if there is one."));
+        }
+        if config.cipher == "columnar" || config.cipher == PASSWORD_CIPHER || config.recipient_key.is_some() {
+            return Err(format!("--mac can't be used with columnar, {PASSWORD_CIPHER}, or --recipient-key."));
+        }
+        if !config.header {
+            return Err(String::from("--mac can't be used with --no-header."));
+        }
+    }
+    // The header is where the salt and nonce go, so the file can't be decrypted without one.
+    if config.cipher == PASSWORD_CIPHER && !config.header {
+        return Err(format!("{PASSWORD_CIPHER} can't be used with --no-header."));
+    }
+
+    // Make sure the chosen cipher has the settings it needs. A benchmark fills in any that are missing, and
+    // generating a key or a completion script, or cracking or detecting a Caesar shift, doesn't use a cipher at all.
+    // Showing how files were encrypted doesn't either.
+    let uses_cipher = !config.gen_key && config.completions.is_none() && !config.crack && !config.info;
+    if !config.benchmark && uses_cipher && !config.detect_shift {
+        if config.cipher == "all" {
+            return Err(String::from("--cipher all only works with --benchmark."));
+        }
+        // Without a key, ask for one on the terminal, so it doesn't have to go anywhere other people can see it.
+        // Without a terminal there's nobody to ask, so it's an error like any other missing setting.
+        let needs_key = config.cipher == "columnar" || config.cipher == PASSWORD_CIPHER || config.mac;
+        if needs_key && config.key.is_none() && io::stdin().is_terminal() {
+            config.key = Some(prompt_for_key(config.mode == Direction::Encrypt)?);
+        }
+        check_cipher_settings(&config)?;
+        if config.mac && config.key.is_none() {
+            return Err(String::from("--mac needs a password, with --key or --key-file."));
+        }
+    }
+
+    // Return the arguments to the main function.
+    Ok(config)
+}
+
+// This function turns scrypt settings as they're usually written, with N as a number, into the ones the header
+// stores, with N as a power of two. N has already been checked to be a power of two.
+fn scrypt_settings((n, r, p): (u64, u32, u32)) -> ScryptKdf {
+    ScryptKdf { log_n: n.trailing_zeros() as u8, r, p }
+}
+
+// This function reads a key from a file for "--key-file", so it doesn't show up in the shell's history or the
+// list of running processes. The line break editors put at the end of a file isn't part of the key.
+fn read_key_file(path: &str) -> Result<Zeroizing<String>, String> {
+    let mut key = Zeroizing::new(fs::read_to_string(path).map_err(|e| format!("Could not read key file {path}: {e}"))?);
+    if key.ends_with('\n') {
+        key.pop();
+        if key.ends_with('\r') {
+            key.pop();
+        }
+    }
+    Ok(key)
+}
+
+// This function asks for a key on the terminal without showing what's typed. When encrypting, the key is asked
+// for twice, since a typo would leave the file encrypted with a key nobody knows.
+fn prompt_for_key(confirm: bool) -> Result<Zeroizing<String>, String> {
+    let read = |prompt| {
+        rpassword::prompt_password(prompt).map(Zeroizing::new).map_err(|e| format!("Could not read the key: {e}"))
+    };
+    let key = read("Key: ")?;
+    let again = if confirm { Some(read("Key again: ")?) } else { None };
+    confirm_key(key, again)
+}
+
+// This function checks a key typed at a prompt, and that it was typed the same way again if it was asked for
+// twice.
+fn confirm_key(key: Zeroizing<String>, again: Option<Zeroizing<String>>) -> Result<Zeroizing<String>, String> {
+    if key.is_empty() {
+        return Err(String::from("The key can't be empty."));
+    }
+    if again.is_some_and(|again| *again != *key) {
+        return Err(String::from("The keys don't match."));
+    }
+    Ok(key)
+}
+
+// This function makes sure the chosen cipher has the settings it needs, like a key.
+fn check_cipher_settings(config: &Config) -> Result<(), &'static str> {
+    let missing = match config.cipher.as_str() {
+        "affine" if config.affine_a.is_none() && config.affine_b.is_none() => {
+            "The affine cipher needs --affine-a or --affine-b."
+        }
+        "rail-fence" if config.rails.is_none() => "The rail fence cipher needs --rails.",
+        "caesar" if config.shift.is_none() => "The Caesar cipher needs --shift.",
+        "columnar" if config.key.is_none() => "The columnar cipher needs a --key or --key-file.",
+        PASSWORD_CIPHER if config.key.is_none() => "ChaCha20-Poly1305 needs a password, with --key or --key-file.",
+        "substitution" if config.alphabet.is_none() => "The substitution cipher needs an --alphabet.",
+        _ => return Ok(()),
+    };
+    Err(missing)
+}
+
+// This function reads a size like "100MB", "64KiB" or "4096" into a number of bytes.
+// "KB", "MB" and "GB" go up in thousands, while "KiB", "MiB" and "GiB" go up in lots of 1024.
+fn parse_size(text: &str) -> Option<usize> {
+    let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(digits);
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "kib" => 1024,
+        "m" | "mb" => 1000 * 1000,
+        "mib" => 1024 * 1024,
+        "g" | "gb" => 1000 * 1000 * 1000,
+        "gib" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+// This function replaces every glob pattern in a list of paths with the files it matches.
+// Paths without any wildcards are passed through unchanged.
+fn expand_globs(paths: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        if path.contains(['*', '?']) {
+            expanded.extend(expand_glob(path)?);
+        } else {
+            expanded.push(path.clone());
+        }
+    }
+    Ok(expanded)
+}
+
+// This function finds every file matching a glob pattern, sorted so the results are always in the same order.
+// Wildcards are only supported in the file name, not in the directories leading up to it.
+fn expand_glob(pattern: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let path = Path::new(pattern);
+    // Split the pattern into the directory to search and the file name pattern to search for.
+    let file_pattern = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return Err(format!("invalid pattern: {pattern}").into()),
+    };
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if dir.to_string_lossy().contains(['*', '?']) {
+        return Err(format!("wildcards are only supported in file names: {pattern}").into());
+    }
+
+    let pattern_chars: Vec<char> = file_pattern.chars().collect();
+    let mut matches = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        // Skip anything that isn't a regular file, like subdirectories.
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        // If the file name couldn't be decoded via valid Unicode, it can't match the pattern.
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str() else { continue };
+        // Like most shells, wildcards don't match hidden files unless the pattern starts with a dot too.
+        if name.starts_with('.') && !file_pattern.starts_with('.') {
+            continue;
+        }
+        let name_chars: Vec<char> = name.chars().collect();
+        if glob_match(&pattern_chars, &name_chars) {
+            // Keep the directory in the path only if the user gave one.
+            match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => {
+                    matches.push(parent.join(name).to_string_lossy().into_owned())
+                }
+                _ => matches.push(name.to_string()),
+            }
+        }
+    }
+
+    // A pattern matching nothing is almost certainly a mistake, so tell the user rather than
+    // trying to open a file literally named "*.txt".
+    if matches.is_empty() {
+        return Err(format!("pattern matched no files: {pattern}").into());
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+// This function checks whether a file name matches a glob pattern.
+// A "*" matches any number of characters and a "?" matches exactly one character. The name is read once from
+// start to end, and when a character doesn't match, only the last star is tried again with one more character.
+// Going back to an earlier star could never help, so this takes at most the pattern's length times the name's,
+// however many stars there are.
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Where the last star seen is in the pattern, and where in the name it stops matching so far.
+    let mut last_star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match last_star {
+                Some((star_p, star_n)) => {
+                    last_star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    // The name has run out, so whatever's left of the pattern has to be able to match nothing.
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// This function leaves the files whose output file already exists out of the plan, for "--skip-existing". They're
+// skipped quietly, only mentioned with -v, since that's what was asked for, but a dry run says which ones would
+// be skipped and the JSON output includes them.
+fn skip_existing(plan: &mut Plan, config: &Config) {
+    plan.jobs.retain(|(in_file_path, out_file_path)| {
+        if !out_file_path.exists() {
+            return true;
+        }
+        if config.json {
+            println!("{}", json!({ "status": "skipped", "input": in_file_path, "output": out_file_path }));
+        } else if config.dry_run && !config.quiet {
+            println!("Would skip {in_file_path}, since {} already exists", out_file_path.display());
+        } else {
+            info!("Skipping {in_file_path}, since {} already exists", out_file_path.display());
+        }
+        false
+    });
+}
+
+// This struct is the list of work to do, worked out before any files are touched.
+struct Plan {
+    // The directories to create on the output side, so that the structure of an input directory
+    // (including any empty parts of it) is recreated there.
+    dirs: Vec<PathBuf>,
+    // Each file to read, paired with the path its encrypted/decrypted contents should be written to.
+    jobs: Vec<(String, PathBuf)>,
+}
+
+// This function pairs each input file with the path its encrypted/decrypted contents should be written to.
+// When there are several input files, or the output path is an existing directory, each file is saved
+// into that directory under its own name. In recursive mode, input directories are searched for files and
+// their structure is copied into the output directory.
+fn plan_jobs(config: &Config) -> Result<Plan, Box<dyn Error>> {
+    let mut plan = Plan { dirs: Vec::new(), jobs: Vec::new() };
+
+    // Everything gets saved into the output directory or to the output path. If there's neither
+    // (because a suffix was given instead), each file gets saved next to the file it came from.
+    // An output path ending in a "/" is a directory too, even if it doesn't exist yet.
+    let out_root = config.output_dir.as_deref().or(config.out_file_path.as_deref()).map(Path::new);
+    let names_dir = config.out_file_path.as_deref().is_some_and(|path| path.ends_with(std::path::is_separator));
+    let into_dir = config.output_dir.is_some()
+        || config.recursive
+        || config.in_file_paths.len() > 1
+        || names_dir
+        || out_root.is_some_and(Path::is_dir);
+
+    // The output directory is created if it doesn't exist yet, but it can't be a file. That goes for one given
+    // with "--output-dir" and for one given as the last path.
+    if let Some(out_dir) = out_root.filter(|_| into_dir) {
+        if out_dir.exists() && !out_dir.is_dir() {
+            return Err(format!("output directory {} exists but is not a directory", out_dir.display()).into());
+        }
+        plan.dirs.push(out_dir.to_path_buf());
+    }
+
+    // Work out which files in input directories should be skipped.
+    let filter = PathFilter::new(config)?;
+
+    for in_file_path in &config.in_file_paths {
+        let in_path = Path::new(in_file_path);
+        if config.recursive && in_path.is_dir() {
+            // Find everything under the input directory and mirror it under the output directory.
+            let out_path = out_root.unwrap_or(in_path);
+            let mut files = Vec::new();
+            let mut dirs = Vec::new();
+            walk_directory(in_path, in_path, &filter, &mut files, &mut dirs)?;
+            for dir in dirs {
+                plan.dirs.push(out_path.join(dir.strip_prefix(in_path)?));
+            }
+            for file in files {
+                let relative_dir = file.strip_prefix(in_path)?.parent().unwrap_or(Path::new(""));
+                let out_file_path = if config.in_place {
+                    file.clone()
+                } else {
+                    output_path_in(config, &file, &out_path.join(relative_dir))
+                };
+                plan.jobs.push((file.to_string_lossy().into_owned(), out_file_path));
+            }
+        } else {
+            let out_file_path = match out_root {
+                None if config.in_place => in_path.to_path_buf(),
+                None => output_path_in(config, in_path, in_path.parent().unwrap_or(Path::new(""))),
+                Some(out_path) if into_dir => output_path_in(config, in_path, out_path),
+                Some(out_path) => out_path.to_path_buf(),
+            };
+            plan.jobs.push((in_file_path.clone(), out_file_path));
+        }
+    }
+
+    // Two inputs with the same file name (from different directories) would be saved to the same place,
+    // with the second one silently replacing the first. Refuse to do that before anything gets written.
+    let mut seen: HashMap<&Path, &str> = HashMap::new();
+    for (in_file_path, out_file_path) in &plan.jobs {
+        if let Some(other) = seen.insert(out_file_path, in_file_path) {
+            return Err(format!(
+                "{other} and {in_file_path} would both be saved to {}",
+                out_file_path.display()
+            ).into());
+        }
+    }
+    Ok(plan)
+}
+
+// This struct decides which of the files found while searching an input directory should be used.
+struct PathFilter {
+    exclude: GlobSet,
+    include: GlobSet,
+    exclude_hidden: bool,
+}
+
+impl PathFilter {
+    // This function compiles the patterns given on the command line.
+    fn new(config: &Config) -> Result<PathFilter, globset::Error> {
+        Ok(PathFilter {
+            exclude: build_glob_set(&config.exclude)?,
+            include: build_glob_set(&config.include)?,
+            exclude_hidden: config.exclude_hidden,
+        })
+    }
+
+    // This function checks whether a file or directory should be skipped. The path is relative to the
+    // input directory, so a pattern like "target/**" only matches things inside that directory's "target".
+    fn excludes(&self, relative_path: &Path) -> bool {
+        let hidden = relative_path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        (self.exclude_hidden && hidden) || self.exclude.is_match(relative_path)
+    }
+
+    // This function checks whether a file should be used. Exclude patterns take priority over include patterns,
+    // so a file matching both is skipped.
+    fn allows(&self, relative_path: &Path) -> bool {
+        !self.excludes(relative_path) && (self.include.is_empty() || self.include.is_match(relative_path))
+    }
+}
+
+// This function compiles a list of glob patterns into a set that can be matched all at once.
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
+
+// This function finds every regular file in a directory and all of its subdirectories, along with every
+// directory along the way (including the one it started in and any empty ones). Anything the filter
+// excludes is skipped, including whole directories. Entries are visited in sorted order so the results
+// are always the same.
+fn walk_directory(
+    root: &Path,
+    dir: &Path,
+    filter: &PathFilter,
+    files: &mut Vec<PathBuf>,
+    dirs: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    dirs.push(dir.to_path_buf());
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        // The filter's patterns are written relative to the directory the search started in.
+        let relative_path = path.strip_prefix(root).unwrap_or(&path);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() && !filter.excludes(relative_path) {
+            walk_directory(root, &path, filter, files, dirs)?;
+        } else if file_type.is_file() && filter.allows(relative_path) {
+            files.push(path);
+        }
+        // Anything else, like a symbolic link, is skipped.
+    }
+    Ok(())
+}
+
+// This function names a file being saved into a directory. With an output directory or a suffix the
+// extension is added or removed, otherwise the file keeps its own name.
+fn output_path_in(config: &Config, input: &Path, dir: &Path) -> PathBuf {
+    if config.output_dir.is_some() || config.suffix {
+        return make_output_path(input, dir, &config.ext, config.mode);
+    }
+    match input.file_name() {
+        Some(file_name) => dir.join(file_name),
+        // If this isn't a valid filename, there's nothing sensible to call the output, so writing to the
+        // directory itself will fail with an error.
+        None => dir.to_path_buf(),
+    }
+}
+
+// This function works out where to save a file in the output directory, naming it with `derive_output_name`.
+fn make_output_path(input: &Path, dir: &Path, ext: &str, mode: Direction) -> PathBuf {
+    // If this isn't a valid filename, fall back to the whole path.
+    let file_name = match input.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => input.to_string_lossy(),
+    };
+    dir.join(derive_output_name(&file_name, ext, mode))
+}
+
+// This function works out the name of an output file from the name of its input file.
+// Encrypting adds the extension to the file name ("notes.txt" becomes "notes.txt.enc", "notes.enc"
+// becomes "notes.enc.enc", and ".bashrc" becomes ".bashrc.enc"). Decrypting removes the extension
+// ("notes.txt.enc" becomes "notes.txt", and "notes.enc" becomes just "notes"), or adds ".dec" if the
+// file doesn't have the extension to remove.
+fn derive_output_name(file_name: &str, ext: &str, mode: Direction) -> String {
+    match mode {
+        Direction::Encrypt => format!("{file_name}.{ext}"),
+        Direction::Decrypt => match file_name.strip_suffix(&format!(".{ext}")) {
+            // A file named just ".enc" has nothing left once the extension is removed.
+            Some(stem) if !stem.is_empty() => stem.to_string(),
+            _ => format!("{file_name}.dec"),
+        },
+    }
+}
+
+// This function checks that an output file isn't already there, so it won't be overwritten by accident.
+fn path_exists_check(path: &Path) -> Result<(), EncryptError> {
+    if path.exists() {
+        return Err(EncryptError::OutputAlreadyExists(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+// This function encrypts/decrypts the text given with "--text", saving the result to the output file, or
+// printing it if there isn't one.
+fn run_literal(text: &str, out_file_path: Option<&str>, config: &Config) -> Result<Stats, EncryptError> {
+    info!("Input: {} bytes of literal text from --text", text.len());
+    // Printed results are meant to be read or copied somewhere, so they're left without a header. A password
+    // cipher's header (or a MAC's) holds what's needed to decrypt, though, so it's always kept.
+    let header = out_file_path.is_some() || config.cipher == PASSWORD_CIPHER || config.mac;
+    let config = &Config { header: config.header && header, ..config.clone() };
+    let start = Instant::now();
+    let mut shift = None;
+    let new_contents = match config.mode {
+        _ if config.detect_shift => {
+            let (decrypted, detected) = decrypt_detecting_shift(text.as_bytes())?;
+            shift = Some(detected);
+            convert_newlines(&decrypted, config.newline).unwrap_or(decrypted)
+        }
+        Direction::Encrypt => {
+            let converted = convert_newlines(text.as_bytes(), config.newline);
+            encrypt_with_header(converted.as_deref().unwrap_or(text.as_bytes()), config)?
+        }
+        Direction::Decrypt => {
+            let decrypted = decrypt_with_header(text.as_bytes(), config)?;
+            convert_newlines(&decrypted, config.newline).unwrap_or(decrypted)
+        }
+    };
+    let transform = start.elapsed();
+    // The result might be printed, so the shift goes to stderr to keep it out of the way.
+    if let Some(shift) = shift {
+        if let Some(warning) = short_input_warning("the text", "--text TEXT", text.len(), config) {
+            eprintln!("{} {warning}", paint("Warning:", Color::Yellow, Stream::Stderr));
+        }
+        if !config.quiet {
+            eprintln!("Detected shift: {shift}");
+        }
+    }
+    let start = Instant::now();
+    match out_file_path {
+        Some(out_file_path) => {
+            if !config.force {
+                path_exists_check(Path::new(out_file_path))?;
+            }
+            back_up(out_file_path, config)?;
+            write_with_progress(out_file_path, &new_contents, config.quiet, &CarriedOver::default())
+                .map_err(EncryptError::writing(out_file_path))?;
+            info!("Wrote {} bytes to {out_file_path}", new_contents.len());
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&new_contents)?;
+            writeln!(stdout)?;
+        }
+    }
+    // There's nothing to read, so only the sizes and times of the other steps go in the log.
+    let timings = Timings { read: Duration::ZERO, transform, write: start.elapsed() };
+    Ok(Stats {
+        bytes_in: text.len(),
+        bytes_out: new_contents.len(),
+        checksum_in: None,
+        checksum_out: None,
+        timings,
+        shift,
+    })
+}
+
+// This function shows how each input file was encrypted, for "info", from its header alone: the cipher, the
+// KDF if the key came from a password, whether there's a MAC, and the profile all that matches, or "custom".
+fn run_info(config: &Config) -> Result<(), EncryptError> {
+    for path in &config.in_file_paths {
+        let mut file = io::BufReader::new(fs::File::open(path).map_err(EncryptError::reading(path))?);
+        let header = FileHeader::read_from(&mut file).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                EncryptError::Cipher(CipherError::InvalidInput(format!("{path} is too short to have a header")))
+            }
+            io::ErrorKind::InvalidData => EncryptError::Cipher(CipherError::InvalidInput(format!("{path}: {e}"))),
+            _ => EncryptError::Read(PathBuf::from(path), e),
+        })?;
+        let cipher = match header.cipher_id {
+            RSA_CIPHER_ID => "rsa",
+            X25519_CIPHER_ID => "x25519",
+            id => CIPHERS.iter().find(|cipher| cipher.id == id).map_or("unknown", |cipher| cipher.name),
+        };
+        let kdf = kdf_name(header.kdf_id);
+        let profile = CipherProfile::ALL
+            .iter()
+            .find(|profile| profile.cipher == cipher && !header.mac && profile_kdf_matches(profile, &header))
+            .map_or("custom", |profile| profile.name);
+        if config.json {
+            println!("{}", json!({
+                "path": path,
+                "cipher": cipher,
+                "kdf": kdf,
+                "mac": header.mac,
+                "profile": profile,
+            }));
+        } else {
+            println!("{path}");
+            println!("  Cipher:  {cipher}");
+            println!("  KDF:     {}", kdf.unwrap_or("none"));
+            println!("  MAC:     {}", if header.mac { "HMAC-SHA256" } else { "none" });
+            println!("  Profile: {profile}");
+        }
+    }
+    Ok(())
+}
+
+// This function checks whether a header's KDF and its settings are exactly the ones a profile uses.
+fn profile_kdf_matches(profile: &CipherProfile, header: &FileHeader) -> bool {
+    match profile.kdf {
+        None => header.kdf_id == 0,
+        Some(kdf) => header.kdf_id == kdf.id() && header.kdf_params == kdf.algorithm().params_to_bytes(),
+    }
+}
+
+// This function decrypts a file (or "--text") encrypted with an unknown Caesar shift every possible way, for
+// "crack". Each result is printed under its shift, or saved to a file named after its shift in the
+// "--output-dir" directory. With "--top N", only the N most English-looking results are kept, best first.
+fn run_crack(config: &Config) -> Result<(), EncryptError> {
+    let (name, contents) = match &config.text {
+        Some(text) => (String::from("text"), text.clone().into_bytes()),
+        None => {
+            let path = Path::new(&config.in_file_paths[0]);
+            let name = path.file_name().map_or(String::from("text"), |name| name.to_string_lossy().into_owned());
+            (name, fs::read(path).map_err(EncryptError::reading(path))?)
+        }
+    };
+    let text = String::from_utf8(contents)
+        .map_err(|e| CipherError::InvalidInput(format!("the text isn't valid UTF-8 ({e})")))?;
+    let mut candidates = crack_caesar(&text);
+    if let Some(top) = config.top {
+        candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+        candidates.truncate(top);
+    }
+
+    if let Some(dir) = &config.output_dir {
+        fs::create_dir_all(dir).map_err(EncryptError::writing(dir))?;
+    }
+    for candidate in &candidates {
+        let (shift, score) = (candidate.shift, candidate.score);
+        let Some(dir) = &config.output_dir else {
+            if config.json {
+                println!("{}", json!({ "shift": shift, "score": score, "text": candidate.text }));
+            } else {
+                println!("{}", paint(&format!("Shift {shift} (score {score:.1}):"), Color::Green, Stream::Stdout));
+                println!("{}", candidate.text.strip_suffix('\n').unwrap_or(&candidate.text));
+            }
+            continue;
+        };
+        let path = Path::new(dir).join(format!("shift-{shift:02}-{name}"));
+        if !config.force {
+            path_exists_check(&path)?;
+        }
+        write_with_progress(&path.to_string_lossy(), candidate.text.as_bytes(), true, &CarriedOver::default())
+            .map_err(EncryptError::writing(&path))?;
+        if config.json {
+            println!("{}", json!({ "shift": shift, "score": score, "output": path }));
+        } else if !config.quiet {
+            println!("Shift {shift} (score {score:.1}) saved to {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+// This function generates a random key for "gen-key", printing it or saving it to the "--key-file" path. The
+// bytes come from the operating system's secure random number generator, so nobody can predict them.
+fn run_gen_key(config: &Config) -> Result<(), EncryptError> {
+    let mut bytes = Zeroizing::new(vec![0; config.key_length]);
+    OsRng.fill_bytes(&mut bytes);
+    let key = Zeroizing::new(match config.key_format {
+        KeyFormat::Base64 => STANDARD.encode(&*bytes),
+        KeyFormat::Hex => bytes.iter().map(|byte| format!("{byte:02x}")).collect(),
+    });
+    let Some(path) = &config.key_out else {
+        if config.json {
+            println!("{}", json!({ "status": "ok", "key": *key, "bytes": config.key_length }));
+        } else {
+            println!("{}", *key);
+        }
+        return Ok(());
+    };
+
+    // Only the owner can read the key file, and an existing one is only replaced with "--force".
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if config.force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => EncryptError::OutputAlreadyExists(PathBuf::from(path)),
+        _ => EncryptError::Write(PathBuf::from(path), e),
+    })?;
+    file.write_all(format!("{}\n", *key).as_bytes()).map_err(EncryptError::writing(path))?;
+    if config.json {
+        println!("{}", json!({ "status": "ok", "key_file": path, "bytes": config.key_length }));
+    } else if !config.quiet {
+        println!("Saved a {}-byte key to {path}.", config.key_length);
+    }
+    Ok(())
+}
+
+// This function decrypts an encrypted file in memory and compares it byte for byte with the original file,
+// printing whether they match and, if they don't, where the first difference is. It returns whether they
+// matched.
+fn run_verify(encrypted_path: &str, original_path: &str, config: &Config) -> Result<bool, EncryptError> {
+    let encrypted = fs::read(encrypted_path).map_err(EncryptError::reading(encrypted_path))?;
+    let original = fs::read(original_path).map_err(EncryptError::reading(original_path))?;
+    let decrypted = decrypt_with_header(&encrypted, config)?;
+    info!("Decrypted {encrypted_path}: {} bytes", decrypted.len());
+    info!("Size of {original_path}: {} bytes", original.len());
+
+    // If one is the start of the other, they first differ where the shorter one ends.
+    let first_difference = decrypted
+        .iter()
+        .zip(&original)
+        .position(|(a, b)| a != b)
+        .or_else(|| (decrypted.len() != original.len()).then(|| decrypted.len().min(original.len())));
+
+    if config.json {
+        println!("{}", json!({
+            "status": if first_difference.is_none() { "ok" } else { "mismatch" },
+            "encrypted": encrypted_path,
+            "original": original_path,
+            "matches": first_difference.is_none(),
+            "first_difference": first_difference,
+        }));
+    } else if let Some(offset) = first_difference {
+        let label = paint("Error:", Color::Red, Stream::Stderr);
+        eprintln!("{label} {encrypted_path} does not decrypt to {original_path}; they first differ at byte {offset}.");
+        if decrypted.len() != original.len() {
+            eprintln!("It decrypts to {} bytes, but {original_path} is {} bytes.", decrypted.len(), original.len());
+        }
+    } else if !config.quiet {
+        println!("{}", paint(&format!("{encrypted_path} decrypts to {original_path}."), Color::Green, Stream::Stdout));
+    }
+    Ok(first_difference.is_none())
+}
+
+// This function checks that a file can be saved to its output path, returning whether it's being saved over
+// itself. Saving over the file being read has to be asked for with "--in-place", even with "--force", since it's
+// easy to do by mistake (like with "./notes.txt" and "notes.txt") and the original is gone afterwards.
+// Otherwise, refuse to overwrite an existing file unless the user asked for it.
+fn check_output(in_file_path: &str, out_file_path: &str, config: &Config) -> Result<bool, EncryptError> {
+    let in_place = same_file(in_file_path, out_file_path);
+    if in_place && !config.in_place {
+        return Err(EncryptError::SameFile(PathBuf::from(out_file_path)));
+    }
+    if !config.force && !in_place {
+        path_exists_check(Path::new(out_file_path))?;
+    }
+    // Backing up the output file mustn't overwrite an older backup by accident either.
+    if config.backup && !config.force && Path::new(out_file_path).exists() {
+        path_exists_check(&backup_path_for(out_file_path))?;
+    }
+    Ok(in_place)
+}
+
+// This function copies an output file that's about to be overwritten to its ".bak" file, for "--backup", so
+// what was there before can still be got back. An output file that isn't there yet has nothing to back up.
+fn back_up(out_file_path: &str, config: &Config) -> Result<(), EncryptError> {
+    if !config.backup || !Path::new(out_file_path).exists() {
+        return Ok(());
+    }
+    let backup_path = backup_path_for(out_file_path);
+    info!("Backing up {out_file_path} to {}", backup_path.display());
+    fs::copy(out_file_path, &backup_path).map_err(EncryptError::writing(&backup_path))?;
+    Ok(())
+}
+
+// This function names the backup of an output file, which is the same name with ".bak" added.
+fn backup_path_for(out_file_path: &str) -> PathBuf {
+    PathBuf::from(format!("{out_file_path}.bak"))
+}
+
+// This function runs one step of processing a file, giving back how long it took along with its result. In
+// verbose mode, it's shown as a status line that says whether the step worked once it's over.
+fn run_step<T>(
+    verbose: bool,
+    message: String,
+    step: impl FnOnce() -> Result<T, EncryptError>,
+) -> Result<(T, Duration), EncryptError> {
+    let status = verbose.then(|| StatusLine::start(message));
+    let start = Instant::now();
+    let result = step();
+    let elapsed = start.elapsed();
+    if let Some(status) = status {
+        match &result {
+            Ok(_) => status.finish_ok(),
+            Err(_) => status.finish_err("failed."),
+        }
+    }
+    Ok((result?, elapsed))
+}
+
+fn run(in_file_path: &str, out_file_path: &str, config: &Config) -> Result<Stats, EncryptError> {
+    let verbosity = config.verbosity;
+    let verbose = verbosity >= Verbosity::Verbose;
+
+    // This is checked first, so no time is wasted reading a file that can't be saved anyway.
+    let in_place = check_output(in_file_path, out_file_path, config)?;
+
+    // Get some pretty file names for verbose output.
+    let in_file_name = get_file_name(in_file_path);
+    let out_file_name = get_file_name(out_file_path);
+
+    // The output file gets the same permissions as the input file (so an executable script stays executable)
+    // unless asked not to, and the same modification time if asked to. They're looked up before anything is
+    // written, since with "--in-place" the input file is about to be replaced.
+    let in_metadata = fs::metadata(in_file_path).map_err(EncryptError::reading(in_file_path))?;
+    let mut carried = CarriedOver::default();
+    if config.preserve {
+        let modified = in_metadata.modified().map_err(EncryptError::reading(in_file_path))?;
+        info!("Setting modification time of {out_file_name} to {}", format_timestamp(modified));
+        carried.modified = Some(modified);
+    }
+    if config.preserve_perms {
+        carried.permissions = Some(in_metadata.permissions());
+    }
+
+    // Read the contents from the file needing to be encrypted/decrypted.
+    // Return an error upon failure.
+    let (contents, read_time) = run_step(verbose, format!("Reading {in_file_name}"), || {
+        // Big files are mapped into memory rather than copied into it. A file that's about to be overwritten
+        // with the output is never mapped though, since emptying a mapped file crashes the program when it's read.
+        match config.mmap_threshold {
+            Some(threshold) if !in_place => {
+                read_file_smart(Path::new(in_file_path), threshold, config.max_file_size, config.quiet)
+            }
+            _ => Ok(FileData::Heap(read_with_progress(in_file_path, config.max_file_size, config.quiet)?)),
+        }
+    })
+    .map_err(|e| e.while_reading(in_file_path))?;
+
+    // With -v, log the size of the file to encrypt/decrypt. With -vv or --show-contents, show it too.
+    info!("Size of {in_file_name}: {} bytes", contents.len());
+    print_contents(in_file_name, &contents, config);
+
+    // Hash the input before it's processed, if asked to.
+    let checksum_in = config.checksum.then(|| sha256_hex(&contents));
+    if let Some(checksum) = &checksum_in {
+        info!("SHA-256 of {in_file_name}: {checksum}");
+    }
+
+    // Encrypt/decrypt the contents of the file with the chosen cipher.
+    debug!("Cipher: {}", describe_cipher(&contents, config));
+    let mut shift = None;
+    let (new_contents, transform_time) = run_step(verbose, String::from("Encrypting/decrypting text"), || {
+        let new_contents = match config.mode {
+            _ if config.detect_shift => {
+                let (decrypted, detected) = decrypt_detecting_shift(&contents)?;
+                shift = Some(detected);
+                convert_newlines(&decrypted, config.newline).unwrap_or(decrypted)
+            }
+            Direction::Encrypt => {
+                let converted = convert_newlines(&contents, config.newline);
+                encrypt_with_header(converted.as_deref().unwrap_or(&contents), config)?
+            }
+            Direction::Decrypt => {
+                let decrypted = decrypt_with_header(&contents, config)?;
+                convert_newlines(&decrypted, config.newline).unwrap_or(decrypted)
+            }
+        };
+        contents.check_unchanged(Path::new(in_file_path))?;
+        Ok(new_contents)
+    })?;
+    if let Some(shift) = shift {
+        info!("Detected shift: {shift}");
+    }
+
+    // Keep a copy of the output file being replaced, if asked to. This is left until the new contents are ready,
+    // so nothing gets backed up for a file that fails.
+    back_up(out_file_path, config)?;
+
+    // Write the encrypted/decrypted contents to the output file.
+    // Return an error upon failure.
+    let ((), write_time) = run_step(verbose, format!("Writing to {out_file_name}"), || {
+        Ok(write_with_progress(out_file_path, &new_contents, config.quiet, &carried)?)
+    })
+    .map_err(|e| e.while_writing(out_file_path))?;
+
+    // The same goes for the output file.
+    info!("Size of {out_file_name}: {} bytes", new_contents.len());
+    print_contents(out_file_name, &new_contents, config);
+
+    // Hash the output exactly as it was written, if asked to.
+    let checksum_out = config.checksum.then(|| sha256_hex(&new_contents));
+    if let Some(checksum) = &checksum_out {
+        info!("SHA-256 of {out_file_name}: {checksum}");
+    }
+
+    // Sum up how long it all took and how fast that was, with a breakdown of each phase at -vv.
+    let timings = Timings { read: read_time, transform: transform_time, write: write_time };
+    info!("{}", describe_timings(contents.len() as u64, &timings));
+    debug!("  Reading: {}", format_duration(timings.read));
+    debug!("  Encrypting/decrypting: {}", format_duration(timings.transform));
+    debug!("  Writing: {}", format_duration(timings.write));
+
+    // Only now that the output file is safely saved can the input file be shredded, if asked to. The input has to
+    // be let go of first, since it might be mapped into memory.
+    let bytes_in = contents.len();
+    drop(contents);
+    if let Some(passes) = config.shred_passes {
+        run_step(verbose, format!("Shredding {in_file_name}"), || Ok(shred_file(Path::new(in_file_path), passes)?))?;
+    }
+
+    // Return from the function with a signalling value that everything went okay,
+    // along with the sizes (and hashes) of the files for the summary.
+    Ok(Stats { bytes_in, bytes_out: new_contents.len(), checksum_in, checksum_out, timings, shift })
+}
+
+// This function overwrites a file with random bytes, as many times as asked, and then deletes it, so what was in
+// it is harder to get back. Each pass is flushed to disk before the next one starts. Filesystems that don't
+// write over the same place on disk (like copy-on-write ones, or SSDs moving blocks around) may still keep the
+// old contents somewhere, so this is a best effort.
+fn shred_file(path: &Path, passes: usize) -> io::Result<()> {
+    let shred = || {
+        let mut file = fs::File::options().write(true).open(path)?;
+        let len = file.metadata()?.len();
+        let mut buffer = vec![0; CHUNK_SIZE];
+        for _ in 0..passes {
+            file.seek(SeekFrom::Start(0))?;
+            let mut left = len;
+            while left > 0 {
+                let chunk = &mut buffer[..left.min(CHUNK_SIZE as u64) as usize];
+                OsRng.fill_bytes(chunk);
+                file.write_all(chunk)?;
+                left -= chunk.len() as u64;
+            }
+            file.sync_all()?;
+        }
+        drop(file);
+        fs::remove_file(path)
+    };
+    shred().map_err(|e| io::Error::new(e.kind(), format!("could not shred {}: {e}", path.display())))
+}
+
+// This function gives how many bytes a second were processed, or nothing if it was too quick to measure.
+fn throughput(bytes: u64, elapsed: Duration) -> Option<u64> {
+    let seconds = elapsed.as_secs_f64();
+    (seconds > 0.0).then(|| (bytes as f64 / seconds) as u64)
+}
+
+// This function describes how long processing a file took, like "1.2 GiB in 3.4 s (361.0 MiB/s)".
+fn describe_timings(bytes: u64, timings: &Timings) -> String {
+    let mut text = format!("{} in {}", format_bytes(bytes), format_duration(timings.total()));
+    if let Some(rate) = throughput(bytes, timings.total()) {
+        text += &format!(" ({}/s)", format_bytes(rate));
+    }
+    text
+}
+
+// This function prints a file's contents: all of them as text with --show-contents, or a hexdump of just the
+// start and end of them with "-vv", so a big file doesn't flood the terminal and binary data can be read.
+fn print_contents(name: &str, contents: &[u8], config: &Config) {
+    if config.show_contents && !config.quiet {
+        println!("Contents of {name}:\n{}", escape_contents(contents));
+    } else {
+        debug!("Contents of {name}:\n{}", hexdump(contents, PREVIEW_SIZE));
+    }
+}
+
+// This function makes a file's contents safe to print to a terminal. Control characters (other than line
+// breaks and tabs) could move the cursor, change colors, or worse, so they're written as escapes like
+// "\u{1b}". Bytes that aren't valid UTF-8 are written in hex, like "\xff", so binary data can still be read.
+fn escape_contents(contents: &[u8]) -> String {
+    let mut escaped = String::with_capacity(contents.len());
+    for chunk in contents.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_control() && c != '\n' && c != '\t' {
+                escaped.extend(c.escape_default());
+            } else {
+                escaped.push(c);
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{byte:02x}"));
+        }
+    }
+    escaped
+}
+
+// This function describes the cipher that will be used on some data for "-vv", along with any of its settings
+// that aren't secret. When decrypting, a header at the start of the data decides the cipher, just like it does
+// in decrypt_with_header.
+fn describe_cipher(data: &[u8], config: &Config) -> String {
+    let header_id = match config.mode {
+        Direction::Decrypt if config.header => {
+            FileHeader::parse(data).ok().flatten().map(|(header, _)| header.cipher_id)
+        }
+        _ => None,
+    };
+    match (config.mode, &config.recipient_key, &config.private_key, header_id) {
+        (Direction::Encrypt, Some(path), _, _) => return format!("public key encryption for the key in {path}"),
+        (Direction::Decrypt, _, Some(path), Some(RSA_CIPHER_ID | X25519_CIPHER_ID) | None) => {
+            return format!("public key encryption, using the private key in {path}")
+        }
+        _ => {}
+    }
+
+    let name = header_id
+        .and_then(|id| CIPHERS.iter().find(|cipher| cipher.id == id))
+        .map_or(config.cipher.as_str(), |cipher| cipher.name);
+    let setting = |value: Option<i32>| value.map_or(String::from("not set"), |value| value.to_string());
+    let settings = match name {
+        "affine" => format!(" (a = {}, b = {})", setting(config.affine_a), setting(config.affine_b)),
+        "rail-fence" => format!(" ({} rails)", setting(config.rails.map(|rails| rails as i32))),
+        "caesar" => {
+            let alphabet = config.alphabet_name.as_deref().unwrap_or("latin");
+            format!(" (shift = {}, {alphabet} alphabet)", setting(config.shift))
+        }
+        "columnar" => match &config.key {
+            Some(key) => format!(" (a key of {} characters)", key.chars().count()),
+            None => String::from(" (no key)"),
+        },
+        "substitution" => String::from(if config.alphabet.is_some() { " (custom alphabet)" } else { " (no alphabet)" }),
+        // When decrypting, the KDF is whichever one the header names.
+        PASSWORD_CIPHER if config.mode == Direction::Encrypt => {
+            format!(" (key derived from the password with {})", config.kdf.algorithm().name())
+        }
+        _ => String::new(),
+    };
+    let header = match header_id {
+        Some(_) => ", named by the file's header",
+        None if config.header && config.mode == Direction::Encrypt => ", with a header",
+        None => "",
+    };
+    format!("{name}{settings}{header}")
+}
+
+// This function reads a whole file a piece at a time, showing the progress for large files.
+// If it's bigger than `limit`, nothing is read and an error is given back instead.
+fn read_with_progress(path: &str, limit: Option<u64>, quiet: bool) -> Result<Vec<u8>, EncryptError> {
+    let mut file = fs::File::open(path)?;
+    let metadata = file.metadata()?;
+    let size = metadata.len();
+    if metadata.is_file() {
+        check_file_size(Path::new(path), size, limit)?;
+    }
+    let mut progress = Progress::new(format!("Reading {path}"), Some(size), quiet);
+    let mut bytes = Vec::with_capacity(size as usize);
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let count = file.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&buffer[..count]);
+        progress.advance(count);
+        // Something like a pipe has no size to check first, and could go on forever, so it's checked as it's read.
+        check_file_size(Path::new(path), bytes.len() as u64, limit)?;
+    }
+    progress.finish();
+    Ok(bytes)
+}
+
+// This function checks a file's size against the "--max-file-size" limit, if there is one.
+fn check_file_size(path: &Path, size: u64, limit: Option<u64>) -> Result<(), EncryptError> {
+    match limit {
+        Some(limit) if size > limit => Err(EncryptError::FileTooLarge { path: path.to_path_buf(), size, limit }),
+        _ => Ok(()),
+    }
+}
+
+// This enum holds the contents of a file that was read, either copied into memory or mapped into it.
+// Either way, it can be used like a slice of bytes. A mapped file also keeps the modification time it had when
+// it was mapped, to notice it being changed while it's in use.
+enum FileData {
+    Heap(Vec<u8>),
+    Mapped(Mmap, Option<SystemTime>),
+}
+
+impl FileData {
+    // This function checks that a mapped file hasn't been changed by another program since it was mapped. If it
+    // has, whatever was made from it is a mix of the old and new contents, so it shouldn't be saved. A file read
+    // into memory is a copy, so it can't change.
+    fn check_unchanged(&self, path: &Path) -> Result<(), EncryptError> {
+        let FileData::Mapped(map, modified) = self else { return Ok(()) };
+        let metadata = fs::metadata(path)?;
+        if metadata.len() != map.len() as u64 || metadata.modified().ok() != *modified {
+            return Err(EncryptError::InputChanged(path.to_path_buf()));
+        }
+        Ok(())
+    }
+}
+
+impl Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Heap(bytes) => bytes,
+            FileData::Mapped(map, _) => map,
+        }
+    }
+}
+
+// This function reads a file, mapping it into memory instead of copying it when it's at least
+// `mmap_threshold` bytes. That saves copying the whole file, and lets the OS load it in as it's needed.
+// If the file can't be mapped, it's read the usual way instead. Either way, one bigger than `limit` isn't read.
+fn read_file_smart(
+    path: &Path,
+    mmap_threshold: u64,
+    limit: Option<u64>,
+    quiet: bool,
+) -> Result<FileData, EncryptError> {
+    let metadata = fs::metadata(path)?;
+    if metadata.is_file() {
+        check_file_size(path, metadata.len(), limit)?;
+    }
+    if metadata.len() >= mmap_threshold {
+        if let Some(map) = map_file(path) {
+            return Ok(FileData::Mapped(map, metadata.modified().ok()));
+        }
+    }
+    Ok(FileData::Heap(read_with_progress(&path.to_string_lossy(), limit, quiet)?))
+}
+
+// This function maps a file into memory so it can be read without copying it.
+// Mapping isn't possible for everything (like pipes, empty files, or some network filesystems), so if it fails
+// this returns None, and the file should be read the usual way instead.
+//
+// A mapped file that gets shorter while it's mapped crashes the program when the missing part is read. To guard
+// against that, only regular files are mapped, and the mapping has to cover exactly the file's size when it was
+// checked. Callers must also make sure they don't overwrite the file while it's mapped.
+fn map_file(path: &Path) -> Option<Mmap> {
+    let file = fs::File::open(path).ok()?;
+    let metadata = file.metadata().ok()?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return None;
+    }
+    // SAFETY: the map is only read from, and callers don't shorten the file while it's mapped. Another program
+    // doing so is the risk every memory-mapped file takes.
+    let map = unsafe { Mmap::map(&file) }.ok()?;
+    (map.len() as u64 == metadata.len()).then_some(map)
+}
+
+// This function checks whether two paths lead to the same file, following any symbolic links and ".." on
+// the way.
+fn same_file(a: &str, b: &str) -> bool {
+    match (resolve_path(Path::new(a)), resolve_path(Path::new(b))) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+// This function works out the full path a path leads to. A file that doesn't exist yet can't be resolved
+// itself, so its directory is resolved instead, with the file's name added back on.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return Some(resolved);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(parent).ok()?.join(path.file_name()?))
+}
+
+// This function writes a whole file a piece at a time, showing the progress for large files.
+// The contents go into a temporary file next to the real one first, which is then renamed over it. Renaming
+// replaces the file all at once, so if writing fails part of the way through (or the app crashes), anything
+// reading the file sees either the old file or the new one, never half of one.
+fn write_with_progress(path: &str, contents: &[u8], quiet: bool, carried: &CarriedOver) -> io::Result<()> {
+    let temp_path = temp_path_for(Path::new(path));
+    // If the user presses Ctrl-C before the temporary file has been renamed, it gets removed.
+    let _partial = PartialFile::track(&temp_path);
+    let result =
+        write_temp_file(&temp_path, path, contents, quiet, carried).and_then(|()| fs::rename(&temp_path, path));
+    // Don't leave the temporary file lying around if anything went wrong.
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// What an output file takes on from its input file: its permissions and modification time. Either can be left
+// out, and a file written from scratch gets neither.
+#[derive(Default)]
+struct CarriedOver {
+    permissions: Option<fs::Permissions>,
+    modified: Option<SystemTime>,
+}
+
+// This function writes the contents to the temporary file, making sure they're on disk before it's renamed.
+// The temporary file is made with the input file's permissions from the start, so decrypted text is never
+// readable by anyone who couldn't read the input. The owner can always write to it though, even when the input
+// is read-only; its permissions and modification time are only set exactly once the contents are in.
+fn write_temp_file(
+    temp_path: &Path,
+    path: &str,
+    contents: &[u8],
+    quiet: bool,
+    carried: &CarriedOver,
+) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if let Some(permissions) = &carried.permissions {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(permissions.mode() & 0o777 | 0o600);
+    }
+    let mut file = options.open(temp_path)?;
+    let mut progress = Progress::new(format!("Writing {path}"), Some(contents.len() as u64), quiet);
+    for chunk in contents.chunks(CHUNK_SIZE) {
+        file.write_all(chunk)?;
+        progress.advance(chunk.len());
+    }
+    progress.finish();
+    if let Some(modified) = carried.modified {
+        file.set_modified(modified)?;
+    }
+    if let Some(permissions) = &carried.permissions {
+        file.set_permissions(permissions.clone())?;
+    }
+    file.sync_all()
+}
+
+// This function picks a name for the temporary file to write `path` through. It's in the same directory, since
+// renaming can't move a file to another filesystem, and it's hidden, named after the file, and numbered, so
+// several files (or several copies of the app) being written at once don't clash.
+fn temp_path_for(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let number = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let name = path.file_name().map_or_else(|| String::from("output"), |name| name.to_string_lossy().into_owned());
+    path.with_file_name(format!(".{name}.{}-{number}.tmp", process::id()))
+}
+
+// This function changes every line ending ("\n" or "\r\n") in some plain text to the one "--newline" asked for,
+// in one pass from start to end. A lone "\r" isn't a line ending, so it's left alone, and so is a last line
+// without a line ending. It gives back None when the line endings are to be left as they are.
+fn convert_newlines(data: &[u8], newline: Newline) -> Option<Vec<u8>> {
+    let ending: &[u8] = match newline {
+        Newline::Preserve => return None,
+        Newline::Lf => b"\n",
+        Newline::Crlf => b"\r\n",
+    };
+    let mut converted = Vec::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+        let line = &rest[..end];
+        converted.extend_from_slice(line.strip_suffix(b"\r").unwrap_or(line));
+        converted.extend_from_slice(ending);
+        rest = &rest[end + 1..];
+    }
+    converted.extend_from_slice(rest);
+    Some(converted)
+}
+
+// This function encrypts a file's contents, starting the result with a header saying which cipher was used.
+fn encrypt_with_header(data: &[u8], config: &Config) -> Result<Vec<u8>, EncryptError> {
+    if config.output_format == OutputFormat::Jwe {
+        return Ok(jwe::encrypt_direct(&jwe_key(config)?, &NONCES.generate()?, data).into_bytes());
+    }
+    let (id, encrypted) = match &config.recipient_key {
+        Some(path) => encrypt_for_key_file(data, path)?,
+        None => {
+            let id = CIPHERS.iter().find(|cipher| cipher.name == config.cipher).map_or(0, |cipher| cipher.id);
+            if config.cipher == PASSWORD_CIPHER {
+                return encrypt_with_password(data, id, config);
+            }
+            (id, transform(data, config)?)
+        }
+    };
+    if !config.header {
+        return Ok(encrypted);
+    }
+    if config.mac {
+        return Ok(add_mac(id, &encrypted, config)?);
+    }
+    let mut with_header = FileHeader::new(id).to_bytes();
+    with_header.extend(encrypted);
+    Ok(with_header)
+}
+
+// This function puts a header in front of data encrypted by a cipher that doesn't authenticate it, and an
+// HMAC-SHA256 tag after it, for "--mac". The tag covers the header as well, so the cipher it names can't be
+// swapped for another. Its key is derived from the password the same way a password cipher's is, with the KDF,
+// its settings, and the salt in the header.
+fn add_mac(id: u8, encrypted: &[u8], config: &Config) -> Result<Vec<u8>, CipherError> {
+    let header = password_header(id, config).with_mac(true);
+    let keys = FileKeys::derive(config, &header)?;
+    let mut with_mac = header.to_bytes();
+    with_mac.extend_from_slice(encrypted);
+    let tag = compute_hmac(keys.mac.as_bytes(), &with_mac);
+    with_mac.extend(tag);
+    Ok(with_mac)
+}
+
+// This function checks the HMAC-SHA256 tag of some data against the one it should have, giving an error if it
+// doesn't match. The tags are compared in constant time, so how long it takes gives nothing away about the
+// right tag.
+fn verify_hmac(key: &[u8], data: &[u8], tag: &[u8]) -> Result<(), EncryptError> {
+    match verify_mac_constant_time(&compute_hmac(key, data), tag) {
+        true => Ok(()),
+        false => Err(EncryptError::MacMismatch),
+    }
+}
+
+// This function checks the tag at the end of a file encrypted with "--mac", giving back the encrypted data
+// between the header and the tag if it matches. Nothing is decrypted until it has.
+fn check_mac<'a>(data: &'a [u8], header: &FileHeader, config: &Config) -> Result<&'a [u8], EncryptError> {
+    if config.key.is_none() {
+        let cipher = CIPHERS.iter().find(|cipher| cipher.id == header.cipher_id).map_or("unknown", |c| c.name);
+        let missing = "Checking its MAC needs the password, with --key or --key-file.";
+        return Err(EncryptError::MissingSettings { cipher, missing });
+    }
+    let Some(tag_start) = data.len().checked_sub(MAC_LEN).filter(|&start| start >= header.len()) else {
+        return Err(EncryptError::MacMismatch);
+    };
+    let keys = FileKeys::derive(config, header)?;
+    verify_hmac(keys.mac.as_bytes(), &data[..tag_start], &data[tag_start..])?;
+    Ok(&data[header.len()..tag_start])
+}
+
+// This function encrypts a file's contents with a key derived from the password, for ChaCha20-Poly1305. The
+// header carries everything needed to derive the key again except the password: which KDF was used, its
+// settings, and a random salt. It also carries the nonce, and is authenticated along with the contents, so
+// changing any of it makes decrypting fail.
+fn encrypt_with_password(data: &[u8], id: u8, config: &Config) -> Result<Vec<u8>, EncryptError> {
+    let header = password_header(id, config).with_nonce(NONCES.generate()?.to_vec());
+    let keys = FileKeys::derive(config, &header)?;
+    let mut with_header = header.to_bytes();
+    let encrypted = ChaCha20Poly1305.encrypt(keys.cipher.as_bytes(), &header.nonce, data, &with_header)?;
+    with_header.extend(encrypted);
+    Ok(with_header)
+}
+
+// This function decrypts a file's contents encrypted by encrypt_with_password, given its header.
+fn decrypt_with_password(encrypted: &[u8], header: &FileHeader, config: &Config) -> Result<Vec<u8>, EncryptError> {
+    let keys = FileKeys::derive(config, header)?;
+    Ok(ChaCha20Poly1305.decrypt(keys.cipher.as_bytes(), &header.nonce, encrypted, &header.to_bytes())?)
+}
+
+// This function makes the header for a file whose key comes from the password: the cipher, the KDF picked with
+// "--kdf" and its settings, and a new random salt.
+fn password_header(id: u8, config: &Config) -> FileHeader {
+    let mut salt = vec![0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    FileHeader::new(id).with_kdf(config.kdf.id(), config.kdf.algorithm().params_to_bytes(), salt)
+}
+
+// This struct holds the keys for one file whose key comes from the password: one for the cipher, and one for
+// the MAC. Both come from a single master key, derived from the password with the KDF, settings, and salt in
+// the file's header. The master key then goes through HKDF (RFC 5869) once for each, with a different `info`,
+// so neither key says anything about the other. The master key is dropped, and zeroed, as soon as they're
+// derived; it's never given to a cipher or a MAC itself.
+struct FileKeys {
+    // The key for the cipher, from HKDF with info = "cipher".
+    cipher: SecretKey,
+    // The key for the MAC, from HKDF with info = "mac".
+    mac: SecretKey,
+}
+
+impl FileKeys {
+    // This function derives the keys for a file from the password and its header.
+    fn derive(config: &Config, header: &FileHeader) -> Result<FileKeys, CipherError> {
+        let name = kdf_name(header.kdf_id)
+            .ok_or_else(|| CipherError::InvalidInput(format!("the header names an unknown KDF ({})", header.kdf_id)))?;
+        let kdf = kdf_from_bytes(name, &header.kdf_params)?;
+        let password = config.key.as_deref().map_or(&b""[..], |key| key.as_bytes());
+        let mut master = SecretKey::zeroed();
+        kdf.derive(password, &header.salt, master.as_mut_bytes())?;
+        Ok(FileKeys {
+            cipher: derive_subkeys(master.as_bytes(), &header.salt, b"cipher"),
+            mac: derive_subkeys(master.as_bytes(), &header.salt, b"mac"),
+        })
+    }
+}
+
+// This function reads the key for "--output-format jwe", which is used as it is rather than derived from a
+// password: 32 bytes, in Base64 or hex, the way "gen-key" writes them.
+fn jwe_key(config: &Config) -> Result<SecretKey, CipherError> {
+    let text = config.key.as_deref().map_or("", |key| key.trim());
+    let bytes = if text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        let digit = |c: u8| (c as char).to_digit(16).expect("every character is a hex digit") as u8;
+        Zeroizing::new(text.as_bytes().chunks(2).map(|pair| digit(pair[0]) << 4 | digit(pair[1])).collect())
+    } else {
+        Zeroizing::new(STANDARD.decode(text).unwrap_or_default())
+    };
+    let wrong = "a JWE key has to be 32 bytes in Base64 or hex, like gen-key makes";
+    SecretKey::from_slice(&bytes).ok_or_else(|| CipherError::InvalidKey(String::from(wrong)))
+}
+
+// This function encrypts some data for the holder of the public key in a key file, returning the cipher ID to
+// put in the header along with the encrypted data. The key can be an age-style X25519 key or a PEM RSA key.
+fn encrypt_for_key_file(data: &[u8], path: &str) -> Result<(u8, Vec<u8>), EncryptError> {
+    let key_file = fs::read_to_string(path)?;
+    if is_age_key(&key_file) {
+        Ok((X25519_CIPHER_ID, encrypt_x25519(data, &parse_public_key(&key_file)?)?))
+    } else {
+        Ok((RSA_CIPHER_ID, encrypt_for_recipient(data, &key_file)?))
+    }
+}
+
+// This function decrypts data encrypted by encrypt_for_key_file, using the private key in a key file.
+// If the file's header said which kind of key it was encrypted for, that's given as `cipher_id`.
+fn decrypt_with_key_file(data: &[u8], config: &Config, cipher_id: Option<u8>) -> Result<Vec<u8>, EncryptError> {
+    let path = config.private_key.as_deref().expect("only called with --private-key");
+    let key_file = Zeroizing::new(fs::read_to_string(path)?);
+    let is_x25519 = is_age_key(&key_file);
+    if cipher_id.is_some_and(|id| (id == X25519_CIPHER_ID) != is_x25519) {
+        let expected = if is_x25519 { "an RSA" } else { "an X25519" };
+        return Err(CipherError::InvalidKey(format!("the file was encrypted for {expected} key")).into());
+    }
+    if is_x25519 {
+        decrypt_x25519(data, &parse_secret_key(&key_file)?)
+    } else {
+        let passphrase = config.passphrase.as_ref().map(|passphrase| passphrase.as_bytes());
+        decrypt_with_private_key(data, &key_file, passphrase)
+    }
+}
+
+// This function decrypts a file's contents with the cipher named in its header. Files without a header (like
+// ones from before there were headers) are decrypted with whichever cipher the user picked instead.
+fn decrypt_with_header(data: &[u8], config: &Config) -> Result<Vec<u8>, EncryptError> {
+    if config.output_format == OutputFormat::Jwe {
+        let token = std::str::from_utf8(data)
+            .map_err(|e| CipherError::InvalidInput(format!("a JWE token is text, but this isn't valid UTF-8 ({e})")))?;
+        return Ok(jwe::decrypt_direct(token, &jwe_key(config)?)?);
+    }
+    let header = if config.header { FileHeader::parse(data)? } else { None };
+    let Some((header, encrypted)) = header else {
+        // A file encrypted for someone else without a header can still be decrypted by giving the private key.
+        if config.private_key.is_some() {
+            return decrypt_with_key_file(data, config, None);
+        }
+        return Ok(transform(data, config)?);
+    };
+    // Nothing this version writes is compressed or armored, so it can't undo either.
+    if header.compressed || header.armored {
+        let message = "the file was compressed or armored when it was encrypted, which this version can't undo";
+        return Err(CipherError::InvalidInput(String::from(message)).into());
+    }
+    // A file with a MAC is checked before any of it is decrypted.
+    let encrypted = if header.mac { check_mac(data, &header, config)? } else { encrypted };
+    if header.cipher_id == RSA_CIPHER_ID || header.cipher_id == X25519_CIPHER_ID {
+        if config.private_key.is_none() {
+            let missing = "Decrypting it needs the matching --private-key.";
+            let cipher = if header.cipher_id == RSA_CIPHER_ID { "RSA recipient" } else { "X25519 recipient" };
+            return Err(EncryptError::MissingSettings { cipher, missing });
+        }
+        return decrypt_with_key_file(encrypted, config, Some(header.cipher_id));
+    }
+    let cipher = CIPHERS
+        .iter()
+        .find(|cipher| cipher.id == header.cipher_id)
+        .ok_or(EncryptError::UnknownCipher(header.cipher_id))?;
+    let detected_config = Config { cipher: String::from(cipher.name), ..config.clone() };
+    check_cipher_settings(&detected_config)
+        .map_err(|missing| EncryptError::MissingSettings { cipher: cipher.name, missing })?;
+    if cipher.name == PASSWORD_CIPHER {
+        return decrypt_with_password(encrypted, &header, &detected_config);
+    }
+    Ok(transform(encrypted, &detected_config)?)
+}
+
+// This function decrypts text encrypted with an unknown Caesar shift, for "--detect-shift". Every shift is tried,
+// and the one whose result looks most like English (with letter frequencies closest to English's) is picked.
+// A header, if there is one, isn't part of the text. It gives back the decrypted text and the shift.
+fn decrypt_detecting_shift(data: &[u8]) -> Result<(Vec<u8>, i32), EncryptError> {
+    let data = match FileHeader::parse(data)? {
+        // There's no password to check the MAC with, and nothing is decrypted without checking it.
+        Some((header, _)) if header.mac => {
+            let message = "the file has a MAC, so decrypt it with its --shift and --key instead of --detect-shift";
+            return Err(CipherError::InvalidInput(String::from(message)).into());
+        }
+        Some((_, encrypted)) => encrypted,
+        None => data,
+    };
+    let text = std::str::from_utf8(data)
+        .map_err(|e| CipherError::InvalidInput(format!("the text isn't valid UTF-8 ({e})")))?;
+    let best = crack_caesar(text)
+        .into_iter()
+        .min_by(|a, b| a.score.total_cmp(&b.score))
+        .expect("there are always 25 shifts to try");
+    Ok((best.text.into_bytes(), best.shift))
+}
+
+// This function gives a warning for "--detect-shift" when there wasn't enough text to be sure of the shift,
+// suggesting "crack" to see every shift instead. `crack_args` is what to give "crack" for the same input.
+fn short_input_warning(name: &str, crack_args: &str, bytes: usize, config: &Config) -> Option<String> {
+    (bytes < config.detect_min_size).then(|| {
+        format!(
+            "{name} is only {bytes} bytes, which may be too little to find the right shift; \
+             try {} crack {crack_args} to see every shift.",
+            env!("CARGO_PKG_NAME")
+        )
+    })
+}
+
+// This function encrypts/decrypts a file's contents with whichever cipher the user picked.
+fn transform(data: &[u8], config: &Config) -> Result<Vec<u8>, CipherError> {
+    let cipher = select_cipher(config);
+    if data.len() >= PARALLEL_THRESHOLD && config.threads > 1 && cipher.works_letter_by_letter() {
+        return transform_in_chunks(data, cipher.as_ref(), config);
+    }
+    cipher.process(data, config.mode)
+}
+
+// This function encrypts/decrypts text in fixed-size pieces, several at a time on different threads, and
+// joins the results back together in order. It gives exactly the same result as doing it all in one go,
+// as long as the cipher works letter by letter.
+fn transform_in_chunks(data: &[u8], cipher: &dyn Cipher, config: &Config) -> Result<Vec<u8>, CipherError> {
+    // Cut the text into pieces, moving each cut forward if it would land in the middle of a character.
+    // Every byte after the first in a UTF-8 character starts with the bits 10.
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let mut end = (start + PARALLEL_CHUNK_SIZE).min(data.len());
+        while end < data.len() && data[end] & 0xC0 == 0x80 {
+            end += 1;
+        }
+        chunks.push(&data[start..end]);
+        start = end;
+    }
+
+    let next_chunk = AtomicUsize::new(0);
+    let results = Mutex::new((0..chunks.len()).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..config.threads.min(chunks.len()) {
+            scope.spawn(|| loop {
+                let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                let Some(chunk) = chunks.get(index) else { break };
+                let result = cipher.process(chunk, config.mode);
+                results.lock().unwrap_or_else(PoisonError::into_inner)[index] = Some(result);
+            });
+        }
+    });
+
+    let mut transformed = Vec::with_capacity(data.len());
+    for result in results.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        // Every piece gets done before the threads finish, so there's always a result.
+        transformed.extend_from_slice(&result.expect("every chunk is transformed")?);
+    }
+    Ok(transformed)
+}
+
+// This function gives the name of the cipher files are encrypted/decrypted with, for the JSON output and the log.
+fn cipher_name(config: &Config) -> &'static str {
+    if config.detect_shift {
+        "caesar"
+    } else {
+        select_cipher(config).name()
+    }
+}
+
+// This function turns the name of the cipher the user picked into the cipher itself, along with its settings.
+// A new cipher only needs to implement the Cipher trait and be added here (and to CIPHERS).
+fn select_cipher(config: &Config) -> Box<dyn Cipher> {
+    match config.cipher.as_str() {
+        // Multiplying by 1 and shifting by 0 leaves a letter alone, so those are the defaults.
+        "affine" => Box::new(Affine { a: config.affine_a.unwrap_or(1), b: config.affine_b.unwrap_or(0) }),
+        "rail-fence" => Box::new(RailFence { rails: config.rails.unwrap_or(1) }),
+        "caesar" => {
+            let name = config.alphabet_name.as_deref().unwrap_or("latin");
+            let alphabet = named_alphabet(name).expect("the name was checked when reading the arguments");
+            Box::new(Caesar { shift: config.shift.unwrap_or(0), alphabet })
+        }
+        "columnar" => Box::new(Columnar { key: config.key.clone().unwrap_or_default() }),
+        "substitution" => match &config.alphabet {
+            Some(mapping) => Box::new(Substitution { mapping: mapping.clone() }),
+            None => Box::new(Rot13),
+        },
+        "base32" => Box::new(Base32),
+        "rot13-5" => Box::new(Rot13Rot5),
+        "rot13-unicode" => Box::new(Rot13Unicode),
+        PASSWORD_CIPHER => Box::new(ChaCha20Poly1305),
+        _ => Box::new(Rot13),
+    }
+}
+
+// This function gets the "stem" of a path, which is the filename minus the last extension.
+fn get_file_name(filename: &str) -> &str {
+    match Path::new(filename).file_stem() {
+        // If this isn't a valid filename, return the name unchanged.
+        None => filename,
+        Some(os_str) => match os_str.to_str() {
+            // If the filename couldn't be decoded via valid Unicode, return the name unchanged.
+            None => filename,
+            // Otherwise, return the stem of the filename.
+            Some(name) => name
+        }
+    }
+}
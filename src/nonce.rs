@@ -0,0 +1,77 @@
+//! Nonces for the authenticated ciphers, which must never use the same nonce twice with the same key.
+//!
+//! A repeated nonce lets an attacker undo the encryption of both messages (by XORing them together) and forge
+//! new ones, so it has to be ruled out rather than just made unlikely. Nonces here are 96 random bits from the
+//! operating system. By the birthday bound, the chance of any two of `n` of them matching is about
+//! `n² / 2⁹⁷`: even after 2³² nonces that's around 1 in 2³³, so a collision is never expected. A
+//! [`NonceRegistry`] makes sure of it anyway by remembering every nonce it has handed out, which costs only
+//! 12 bytes a nonce.
+//!
+//! ```
+//! use rust_project::nonce::NonceRegistry;
+//!
+//! let registry = NonceRegistry::new();
+//! let first = registry.generate().unwrap();
+//! let second = registry.generate().unwrap();
+//! assert_ne!(first, second);
+//! assert_eq!(registry.len(), 2);
+//! ```
+
+use std::collections::HashSet;
+use std::sync::{Mutex, PoisonError};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use crate::CipherError;
+
+/// The size of a nonce in bytes, as used by ChaCha20-Poly1305 and AES-GCM.
+pub const NONCE_LEN: usize = 12;
+
+/// Makes a random nonce with the operating system's secure random number generator.
+///
+/// Prefer [`NonceRegistry::generate`] when several nonces might be used with the same key.
+pub fn generate_nonce() -> [u8; NONCE_LEN] {
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Keeps track of every nonce generated through it, so none is ever handed out twice.
+///
+/// It can be shared between threads, so a single registry can cover every file in a batch.
+#[derive(Debug, Default)]
+pub struct NonceRegistry {
+    used: Mutex<HashSet<[u8; NONCE_LEN]>>,
+}
+
+impl NonceRegistry {
+    /// Makes a registry that hasn't handed out any nonces yet.
+    pub fn new() -> NonceRegistry {
+        NonceRegistry::default()
+    }
+
+    /// Makes a random nonce that this registry has never handed out before.
+    ///
+    /// A repeat means the random number generator is broken, so rather than trying again it's reported as
+    /// [`CipherError::InvalidInput`], and nothing should be encrypted.
+    pub fn generate(&self) -> Result<[u8; NONCE_LEN], CipherError> {
+        let nonce = generate_nonce();
+        // A thread that panicked while holding the lock can't have left the set half-changed.
+        let mut used = self.used.lock().unwrap_or_else(PoisonError::into_inner);
+        if !used.insert(nonce) {
+            return Err(CipherError::InvalidInput(String::from(
+                "the same nonce came up twice, so the random number generator can't be trusted",
+            )));
+        }
+        Ok(nonce)
+    }
+
+    /// How many nonces this registry has handed out.
+    pub fn len(&self) -> usize {
+        self.used.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Whether this registry hasn't handed out any nonces yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
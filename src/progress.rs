@@ -0,0 +1,190 @@
+// This module draws progress updates in place on the terminal, like the "working"/"complete!" messages in
+// verbose mode and the progress indicator shown while reading and writing large files. When the output isn't
+// going to a terminal, nothing is drawn in place, so no control characters end up in logs.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+use crate::color::{paint, Color, Stream};
+
+// Redrawing more often than this only makes the terminal flicker. It's also how long an operation has to
+// take before any progress is shown at all, so small files don't flash up a progress indicator.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+// What a verbose step's status line says until the step is over.
+const WORKING: &str = "working";
+
+// This function replaces the last `previous_len` characters written to a terminal with new text.
+// The "\x08" character is the ASCII backspace character. It moves the cursor back one space (but does not
+// delete), so if the new text is shorter than the old text, the leftover characters are covered with spaces.
+pub fn redraw(out: &mut impl Write, previous_len: usize, text: &str) -> io::Result<()> {
+    // Here we use a format string to fill an arbitrary length space with backspace characters.
+    write!(out, "{:\x08<1$}{text}", "", previous_len)?;
+    let leftover = previous_len.saturating_sub(text.chars().count());
+    if leftover > 0 {
+        write!(out, "{:leftover$}{:\x08<leftover$}", "", "")?;
+    }
+    out.flush()
+}
+
+// This struct is the line printed for a step in verbose mode, like "Reading notes... working". On a terminal,
+// "working" is replaced once the step is over by going back to the start of the line and drawing the whole
+// thing again. Anywhere else (like a log file), that would leave control characters in the file, so the
+// outcome is just added to the end of the line instead.
+pub struct StatusLine {
+    // What the step is doing, like "Reading notes".
+    message: String,
+    // When the step started, for showing how long it took.
+    start: Instant,
+    // Whether stdout is a terminal, so the line can be redrawn.
+    terminal: bool,
+}
+
+impl StatusLine {
+    // This function prints the line for a step that's starting.
+    pub fn start(message: String) -> StatusLine {
+        let mut stdout = io::stdout().lock();
+        let status = StatusLine { message, start: Instant::now(), terminal: stdout.is_terminal() };
+        // Failing to print a status message isn't worth stopping the program over.
+        let _ = status.draw_start(&mut stdout);
+        status
+    }
+
+    // This function finishes the line with "complete!" and how long the step took.
+    pub fn finish_ok(self) {
+        let elapsed = format!("({})", format_duration(self.start.elapsed()));
+        let outcome = format!("{} {elapsed}", paint("complete!", Color::Green, Stream::Stdout));
+        self.finish(&outcome);
+    }
+
+    // This function finishes the line with a message saying the step failed.
+    pub fn finish_err(self, message: &str) {
+        self.finish(&paint(message, Color::Red, Stream::Stdout));
+    }
+
+    // This function finishes the line with the step's outcome.
+    fn finish(self, outcome: &str) {
+        let mut stdout = io::stdout().lock();
+        let _ = self.draw_finish(&mut stdout, outcome);
+    }
+
+    // This function draws the start of the line.
+    fn draw_start(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.terminal {
+            write!(out, "{}... {WORKING}", self.message)?;
+        } else {
+            write!(out, "{}... ", self.message)?;
+        }
+        out.flush()
+    }
+
+    // This function draws the end of the line. On a terminal the whole line is drawn again from the start, with
+    // spaces over anything left of "working" if the outcome is shorter.
+    fn draw_finish(&self, out: &mut impl Write, outcome: &str) -> io::Result<()> {
+        if self.terminal {
+            let leftover = WORKING.len().saturating_sub(outcome.chars().count());
+            writeln!(out, "\r{}... {outcome}{:leftover$}", self.message, "")
+        } else {
+            writeln!(out, "{outcome}")
+        }
+    }
+}
+
+// This function formats a number of bytes for people to read, using binary units (1 KiB is 1024 bytes).
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+// This function formats a length of time for people to read: in milliseconds if it's under a second, so quick
+// operations don't all show up as "0.0 s", and in seconds otherwise.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 1.0 {
+        format!("{:.1} ms", seconds * 1000.0)
+    } else {
+        format!("{seconds:.1} s")
+    }
+}
+
+// This struct shows how far through reading or writing a file the program is, on stderr.
+// The progress is drawn after whatever is already on the line and erased again when finished, so it
+// doesn't get in the way of the normal output. Nothing is drawn when stderr isn't a terminal (for example
+// when it's redirected to a log file), or when the user asked for quiet output.
+pub struct Progress {
+    // What's being done, like "Reading notes.txt".
+    label: String,
+    // How many bytes there are in total, if that's known.
+    total: Option<u64>,
+    // How many bytes have been done so far.
+    done: u64,
+    // When the operation started, for working out the throughput.
+    start: Instant,
+    // When the progress was last drawn, if it has been drawn at all.
+    last_draw: Option<Instant>,
+    // How many characters were drawn last time, so they can be replaced.
+    drawn_len: usize,
+    // Whether to draw anything at all.
+    enabled: bool,
+}
+
+impl Progress {
+    // This function starts keeping track of an operation's progress.
+    pub fn new(label: String, total: Option<u64>, quiet: bool) -> Progress {
+        Progress {
+            label,
+            total,
+            done: 0,
+            start: Instant::now(),
+            last_draw: None,
+            drawn_len: 0,
+            enabled: !quiet && io::stderr().is_terminal(),
+        }
+    }
+
+    // This function records that some more bytes have been done, redrawing the progress if it's been a while.
+    pub fn advance(&mut self, bytes: usize) {
+        self.done += bytes as u64;
+        if !self.enabled {
+            return;
+        }
+        let since = self.last_draw.unwrap_or(self.start);
+        if since.elapsed() >= REDRAW_INTERVAL {
+            self.draw();
+        }
+    }
+
+    // This function erases the progress once the operation is over, leaving the cursor back where it was
+    // before the progress was drawn.
+    pub fn finish(&mut self) {
+        if self.enabled && self.drawn_len > 0 {
+            let _ = redraw(&mut io::stderr().lock(), self.drawn_len, "");
+            self.drawn_len = 0;
+        }
+    }
+
+    // This function draws the bytes done so far, the percentage done (if the total is known), and the throughput.
+    fn draw(&mut self) {
+        let seconds = self.start.elapsed().as_secs_f64();
+        let throughput = if seconds > 0.0 { (self.done as f64 / seconds) as u64 } else { 0 };
+        let mut text = format!(" [{}: {}", self.label, format_bytes(self.done));
+        if let Some(total) = self.total.filter(|&total| total > 0) {
+            text += &format!(" of {} ({:.0}%)", format_bytes(total), self.done as f64 * 100.0 / total as f64);
+        }
+        text += &format!(", {}/s]", format_bytes(throughput));
+
+        // Anything printed to stdout without a newline is still waiting to be shown, and needs to go first.
+        let _ = io::stdout().flush();
+        let _ = redraw(&mut io::stderr().lock(), self.drawn_len, &text);
+        self.drawn_len = text.chars().count();
+        self.last_draw = Some(Instant::now());
+    }
+}
@@ -0,0 +1,93 @@
+//! A Python extension module, built with the `python` feature and `maturin build` (see `pyproject.toml`).
+//!
+//! The module is called `rfec`. It encrypts bytes with ChaCha20-Poly1305, using a key derived from the
+//! passphrase with Argon2id, and starts them with a [`FileHeader`] holding the KDF's settings, the salt, and the
+//! nonce. What it gives back is the same as a file encrypted by the command-line tool with
+//! `--cipher chacha20-poly1305 --key`:
+//!
+//! ```python
+//! import rfec
+//!
+//! encrypted = rfec.encrypt_bytes(b"Hello, World!", "correct horse")
+//! assert rfec.decrypt_bytes(encrypted, "correct horse") == b"Hello, World!"
+//! ```
+//!
+//! A wrong passphrase, data that's been changed, or anything else that can't be encrypted/decrypted raises a
+//! `ValueError`, never a panic.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use crate::kdf::{derive_subkeys, kdf_from_bytes, kdf_name, KdfParams, ARGON2ID_DEFAULT};
+use crate::nonce::generate_nonce;
+use crate::{ChaCha20Poly1305, Cipher, CipherError, FileHeader, SecretKey};
+
+/// The ID ChaCha20-Poly1305 is saved with in file headers, the same one the command-line tool uses.
+const CHACHA20_POLY1305_CIPHER_ID: u8 = 12;
+
+/// The size of the random salt the passphrase is mixed with, in bytes, the same as the command-line tool's.
+const SALT_LEN: usize = 16;
+
+/// Encrypts `data`, which can be any bytes, with a key derived from `passphrase`.
+#[pyfunction]
+fn encrypt_bytes(data: &[u8], passphrase: &str) -> PyResult<Vec<u8>> {
+    if passphrase.is_empty() {
+        return Err(PyValueError::new_err("the passphrase can't be empty"));
+    }
+    let kdf = KdfParams::Argon2id(ARGON2ID_DEFAULT);
+    let mut salt = vec![0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let header = FileHeader::new(CHACHA20_POLY1305_CIPHER_ID)
+        .with_kdf(kdf.id(), kdf.algorithm().params_to_bytes(), salt)
+        .with_nonce(generate_nonce().to_vec());
+    let key = cipher_key(&header, passphrase).map_err(value_error)?;
+    let mut encrypted = header.to_bytes();
+    let sealed = ChaCha20Poly1305.encrypt(key.as_bytes(), &header.nonce, data, &encrypted).map_err(value_error)?;
+    encrypted.extend(sealed);
+    Ok(encrypted)
+}
+
+/// Decrypts data encrypted by `encrypt_bytes` (or the command-line tool's chacha20-poly1305 cipher) with
+/// `passphrase`.
+///
+/// The data is authenticated before anything is given back, so a wrong passphrase, or data that's been changed
+/// at all, raises a `ValueError`. So does data that wasn't encrypted this way.
+#[pyfunction]
+fn decrypt_bytes(data: &[u8], passphrase: &str) -> PyResult<Vec<u8>> {
+    let (header, encrypted) = match FileHeader::parse(data).map_err(value_error)? {
+        Some((header, _)) if header.cipher_id != CHACHA20_POLY1305_CIPHER_ID => {
+            return Err(PyValueError::new_err(format!("the data was encrypted with cipher ID {}", header.cipher_id)));
+        }
+        Some((header, _)) if header.mac || header.compressed || header.armored => {
+            return Err(PyValueError::new_err("the data has a MAC, or was compressed or armored"));
+        }
+        Some(parsed) => parsed,
+        None => return Err(PyValueError::new_err("the data doesn't start with a header")),
+    };
+    let key = cipher_key(&header, passphrase).map_err(value_error)?;
+    ChaCha20Poly1305.decrypt(key.as_bytes(), &header.nonce, encrypted, &header.to_bytes()).map_err(value_error)
+}
+
+// This function derives the cipher's key from the passphrase the same way the command-line tool does: a master
+// key from the KDF and salt in the header, and then the cipher's own sub-key from that with HKDF.
+fn cipher_key(header: &FileHeader, passphrase: &str) -> Result<SecretKey, CipherError> {
+    let name = kdf_name(header.kdf_id)
+        .ok_or_else(|| CipherError::InvalidInput(format!("the header names an unknown KDF ({})", header.kdf_id)))?;
+    let mut master = SecretKey::zeroed();
+    kdf_from_bytes(name, &header.kdf_params)?.derive(passphrase.as_bytes(), &header.salt, master.as_mut_bytes())?;
+    Ok(derive_subkeys(master.as_bytes(), &header.salt, b"cipher"))
+}
+
+// This function turns a cipher's error into a Python ValueError with the same message.
+fn value_error(e: CipherError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// The `rfec` Python module.
+#[pymodule]
+fn rfec(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(encrypt_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_bytes, m)?)?;
+    Ok(())
+}
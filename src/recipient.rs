@@ -0,0 +1,87 @@
+// This module encrypts files for someone else using their RSA public key, so only their private key can
+// decrypt them. RSA can only encrypt a few hundred bytes, so it's used to encrypt a random session key, and
+// the file itself is encrypted with ChaCha20-Poly1305 using that session key.
+//
+// The encrypted data is laid out as:
+//   - the length of the encrypted session key, as 2 bytes (big-endian)
+//   - the session key, encrypted with RSA-OAEP (SHA-256)
+//   - the 12 byte nonce
+//   - the file encrypted with ChaCha20-Poly1305, followed by its 16 byte authentication tag
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use sha2::Sha256;
+use rust_project::nonce::NONCE_LEN;
+use rust_project::{CipherError, SecretKey, Zeroizing};
+use crate::{EncryptError, NONCES};
+
+// This function encrypts some data so only the holder of the private key matching `pub_key_pem` can decrypt it.
+// The public key can be in either PKCS#8 ("BEGIN PUBLIC KEY") or PKCS#1 ("BEGIN RSA PUBLIC KEY") PEM format.
+pub fn encrypt_for_recipient(plaintext: &[u8], pub_key_pem: &str) -> Result<Vec<u8>, EncryptError> {
+    let public_key = RsaPublicKey::from_public_key_pem(pub_key_pem)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(pub_key_pem))
+        .map_err(|e| CipherError::InvalidKey(format!("couldn't read the RSA public key ({e})")))?;
+
+    // A new session key and nonce are made for every file, so no two files are ever encrypted the same way.
+    let mut session_key = SecretKey::zeroed();
+    OsRng.fill_bytes(session_key.as_mut_bytes());
+    let encrypted_key = public_key
+        .encrypt(&mut OsRng, Oaep::new::<Sha256>(), session_key.as_ref())
+        .map_err(|e| CipherError::InvalidKey(format!("couldn't encrypt the session key ({e})")))?;
+    let nonce = NONCES.generate()?;
+    let ciphertext = ChaCha20Poly1305::new(session_key.as_ref().into())
+        .encrypt(&nonce.into(), plaintext)
+        .map_err(|_| CipherError::InvalidInput(String::from("the file is too big to encrypt")))?;
+
+    let mut encrypted = Vec::with_capacity(2 + encrypted_key.len() + NONCE_LEN + ciphertext.len());
+    encrypted.extend_from_slice(&(encrypted_key.len() as u16).to_be_bytes());
+    encrypted.extend_from_slice(&encrypted_key);
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+// This function decrypts data encrypted by encrypt_for_recipient, using the private key in `priv_key_pem`.
+// The private key can be in PKCS#8 ("BEGIN PRIVATE KEY"), encrypted PKCS#8 ("BEGIN ENCRYPTED PRIVATE KEY",
+// which needs the passphrase), or PKCS#1 ("BEGIN RSA PRIVATE KEY") PEM format.
+pub fn decrypt_with_private_key(
+    ciphertext: &[u8],
+    priv_key_pem: &str,
+    passphrase: Option<&[u8]>,
+) -> Result<Vec<u8>, EncryptError> {
+    if passphrase.is_none() && priv_key_pem.contains("BEGIN ENCRYPTED PRIVATE KEY") {
+        let message = "the RSA private key is encrypted; give its passphrase with --passphrase-file";
+        return Err(CipherError::InvalidKey(String::from(message)).into());
+    }
+    let private_key = match passphrase {
+        Some(passphrase) => RsaPrivateKey::from_pkcs8_encrypted_pem(priv_key_pem, passphrase),
+        None => RsaPrivateKey::from_pkcs8_pem(priv_key_pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(priv_key_pem).map_err(rsa::pkcs8::Error::from)),
+    }
+    .map_err(|e| CipherError::InvalidKey(format!("couldn't read the RSA private key ({e})")))?;
+
+    // Split the data back into its parts, making sure none of them are cut short.
+    let truncated = || CipherError::InvalidInput(String::from("the encrypted data has been cut short"));
+    let (length, rest) = ciphertext.split_first_chunk::<2>().ok_or_else(truncated)?;
+    let key_len = u16::from_be_bytes(*length) as usize;
+    if rest.len() < key_len + NONCE_LEN {
+        return Err(truncated().into());
+    }
+    let (encrypted_key, rest) = rest.split_at(key_len);
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+
+    let wrong_key = || CipherError::InvalidKey(String::from("the file wasn't encrypted for this private key"));
+    let decrypted_key = Zeroizing::new(
+        private_key.decrypt(Oaep::new::<Sha256>(), encrypted_key).map_err(|_| wrong_key())?,
+    );
+    let session_key = SecretKey::from_slice(&decrypted_key).ok_or_else(wrong_key)?;
+    let plaintext = ChaCha20Poly1305::new(session_key.as_ref().into())
+        .decrypt(nonce.into(), sealed)
+        .map_err(|_| CipherError::InvalidInput(String::from("the file has been changed since it was encrypted")))?;
+    Ok(plaintext)
+}
@@ -0,0 +1,56 @@
+//! JavaScript bindings for the ciphers, built with the `wasm` feature and `wasm-pack build --features wasm`.
+//!
+//! Nothing here reads files or the environment: the JavaScript side hands over the text or bytes and gets the
+//! result back.
+//!
+//! ```js
+//! import { encrypt_rot13 } from "rust_project";
+//!
+//! encrypt_rot13("Hello, World!"); // "Uryyb, Jbeyq!"
+//! ```
+
+use wasm_bindgen::prelude::*;
+use crate::nonce::{generate_nonce, NONCE_LEN};
+use crate::{rot13, Aes256Gcm, Base32, Cipher, CipherError, Direction};
+
+/// Encrypts (or decrypts, since it's its own inverse) text with ROT13.
+#[wasm_bindgen]
+pub fn encrypt_rot13(text: &str) -> String {
+    rot13(text)
+}
+
+/// Encodes any bytes (a `Uint8Array` on the JavaScript side) as Base32 text.
+#[wasm_bindgen]
+pub fn encode_base32(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    Base32.process(data, Direction::Encrypt).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Decodes Base32 text back into the bytes it was made from, throwing an `Error` if it isn't valid Base32.
+#[wasm_bindgen]
+pub fn decode_base32(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    Base32.process(data, Direction::Decrypt).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Encrypts bytes with AES-256-GCM and a 32-byte key, under a new random nonce. The nonce goes first in the
+/// result, followed by the encrypted bytes and the 16-byte tag, so [`decrypt_aes_gcm`] needs only the key.
+#[wasm_bindgen]
+pub fn encrypt_aes_gcm(data: &[u8], key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let nonce = generate_nonce();
+    let sealed = Aes256Gcm.encrypt(key, &nonce, data, b"").map_err(js_error)?;
+    Ok([&nonce[..], &sealed].concat())
+}
+
+/// Decrypts bytes made by [`encrypt_aes_gcm`], throwing an `Error` if the key is wrong or they've been changed.
+#[wasm_bindgen]
+pub fn decrypt_aes_gcm(data: &[u8], key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    if data.len() < NONCE_LEN {
+        return Err(js_error(CipherError::InvalidInput(String::from("the data is too short to have a nonce"))));
+    }
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    Aes256Gcm.decrypt(key, nonce, sealed, b"").map_err(js_error)
+}
+
+// This function turns a cipher's error into a JavaScript `Error` to throw.
+fn js_error(error: CipherError) -> JsValue {
+    JsError::new(&error.to_string()).into()
+}
@@ -0,0 +1,103 @@
+// This module walks someone through encrypting a file when the app is started on a terminal without any
+// arguments, for people who'd rather answer a few questions than learn the options. It asks for the file to
+// read, where to save the result, and which cipher to use (along with the cipher's settings), and gives back
+// the arguments that would have done the same, so they're checked and run exactly like typed ones.
+//
+// The questions are asked on whatever reader and writer are passed in rather than on the terminal itself.
+
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use crate::CIPHERS;
+
+// This function asks the questions, giving back the arguments to run the app with (without the app's name).
+// Running out of input before every question is answered is an error.
+pub fn ask(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<Vec<String>> {
+    writeln!(output, "No arguments were given, so a few questions instead (run with --help to see every option).")?;
+
+    // The input file has to be there already.
+    let in_path = loop {
+        let answer = read_answer(input, output, "File to encrypt: ")?;
+        let path = Path::new(&answer);
+        if answer.is_empty() {
+            continue;
+        } else if path.is_file() {
+            break answer;
+        } else if path.exists() {
+            writeln!(output, "{answer} isn't a file. Try again.")?;
+        } else {
+            writeln!(output, "There's no file called {answer}. Try again.")?;
+        }
+    };
+
+    // Saving over a file that's already there has to be confirmed.
+    let mut force = false;
+    let out_path = loop {
+        let answer = read_answer(input, output, "Save the result to: ")?;
+        let path = Path::new(&answer);
+        if answer.is_empty() {
+            continue;
+        } else if path.is_dir() {
+            writeln!(output, "{answer} is a directory. Give the name of a file to save to.")?;
+        } else if !path.exists() {
+            break answer;
+        } else if confirm(input, output, &format!("{answer} already exists. Overwrite it? [y/N] "))? {
+            force = true;
+            break answer;
+        }
+    };
+
+    writeln!(output, "Ciphers:")?;
+    for (i, cipher) in CIPHERS.iter().enumerate() {
+        writeln!(output, "  {}. {:<14} {}", i + 1, cipher.name, cipher.description)?;
+    }
+    let cipher = loop {
+        let answer = read_answer(input, output, &format!("Cipher (1-{}, or Enter for 1): ", CIPHERS.len()))?;
+        if answer.is_empty() {
+            break &CIPHERS[0];
+        }
+        match answer.parse::<usize>() {
+            Ok(number) if (1..=CIPHERS.len()).contains(&number) => break &CIPHERS[number - 1],
+            _ => writeln!(output, "Pick a number from 1 to {}.", CIPHERS.len())?,
+        }
+    };
+
+    let mut args = vec![String::from("--cipher"), cipher.name.to_string()];
+    // Each setting is an option followed by what its value is called, like "--rails N". Keys and passwords
+    // aren't asked for here, since they're asked for later without showing what's typed.
+    if let Some(settings) = cipher.key.filter(|settings| !settings.starts_with("--key")) {
+        let words: Vec<&str> = settings.split_whitespace().collect();
+        for setting in words.chunks(2) {
+            let (option, value) = (setting[0], setting.get(1).copied().unwrap_or("value"));
+            let answer = loop {
+                let answer = read_answer(input, output, &format!("{value} (for {option}): "))?;
+                if !answer.is_empty() {
+                    break answer;
+                }
+            };
+            args.extend([option.to_string(), answer]);
+        }
+    }
+    if force {
+        args.push(String::from("--force"));
+    }
+    // The paths go after "--", so a file name starting with a dash isn't taken for an option.
+    args.extend([String::from("--"), in_path, out_path]);
+    Ok(args)
+}
+
+// This function asks a question and reads the answer, without the spaces around it.
+fn read_answer(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> io::Result<String> {
+    write!(output, "{question}")?;
+    output.flush()?;
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no answer was given"));
+    }
+    Ok(answer.trim().to_string())
+}
+
+// This function asks a yes or no question, where anything but yes counts as no.
+fn confirm(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> io::Result<bool> {
+    let answer = read_answer(input, output, question)?;
+    Ok(matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,117 @@
+// This module encrypts files for someone else using their X25519 public key, which is much smaller and faster
+// than RSA. It follows the same idea as the age file format, and uses the same key encoding, but the
+// encrypted data is laid out in its own (simpler) way:
+//   - the ephemeral public key, 32 bytes
+//   - the 12 byte nonce
+//   - the file encrypted with ChaCha20-Poly1305, followed by its 16 byte authentication tag
+//
+// A new ephemeral key pair is made for every file. Its secret half and the recipient's public key agree on a
+// shared secret, and HKDF-SHA256 turns that into the key for ChaCha20-Poly1305. The recipient gets the same
+// shared secret from their secret key and the ephemeral public key.
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use rand::rngs::OsRng;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use rust_project::kdf::derive_subkeys;
+use rust_project::nonce::NONCE_LEN;
+use rust_project::{CipherError, SecretKey, Zeroizing};
+use crate::{EncryptError, NONCES};
+
+// This is mixed into the key derivation, so a key derived here can't be mistaken for one derived for
+// anything else.
+const HKDF_INFO: &[u8] = b"rust_project x25519 v1";
+
+// This function encrypts some data so only the holder of the secret key matching `recipient_pub` can decrypt it.
+pub fn encrypt_x25519(plaintext: &[u8], recipient_pub: &[u8; 32]) -> Result<Vec<u8>, EncryptError> {
+    let recipient = PublicKey::from(*recipient_pub);
+    let ephemeral_secret = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral_secret);
+    let shared = ephemeral_secret.diffie_hellman(&recipient);
+    // A few bad public keys give a shared secret of all zeros, which anyone could work out.
+    if !shared.was_contributory() {
+        return Err(CipherError::InvalidKey(String::from("the X25519 public key is not a usable key")).into());
+    }
+    let key = derive_key(shared.as_bytes(), ephemeral_public.as_bytes(), recipient_pub);
+
+    let nonce = NONCES.generate()?;
+    let ciphertext = ChaCha20Poly1305::new(key.as_ref().into())
+        .encrypt(&nonce.into(), plaintext)
+        .map_err(|_| CipherError::InvalidInput(String::from("the file is too big to encrypt")))?;
+
+    let mut encrypted = Vec::with_capacity(32 + NONCE_LEN + ciphertext.len());
+    encrypted.extend_from_slice(ephemeral_public.as_bytes());
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+// This function decrypts data encrypted by encrypt_x25519, using the recipient's secret key.
+pub fn decrypt_x25519(ciphertext: &[u8], recipient_priv: &SecretKey) -> Result<Vec<u8>, EncryptError> {
+    if ciphertext.len() < 32 + NONCE_LEN {
+        return Err(CipherError::InvalidInput(String::from("the encrypted data has been cut short")).into());
+    }
+    let (ephemeral_public, rest) = ciphertext.split_at(32);
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+    let ephemeral_public: [u8; 32] = ephemeral_public.try_into().expect("split at 32 bytes");
+
+    let secret = StaticSecret::from(*recipient_priv.as_bytes());
+    let recipient_pub = PublicKey::from(&secret);
+    let shared = secret.diffie_hellman(&PublicKey::from(ephemeral_public));
+    let key = derive_key(shared.as_bytes(), &ephemeral_public, recipient_pub.as_bytes());
+
+    let plaintext = ChaCha20Poly1305::new(key.as_ref().into()).decrypt(nonce.into(), sealed).map_err(|_| {
+        CipherError::InvalidInput(String::from(
+            "the file wasn't encrypted for this secret key, or has been changed since it was encrypted",
+        ))
+    })?;
+    Ok(plaintext)
+}
+
+// This function turns a shared secret into a ChaCha20-Poly1305 key. Both public keys go in as the salt, so the
+// key belongs to this exact exchange.
+fn derive_key(shared: &[u8; 32], ephemeral_pub: &[u8; 32], recipient_pub: &[u8; 32]) -> SecretKey {
+    let mut salt = [0; 64];
+    salt[..32].copy_from_slice(ephemeral_pub);
+    salt[32..].copy_from_slice(recipient_pub);
+    derive_subkeys(shared, &salt, HKDF_INFO)
+}
+
+// This function checks whether a key file holds an age-style X25519 key (public or secret) rather than a
+// PEM one.
+pub fn is_age_key(key_file: &str) -> bool {
+    key_line(key_file).is_some_and(|line| {
+        line.starts_with("age1") || line.to_ascii_uppercase().starts_with("AGE-SECRET-KEY-1")
+    })
+}
+
+// This function reads an X25519 public key written the way age writes them, like "age1...".
+pub fn parse_public_key(key_file: &str) -> Result<[u8; 32], CipherError> {
+    Ok(decode_key(key_file, "age")?.as_slice().try_into().expect("the key was checked to be 32 bytes"))
+}
+
+// This function reads an X25519 secret key written the way age writes them, like "AGE-SECRET-KEY-1...".
+pub fn parse_secret_key(key_file: &str) -> Result<SecretKey, CipherError> {
+    Ok(SecretKey::from_slice(&decode_key(key_file, "age-secret-key-")?).expect("the key was checked to be 32 bytes"))
+}
+
+// This function decodes the bech32 key in a key file, checking it's the expected kind of key and 32 bytes long.
+fn decode_key(key_file: &str, expected_hrp: &str) -> Result<Zeroizing<Vec<u8>>, CipherError> {
+    let line = key_line(key_file).ok_or_else(|| CipherError::InvalidKey(String::from("the key file is empty")))?;
+    let (hrp, data) = bech32::decode(line)
+        .map_err(|e| CipherError::InvalidKey(format!("the key isn't valid bech32 ({e})")))?;
+    let data = Zeroizing::new(data);
+    if hrp.to_lowercase() != expected_hrp {
+        return Err(CipherError::InvalidKey(format!("expected a key starting with {expected_hrp}1")));
+    }
+    if data.len() != 32 {
+        return Err(CipherError::InvalidKey(String::from("an X25519 key must be 32 bytes")));
+    }
+    Ok(data)
+}
+
+// This function finds the key in a key file, skipping blank lines and "#" comments like the ones age-keygen
+// writes.
+fn key_line(key_file: &str) -> Option<&str> {
+    key_file.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'))
+}
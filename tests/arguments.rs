@@ -0,0 +1,102 @@
+// Options can go anywhere among the files, short options without a value can share one dash, and everything
+// after "--" is a file. This runs the built app with the same options in different places and checks it does
+// the same thing, and that unknown options are refused by name.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+// This function writes notes.txt.enc (notes.txt encrypted with rot13), so it can be decrypted.
+fn write_encrypted(dir: &Path) {
+    fs::write(dir.join("notes.txt"), "Meet at noon.\n").unwrap();
+    let output = run_app(dir, &["--force", "notes.txt", "notes.txt.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn options_work_before_between_and_after_the_files() {
+    let temp = scratch_dir();
+    write_encrypted(temp.path());
+    for args in [
+        ["-d", "--force", "notes.txt.enc", "out.txt"],
+        ["notes.txt.enc", "-d", "--force", "out.txt"],
+        ["notes.txt.enc", "out.txt", "-d", "--force"],
+        ["--force", "notes.txt.enc", "out.txt", "-d"],
+    ] {
+        let _ = fs::remove_file(temp.path().join("out.txt"));
+        let output = run_app(temp.path(), &args);
+        assert!(output.status.success(), "{args:?}: {}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(fs::read_to_string(temp.path().join("out.txt")).unwrap(), "Meet at noon.\n", "{args:?}");
+    }
+    // An option that takes a value takes the next argument, wherever it is.
+    let output = run_app(temp.path(), &["notes.txt", "--cipher", "base32", "out.b32", "--force"]);
+    assert!(output.status.success());
+    assert!(fs::read(temp.path().join("out.b32")).unwrap().ends_with(b"JVSWK5BAMF2CA3TPN5XC4CQ="));
+}
+
+#[test]
+fn short_options_can_share_one_dash() {
+    let temp = scratch_dir();
+    write_encrypted(temp.path());
+    fs::write(temp.path().join("out.txt"), "already here").unwrap();
+    // "-dfq" is "-d -f -q": decrypt, overwrite out.txt, and print nothing.
+    let output = run_app(temp.path(), &["-dfq", "notes.txt.enc", "out.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read_to_string(temp.path().join("out.txt")).unwrap(), "Meet at noon.\n");
+
+    // "-vv" is still very verbose, and so is "-v -v".
+    for verbose in [&["-vv"][..], &["-v", "-v"], &["-fvv"]] {
+        let output = run_app(temp.path(), &[verbose, &["--force", "notes.txt", "notes.txt.enc"]].concat());
+        assert!(output.status.success(), "{verbose:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Contents of notes"), "{verbose:?}");
+    }
+
+    // An option that takes a value can't share a dash, since it wouldn't be clear where the value goes.
+    for combined in ["-dk", "-qx", "-fc"] {
+        let output = run_app(temp.path(), &[combined, "notes.txt.enc", "out.txt"]);
+        assert_eq!(output.status.code(), Some(2), "{combined}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains(&format!("Unknown option {combined};")));
+    }
+}
+
+#[test]
+fn everything_after_a_double_dash_is_a_file() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("-v"), "Not an option.\n").unwrap();
+    let output = run_app(temp.path(), &["-q", "--", "-v"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read(temp.path().join("-v.enc")).unwrap(), b"FENC\x01\x01Abg na bcgvba.\n");
+
+    // "-d" before it is the option, and after it is the file to save to.
+    let output = run_app(temp.path(), &["-d", "--", "-v.enc", "-d"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(temp.path().join("-d")).unwrap(), "Not an option.\n");
+}
+
+#[test]
+fn unknown_options_are_refused_by_name() {
+    let temp = scratch_dir();
+    write_encrypted(temp.path());
+    for unknown in ["--bogus", "-x", "-", "---force"] {
+        for args in [
+            [unknown, "notes.txt", "out.txt"],
+            ["notes.txt", unknown, "out.txt"],
+            ["notes.txt", "out.txt", unknown],
+        ] {
+            let output = run_app(temp.path(), &args);
+            assert_eq!(output.status.code(), Some(2), "{args:?}");
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.contains(&format!("Unknown option {unknown};")), "{args:?}: {stderr}");
+        }
+    }
+    // Before "--", "-v" is the option even if there's a file called that.
+    fs::write(temp.path().join("-v"), "Not an option.\n").unwrap();
+    let output = run_app(temp.path(), &["notes.txt", "-v", "--force"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Program completed."));
+    assert!(!temp.path().join("-v.enc").exists());
+}
@@ -0,0 +1,46 @@
+// Output files are written to a temporary file and renamed into place, so a write that fails part of the way
+// through never leaves a half-written output file behind. This runs the built app into write failures and
+// checks what's left in the directory afterwards.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use common::{run_app, scratch_dir};
+
+// This function lists the names of the files in a directory, in order.
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> =
+        fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn successful_write_leaves_only_the_output() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert!(run_app(dir, &["--no-header", "notes.txt", "notes.enc"]).status.success());
+    assert_eq!(file_names(dir), ["notes.enc", "notes.txt"]);
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "Uryyb, Jbeyq!");
+}
+
+// Limiting how big a file the app may write makes writing the output fail part of the way through. Ignoring
+// SIGXFSZ turns going over the limit into an ordinary write error instead of killing the app.
+#[cfg(unix)]
+#[test]
+fn failed_write_keeps_the_old_output_and_cleans_up() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "a".repeat(100_000)).unwrap();
+    fs::write(dir.join("notes.enc"), "old contents").unwrap();
+    let app = env!("CARGO_BIN_EXE_rust_project");
+    let script = format!("trap '' XFSZ; ulimit -f 1; exec '{app}' --force notes.txt notes.enc");
+    let output =
+        Command::new("sh").args(["-c", &script]).current_dir(dir).env("XDG_CONFIG_HOME", dir).output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(file_names(dir), ["notes.enc", "notes.txt"]);
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+}
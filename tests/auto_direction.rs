@@ -0,0 +1,46 @@
+// Given a single file and nowhere to save it, the app works out whether to encrypt or decrypt it from its name.
+// This runs the built app on files with and without the extension and checks where they're saved.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+#[test]
+fn plain_file_is_encrypted_and_enc_file_is_decrypted() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--columnar", "--key", "ZEBRAS", "notes.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(dir.join("notes.txt.enc").exists());
+
+    // The header names the cipher, so only the key is needed to decrypt.
+    fs::remove_file(dir.join("notes.txt")).unwrap();
+    let output = run_app(dir, &["--key", "ZEBRAS", "notes.txt.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn suffix_decides_which_files_are_decrypted() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert!(run_app(dir, &["--suffix", "secret", "notes.txt"]).status.success());
+    fs::remove_file(dir.join("notes.txt")).unwrap();
+    assert!(run_app(dir, &["--suffix", "secret", "notes.txt.secret"]).status.success());
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+}
+
+// Saying which direction always wins over the file's name.
+#[test]
+fn encrypt_or_decrypt_given_overrides_the_name() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt.enc"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--encrypt", "notes.txt.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(dir.join("notes.txt.enc.enc").exists());
+    assert!(!dir.join("notes.txt").exists());
+}
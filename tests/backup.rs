@@ -0,0 +1,48 @@
+// With --backup, an output file that's about to be overwritten is copied to a ".bak" file first. This runs the
+// built app over outputs that are already there and checks what's kept.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+#[test]
+fn overwritten_output_is_backed_up() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("notes.enc"), b"old \xff bytes").unwrap();
+    let output = run_app(dir, &["--backup", "--force", "--no-header", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read(dir.join("notes.enc.bak")).unwrap(), b"old \xff bytes");
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "Uryyb, Jbeyq!");
+}
+
+// There's nothing to back up when the output file is new.
+#[test]
+fn new_output_has_no_backup() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert!(run_app(dir, &["--backup", "notes.txt", "notes.enc"]).status.success());
+    assert!(dir.join("notes.enc").exists());
+    assert!(!dir.join("notes.enc.bak").exists());
+}
+
+// Saving a file over itself backs up the original, and an older backup is only replaced with --force.
+#[test]
+fn existing_backup_needs_force() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("notes.txt.bak"), "older backup").unwrap();
+    let output = run_app(dir, &["--backup", "--in-place", "--no-header", "notes.txt"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+    assert_eq!(fs::read_to_string(dir.join("notes.txt.bak")).unwrap(), "older backup");
+
+    let output = run_app(dir, &["--backup", "--in-place", "--force", "--no-header", "notes.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Uryyb, Jbeyq!");
+    assert_eq!(fs::read_to_string(dir.join("notes.txt.bak")).unwrap(), "Hello, World!");
+}
@@ -0,0 +1,74 @@
+// The Caesar cipher rotates letters through an alphabet given in order, either one of the built-in ones picked
+// with --alphabet-name or any list of characters through the library. This checks both, with accented letters and
+// ones written as more than one character.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use rust_project::{named_alphabet, rotate_alphabet};
+
+#[test]
+fn custom_alphabet_wraps_around() {
+    let alphabet = ['x', 'ñ', 'é', '🦀'];
+    assert_eq!(rotate_alphabet("xñé🦀 abc", 1, &alphabet), "ñé🦀x abc");
+    assert_eq!(rotate_alphabet("ñé🦀x abc", -1, &alphabet), "xñé🦀 abc");
+    assert_eq!(rotate_alphabet("xñé🦀", 6, &alphabet), "é🦀xñ");
+}
+
+// "ñ" written as "n" followed by a combining tilde is two characters. Only the "n" is in the alphabet, so it's
+// rotated and the tilde stays after the new letter, while the precomposed "ñ" is rotated as a letter of its own.
+#[test]
+fn a_letter_with_a_combining_mark_is_rotated_by_its_base_letter() {
+    let alphabet = ['n', 'ñ', 'o'];
+    assert_eq!(rotate_alphabet("n\u{303}", 1, &alphabet), "ñ\u{303}");
+    assert_eq!(rotate_alphabet("ñ\u{303}", -1, &alphabet), "n\u{303}");
+    assert_eq!(rotate_alphabet("ñ", 1, &alphabet), "o");
+}
+
+// A lower case alphabet rotates upper case letters too, keeping their case. One with both cases in it treats
+// them as different letters.
+#[test]
+fn case_is_kept_only_when_the_alphabet_has_one_case() {
+    let spanish = named_alphabet("spanish").unwrap();
+    assert_eq!(rotate_alphabet("Ñandú", 1, &spanish), "Oáñeü");
+    assert_eq!(rotate_alphabet("Oáñeü", -1, &spanish), "Ñandú");
+    assert_eq!(rotate_alphabet("aA", 1, &['a', 'A']), "Aa");
+    assert_eq!(rotate_alphabet("ẞß", 1, &['ß', 'x']), "ẞx");
+}
+
+#[test]
+fn named_alphabets_rotate_their_own_letters() {
+    assert_eq!(rotate_alphabet("Hello, World!", 3, &named_alphabet("latin").unwrap()), "Khoor, Zruog!");
+    assert_eq!(rotate_alphabet("Ёж", 1, &named_alphabet("cyrillic").unwrap()), "Жз");
+    assert_eq!(rotate_alphabet("Ωω", 1, &named_alphabet("greek").unwrap()), "Αα");
+    assert_eq!(rotate_alphabet("Ñandú", 1, &named_alphabet("latin").unwrap()), "Ñboeú");
+}
+
+#[test]
+fn files_are_encrypted_and_decrypted_through_a_named_alphabet() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "¿Mañana? ¡Sí!").unwrap();
+    let output = run_app(dir, &["--shift", "1", "--alphabet-name", "spanish", "--no-header", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "¿Náoáñá? ¡Tj!");
+
+    let output = run_app(dir, &["--decrypt", "--shift", "1", "--alphabet-name", "spanish", "notes.enc", "notes.dec"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "¿Mañana? ¡Sí!");
+}
+
+#[test]
+fn unknown_alphabets_and_a_missing_shift_are_refused() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hola").unwrap();
+    let output = run_app(dir, &["--shift", "1", "--alphabet-name", "klingon", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("latin, spanish, cyrillic, greek"));
+    let output = run_app(dir, &["--cipher", "caesar", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs --shift"));
+    assert!(!dir.join("notes.enc").exists());
+}
@@ -0,0 +1,166 @@
+// Every cipher must give back exactly what it was given when decrypting with the same key it encrypted with.
+// This checks that on many random texts and keys for each cipher, both through its own functions and through
+// the Cipher trait the app uses. The authenticated ciphers must also refuse the wrong key or changed data.
+
+use proptest::prelude::*;
+use rust_project::{
+    affine_decrypt, affine_encrypt, base32_decode, base32_encode, columnar_decrypt, columnar_encrypt,
+    named_alphabet, rail_fence_decrypt, rail_fence_encrypt, rot13, rot13_5, rot13_unicode, rotate_alphabet, rotate_by,
+    substitute, Aes256Gcm, Affine, Base32, Caesar, ChaCha20Poly1305, Cipher, Columnar, Direction, RailFence, Rot13,
+    Rot13Rot5, Rot13Unicode, Substitution, NAMED_ALPHABETS,
+};
+use zeroize::Zeroizing;
+
+// The values of `a` the affine cipher accepts: the ones coprime with 26.
+const AFFINE_KEYS: [i32; 12] = [1, 3, 5, 7, 9, 11, 15, 17, 19, 21, 23, 25];
+
+// This function makes a random substitution alphabet, by shuffling the letters A to Z.
+fn alphabet() -> impl Strategy<Value = [char; 26]> {
+    Just(('A'..='Z').collect::<Vec<char>>())
+        .prop_shuffle()
+        .prop_map(|letters| letters.try_into().unwrap())
+}
+
+// This function checks that a cipher's decryption undoes its encryption, through the Cipher trait.
+fn round_trip(cipher: &dyn Cipher, data: &[u8]) -> Result<(), TestCaseError> {
+    let encrypted = cipher.process(data, Direction::Encrypt)?;
+    prop_assert_eq!(cipher.process(&encrypted, Direction::Decrypt)?, data);
+    Ok(())
+}
+
+// This function checks that an authenticated cipher's decryption undoes its encryption with the same key, nonce,
+// and additional data, and that another key, or additional data with one bit changed, is refused rather than
+// giving back anything at all.
+fn aead_round_trip(
+    cipher: &dyn Cipher,
+    (key, other_key, nonce): ([u8; 32], [u8; 32], [u8; 12]),
+    data: &[u8],
+    aad: &[u8],
+    flip: prop::sample::Index,
+) -> Result<(), TestCaseError> {
+    let sealed = cipher.encrypt(&key, &nonce, data, aad)?;
+    prop_assert_eq!(cipher.decrypt(&key, &nonce, &sealed, aad)?, data);
+    if other_key != key {
+        prop_assert!(cipher.decrypt(&other_key, &nonce, &sealed, aad).is_err());
+    }
+    let mut changed_aad = aad.to_vec();
+    if aad.is_empty() {
+        changed_aad.push(0);
+    } else {
+        changed_aad[flip.index(aad.len())] ^= 1;
+    }
+    prop_assert!(cipher.decrypt(&key, &nonce, &sealed, &changed_aad).is_err());
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn rot13_round_trips(text in any::<String>()) {
+        round_trip(&Rot13, text.as_bytes())?;
+    }
+
+    // ROT13 with ROT5 is its own inverse, and letters stay letters and digits stay digits.
+    #[test]
+    fn rot13_5_round_trips(text in any::<String>()) {
+        let encrypted = rot13_5(&text);
+        prop_assert_eq!(rot13_5(&encrypted), text.clone());
+        for (before, after) in text.chars().zip(encrypted.chars()) {
+            prop_assert_eq!(before.is_ascii_alphabetic(), after.is_ascii_alphabetic());
+            prop_assert_eq!(before.is_ascii_digit(), after.is_ascii_digit());
+        }
+        round_trip(&Rot13Rot5, text.as_bytes())?;
+    }
+
+    // The Unicode ROT13 is its own inverse on any text, including the odd-sized Hebrew alphabet, and changes ASCII
+    // just like plain ROT13.
+    #[test]
+    fn rot13_unicode_round_trips(text in any::<String>(), hebrew in "[\u{5D0}-\u{5EA}]*") {
+        prop_assert_eq!(rot13_unicode(&rot13_unicode(&text)), text.clone());
+        prop_assert_eq!(rot13_unicode(&rot13_unicode(&hebrew)), hebrew);
+        if text.is_ascii() {
+            prop_assert_eq!(rot13_unicode(&text), rot13(&text));
+        }
+        round_trip(&Rot13Unicode, text.as_bytes())?;
+    }
+
+    // Rotating back by the same shift undoes a Caesar cipher, whatever the shift, even one past 26 or negative.
+    #[test]
+    fn caesar_round_trips(text in any::<String>(), shift in any::<i32>()) {
+        prop_assert_eq!(rotate_by(&rotate_by(&text, shift), shift.wrapping_neg()), text);
+    }
+
+    // The same goes for rotating through any of the named alphabets, on text with letters of both cases from it.
+    #[test]
+    fn caesar_over_an_alphabet_round_trips(
+        (name, text) in prop::sample::select(&NAMED_ALPHABETS[..]).prop_flat_map(|(name, letters)| {
+            let letters: Vec<char> = letters.chars().flat_map(|c| c.to_uppercase().chain([c])).collect();
+            let character = prop_oneof![prop::sample::select(letters), any::<char>()];
+            (Just(name), prop::collection::vec(character, 0..50).prop_map(String::from_iter))
+        }),
+        shift in any::<i32>(),
+    ) {
+        let alphabet = named_alphabet(name).unwrap();
+        let small_shift = shift % 1000;
+        let encrypted = rotate_alphabet(&text, small_shift, &alphabet);
+        prop_assert_eq!(rotate_alphabet(&encrypted, -small_shift, &alphabet), text.clone());
+        round_trip(&Caesar { shift, alphabet }, text.as_bytes())?;
+    }
+
+    #[test]
+    fn affine_round_trips(text in any::<String>(), a in prop::sample::select(&AFFINE_KEYS[..]), b in any::<i32>()) {
+        prop_assert_eq!(affine_decrypt(&affine_encrypt(&text, a, b)?, a, b)?, text.clone());
+        round_trip(&Affine { a, b }, text.as_bytes())?;
+    }
+
+    // Every `a` that isn't coprime with 26 is turned down, rather than encrypting text that can't be decrypted.
+    #[test]
+    fn affine_rejects_keys_it_cant_undo(text in any::<String>(), a in any::<i32>(), b in any::<i32>()) {
+        prop_assume!(!AFFINE_KEYS.contains(&a.rem_euclid(26)));
+        prop_assert!(affine_encrypt(&text, a, b).is_err());
+    }
+
+    #[test]
+    fn substitution_round_trips(text in any::<String>(), mapping in alphabet()) {
+        prop_assert_eq!(substitute(&substitute(&text, &mapping, false), &mapping, true), text.clone());
+        round_trip(&Substitution { mapping: Zeroizing::new(mapping) }, text.as_bytes())?;
+    }
+
+    #[test]
+    fn rail_fence_round_trips(text in any::<String>(), rails in 1usize..20) {
+        prop_assert_eq!(rail_fence_decrypt(&rail_fence_encrypt(&text, rails)?, rails)?, text.clone());
+        round_trip(&RailFence { rails }, text.as_bytes())?;
+    }
+
+    #[test]
+    fn columnar_round_trips(text in any::<String>(), key in "[A-Za-z]{1,12}") {
+        prop_assert_eq!(columnar_decrypt(&columnar_encrypt(&text, &key)?, &key)?, text.clone());
+        round_trip(&Columnar { key: Zeroizing::new(key) }, text.as_bytes())?;
+    }
+
+    // Base32 works on any bytes, not just text.
+    #[test]
+    fn base32_round_trips(data in any::<Vec<u8>>()) {
+        prop_assert_eq!(base32_decode(&base32_encode(&data))?, data.clone());
+        round_trip(&Base32, &data)?;
+    }
+
+    #[test]
+    fn chacha20_poly1305_round_trips(
+        keys in any::<([u8; 32], [u8; 32], [u8; 12])>(),
+        data in any::<Vec<u8>>(),
+        aad in any::<Vec<u8>>(),
+        flip in any::<prop::sample::Index>(),
+    ) {
+        aead_round_trip(&ChaCha20Poly1305, keys, &data, &aad, flip)?;
+    }
+
+    #[test]
+    fn aes_256_gcm_round_trips(
+        keys in any::<([u8; 32], [u8; 32], [u8; 12])>(),
+        data in any::<Vec<u8>>(),
+        aad in any::<Vec<u8>>(),
+        flip in any::<prop::sample::Index>(),
+    ) {
+        aead_round_trip(&Aes256Gcm, keys, &data, &aad, flip)?;
+    }
+}
@@ -0,0 +1,91 @@
+// Ciphers are used through the Cipher trait's encrypt and decrypt, which take a key, a nonce, and additional
+// data to authenticate. This checks ChaCha20-Poly1305 against the RFC 8439 test vector, that the classical
+// ciphers work the same way with an empty key and nonce, and that a cipher from another crate (this one) can be
+// registered with the global registry and found through it.
+
+use rust_project::{ChaCha20Poly1305, Cipher, CipherError, CipherRegistry, Direction, RailFence, Rot13};
+
+fn hex(text: &str) -> Vec<u8> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    digits.chunks(2).map(|pair| u8::from_str_radix(&pair.iter().collect::<String>(), 16).unwrap()).collect()
+}
+
+// RFC 8439, section 2.8.2.
+#[test]
+fn chacha20_poly1305_matches_the_rfc_8439_test_vector() {
+    let key = hex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+    let nonce = hex("070000004041424344454647");
+    let aad = hex("50515253c0c1c2c3c4c5c6c7");
+    let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, \
+                      sunscreen would be it.";
+    let sealed = ChaCha20Poly1305.encrypt(&key, &nonce, plaintext, &aad).unwrap();
+    assert_eq!(sealed[..16], hex("d31a8d34648e60db7b86afbc53ef7ec2"));
+    assert_eq!(sealed[sealed.len() - 16..], hex("1ae10b594f09e26a7e902ecbd0600691"));
+    assert_eq!(ChaCha20Poly1305.decrypt(&key, &nonce, &sealed, &aad).unwrap(), plaintext);
+}
+
+#[test]
+fn changing_anything_makes_decrypting_fail() {
+    let (key, nonce) = ([1; 32], [2; 12]);
+    let sealed = ChaCha20Poly1305.encrypt(&key, &nonce, b"attack at dawn", b"aad").unwrap();
+    for i in 0..sealed.len() {
+        let mut changed = sealed.clone();
+        changed[i] ^= 1;
+        assert!(ChaCha20Poly1305.decrypt(&key, &nonce, &changed, b"aad").is_err(), "byte {i}");
+    }
+    assert!(ChaCha20Poly1305.decrypt(&key, &[3; 12], &sealed, b"aad").is_err());
+    assert!(ChaCha20Poly1305.decrypt(&key, &nonce, &sealed, b"").is_err());
+}
+
+#[test]
+fn keys_and_nonces_of_the_wrong_length_are_refused() {
+    assert!(matches!(ChaCha20Poly1305.encrypt(&[1; 16], &[2; 12], b"", b""), Err(CipherError::InvalidKey(_))));
+    assert!(matches!(ChaCha20Poly1305.encrypt(&[1; 32], &[2; 8], b"", b""), Err(CipherError::InvalidInput(_))));
+    assert!(matches!(Rot13.encrypt(b"key", &[], b"", b""), Err(CipherError::InvalidKey(_))));
+    assert!(ChaCha20Poly1305.process(b"text", Direction::Encrypt).is_err());
+}
+
+#[test]
+fn classical_ciphers_take_an_empty_key_and_nonce() {
+    let rail_fence = RailFence { rails: 3 };
+    assert_eq!((rail_fence.key_len(), rail_fence.nonce_len()), (0, 0));
+    let encrypted = rail_fence.encrypt(&[], &[], b"WEAREDISCOVERED", b"").unwrap();
+    assert_eq!(encrypted, rail_fence.process(b"WEAREDISCOVERED", Direction::Encrypt).unwrap());
+    assert_eq!(rail_fence.decrypt(&[], &[], &encrypted, b"").unwrap(), b"WEAREDISCOVERED");
+}
+
+// A cipher like another crate would write: XOR with a one-byte key.
+struct Xor;
+
+impl Cipher for Xor {
+    fn name(&self) -> &'static str {
+        "xor"
+    }
+
+    fn process(&self, _data: &[u8], _direction: Direction) -> Result<Vec<u8>, CipherError> {
+        Err(CipherError::InvalidKey(String::from("xor needs a key")))
+    }
+
+    fn key_len(&self) -> usize {
+        1
+    }
+
+    fn encrypt(&self, key: &[u8], _nonce: &[u8], plaintext: &[u8], _aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        Ok(plaintext.iter().map(|byte| byte ^ key[0]).collect())
+    }
+
+    fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CipherError> {
+        self.encrypt(key, nonce, ciphertext, aad)
+    }
+}
+
+#[test]
+fn ciphers_from_other_crates_can_be_registered_globally() {
+    assert!(CipherRegistry::global().get("rot13").is_some());
+    assert!(CipherRegistry::register_global(Box::new(Xor)).is_none());
+    let registry = CipherRegistry::global();
+    let xor = registry.get("xor").unwrap();
+    let encrypted = xor.encrypt(&[0x20], &[], b"abc", &[]).unwrap();
+    assert_eq!(encrypted, b"ABC");
+    assert_eq!(xor.decrypt(&[0x20], &[], &encrypted, &[]).unwrap(), b"abc");
+}
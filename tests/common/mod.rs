@@ -0,0 +1,29 @@
+// Helpers shared by the tests that run the built app.
+
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+// This function makes an empty directory for one test to work in, so tests running at the same time don't
+// trip over each other's files. It's deleted when the test is done with it.
+pub fn scratch_dir() -> TempDir {
+    tempfile::Builder::new().prefix("rust_project_test_").tempdir().unwrap()
+}
+
+// This function runs the app with some arguments in a directory, giving back everything it printed and the
+// code it exited with.
+pub fn run_app(dir: &Path, args: &[&str]) -> Output {
+    app(dir).args(args).output().unwrap()
+}
+
+// This function sets up a command to run the app in a directory. The directory also stands in for the user's
+// config directory, and the environment variables that set options are cleared, so neither a config file nor
+// the environment of the computer running the tests can change what they do.
+pub fn app(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust_project"));
+    command.current_dir(dir).env("XDG_CONFIG_HOME", dir);
+    for name in ["RUST_PROJECT_CIPHER", "RUST_PROJECT_SUFFIX", "RUST_PROJECT_KEY", "RUST_PROJECT_VERBOSE"] {
+        command.env_remove(name);
+    }
+    command
+}
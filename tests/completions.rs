@@ -0,0 +1,44 @@
+// "completions SHELL" prints a tab completion script. This runs the built app for every shell and checks the
+// scripts mention the options and cipher names they should complete.
+
+mod common;
+
+use common::{run_app, scratch_dir};
+
+// This function prints the completion script for a shell.
+fn script(shell: &str) -> String {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &["completions", shell]);
+    assert!(output.status.success(), "{shell}: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn every_shell_completes_options_commands_and_ciphers() {
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let script = script(shell);
+        // fish writes long options without their dashes.
+        for option in ["decrypt", "cipher", "output-dir", "key-file", "no-config", "dry-run", "base32"] {
+            assert!(script.contains(option), "{shell} doesn't complete --{option}");
+        }
+        for word in ["verify", "gen-key", "rot13", "rail-fence", "substitution", "base64", "always"] {
+            assert!(script.contains(word), "{shell} doesn't complete {word}");
+        }
+    }
+}
+
+// bash is there to check the script's syntax on most Unix systems.
+#[cfg(unix)]
+#[test]
+fn bash_script_is_valid_bash() {
+    let output = std::process::Command::new("bash").args(["-n", "-c", &script("bash")]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn unknown_shells_are_a_usage_error() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &["completions", "tcsh"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bash, zsh, fish, powershell"));
+}
@@ -0,0 +1,69 @@
+// Default options can be kept in a config file. The command line wins over the config file, which wins over the
+// app's built-in defaults. This runs the built app with and without a config file and checks which one wins.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+// This function encrypts "Hello, World!" without a header, so the output shows which cipher was used.
+fn encrypt(dir: &Path, args: &[&str]) -> String {
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let _ = fs::remove_file(dir.join("notes.enc"));
+    let output = run_app(dir, &[args, &["--no-header", "notes.txt", "notes.enc"]].concat());
+    assert!(output.status.success(), "{args:?} failed: {}", String::from_utf8_lossy(&output.stderr));
+    fs::read_to_string(dir.join("notes.enc")).unwrap()
+}
+
+#[test]
+fn config_file_overrides_built_in_defaults_and_cli_overrides_config_file() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    assert_eq!(encrypt(dir, &[]), "Uryyb, Jbeyq!");
+
+    fs::write(dir.join("custom.toml"), "cipher = \"base32\"\n").unwrap();
+    assert_eq!(encrypt(dir, &["--config", "custom.toml"]), "JBSWY3DPFQQFO33SNRSCC===");
+    assert_eq!(encrypt(dir, &["--config", "custom.toml", "--cipher", "rot13"]), "Uryyb, Jbeyq!");
+}
+
+// The tests run with the scratch directory as the user's config directory.
+#[test]
+fn usual_config_file_is_read_unless_no_config_is_given() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::create_dir(dir.join("rust_project")).unwrap();
+    fs::write(dir.join("rust_project/config.toml"), "cipher = \"base32\"\n").unwrap();
+    assert_eq!(encrypt(dir, &[]), "JBSWY3DPFQQFO33SNRSCC===");
+    assert_eq!(encrypt(dir, &["--no-config"]), "Uryyb, Jbeyq!");
+}
+
+#[test]
+fn quiet_in_config_file_gives_way_to_verbose_on_cli() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("quiet.toml"), "quiet = true\n").unwrap();
+    let output = run_app(dir, &["--config", "quiet.toml", "notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = run_app(dir, &["--config", "quiet.toml", "-v", "--force", "notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Size of notes"));
+}
+
+#[test]
+fn mistakes_in_config_file_name_the_file_line_and_key() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("bad.toml"), "quiet = true\nbogus = 1\n").unwrap();
+    let output = run_app(dir, &["--config", "bad.toml", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("bad.toml") && stderr.contains("line 2") && stderr.contains("bogus"), "{stderr}");
+
+    let output = run_app(dir, &["--config", "missing.toml", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,70 @@
+// "crack" tries every Caesar shift on text encrypted with an unknown one. This runs the built app on a file
+// encrypted with a known shift and checks the right shift comes out on top.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use rust_project::rotate_by;
+use serde_json::Value;
+
+const PLAIN: &str = "It was the best of times, it was the worst of times, it was the age of wisdom, it was the age of \
+                     foolishness, it was the epoch of belief, it was the epoch of incredulity.\n";
+const SHIFT: i32 = 11;
+
+#[test]
+fn known_shift_ranks_first() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("mystery.txt"), rotate_by(PLAIN, SHIFT)).unwrap();
+    let output = run_app(dir, &["crack", "--json", "--top", "3", "mystery.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let candidates: Vec<Value> =
+        String::from_utf8_lossy(&output.stdout).lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(candidates.len(), 3);
+    assert_eq!(candidates[0]["shift"], SHIFT);
+    assert_eq!(candidates[0]["text"], PLAIN);
+    assert!(candidates[0]["score"].as_f64() < candidates[1]["score"].as_f64());
+}
+
+// Without --top, every shift is printed in order, each under its own label.
+#[test]
+fn every_shift_is_printed_with_its_label() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("mystery.txt"), rotate_by(PLAIN, SHIFT)).unwrap();
+    let output = run_app(dir, &["crack", "mystery.txt"]);
+    assert!(output.status.success());
+    let printed = String::from_utf8_lossy(&output.stdout);
+    let labels: Vec<&str> = printed.lines().filter(|line| line.starts_with("Shift ")).collect();
+    assert_eq!(labels.len(), 25);
+    assert!(labels[0].starts_with("Shift 1 "));
+    assert!(labels[24].starts_with("Shift 25 "));
+    assert!(printed.contains(&format!("Shift {SHIFT} ")));
+    assert!(printed.contains(PLAIN));
+}
+
+#[test]
+fn candidates_are_saved_to_numbered_files() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("mystery.txt"), rotate_by(PLAIN, SHIFT)).unwrap();
+    let output = run_app(dir, &["crack", "--output-dir", "candidates", "mystery.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_dir(dir.join("candidates")).unwrap().count(), 25);
+    assert_eq!(fs::read_to_string(dir.join("candidates/shift-11-mystery.txt")).unwrap(), PLAIN);
+
+    // Running it again doesn't overwrite them unless asked to.
+    let output = run_app(dir, &["crack", "--output-dir", "candidates", "mystery.txt"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn top_only_works_with_crack() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert_eq!(run_app(dir, &["--top", "3", "notes.txt", "notes.enc"]).status.code(), Some(2));
+    assert_eq!(run_app(dir, &["crack", "--top", "0", "notes.txt"]).status.code(), Some(2));
+    assert_eq!(run_app(dir, &["crack"]).status.code(), Some(2));
+}
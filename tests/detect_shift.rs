@@ -0,0 +1,72 @@
+// --detect-shift decrypts text encrypted with an unknown Caesar shift, picking the shift that makes it look most
+// like English. This runs the built app on English text encrypted with several shifts, and on text too short
+// to go by.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use rust_project::rotate_by;
+use serde_json::Value;
+
+const PLAIN: &str = "It was a bright cold day in April, and the clocks were striking thirteen. Winston Smith, his chin \
+                     nuzzled into his breast in an effort to escape the vile wind, slipped quickly through the glass \
+                     doors of Victory Mansions.\n";
+
+#[test]
+fn english_text_is_decrypted_at_any_shift() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    for shift in [1, 3, 7, 13, 20, 25] {
+        fs::write(dir.join("mystery.txt"), rotate_by(PLAIN, shift)).unwrap();
+        let output = run_app(dir, &["--detect-shift", "--json", "--force", "mystery.txt", "plain.txt"]);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["detected_shift"], shift);
+        assert_eq!(report["cipher"], "caesar");
+        assert!(report["warning"].is_null());
+        assert_eq!(fs::read_to_string(dir.join("plain.txt")).unwrap(), PLAIN);
+    }
+}
+
+#[test]
+fn detected_shift_is_shown_in_the_summary() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("mystery.txt"), rotate_by(PLAIN, 7)).unwrap();
+    let output = run_app(dir, &["--detect-shift", "mystery.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Detected shift: 7"));
+    assert!(output.stderr.is_empty());
+    assert_eq!(fs::read_to_string(dir.join("mystery.txt.dec")).unwrap(), PLAIN);
+}
+
+// With only a few letters, the most English-looking shift is often the wrong one, so it's warned about.
+#[test]
+fn short_input_warns_and_suggests_crack() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("short.txt"), rotate_by("Zzz", 4)).unwrap();
+    let output = run_app(dir, &["--detect-shift", "short.txt", "short.dec"]);
+    assert!(output.status.success());
+    let warning = String::from_utf8_lossy(&output.stderr);
+    assert!(warning.contains("Warning:"));
+    assert!(warning.contains("crack short.txt"));
+
+    // The threshold can be lowered for text that's known to be enough.
+    fs::write(dir.join("mystery.txt"), rotate_by(PLAIN, 7)).unwrap();
+    let output = run_app(dir, &["--detect-shift", "--detect-min-size", "1KB", "mystery.txt", "mystery.dec"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning:"));
+    let output = run_app(dir, &["--detect-shift", "--detect-min-size", "3", "short.txt", "other.dec"]);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn detect_shift_only_decrypts() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--detect-shift", "--encrypt", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!dir.join("notes.enc").exists());
+}
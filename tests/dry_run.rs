@@ -0,0 +1,64 @@
+// "--dry-run" checks every file and shows where it would be saved, without changing anything. This runs the
+// built app with it and checks nothing in the directory was created or touched.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+use common::{run_app, scratch_dir};
+
+// This function lists every file and directory under a directory, along with when each was last modified.
+fn snapshot(dir: &Path) -> Vec<(String, SystemTime)> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let entry = entry.unwrap();
+        entries.push((entry.path().to_string_lossy().into_owned(), entry.metadata().unwrap().modified().unwrap()));
+        if entry.file_type().unwrap().is_dir() {
+            entries.extend(snapshot(&entry.path()));
+        }
+    }
+    entries.sort();
+    entries
+}
+
+#[test]
+fn dry_run_shows_the_plan_and_changes_nothing() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::create_dir_all(dir.join("docs/sub")).unwrap();
+    fs::write(dir.join("docs/a.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("docs/sub/b.txt"), "Goodbye!").unwrap();
+    let before = snapshot(dir);
+
+    let output = run_app(dir, &["--dry-run", "--recursive", "--output-dir", "encrypted", "docs"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would create directory encrypted"));
+    assert!(stdout.contains(&format!("Would encrypt {} -> ", Path::new("docs").join("a.txt").display())));
+    assert!(stdout.contains("b.txt.enc"));
+    assert_eq!(snapshot(dir), before);
+}
+
+#[test]
+fn dry_run_fails_for_files_that_would_fail() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("notes.enc"), "already here").unwrap();
+    let before = snapshot(dir);
+
+    // The output is already there, and --force wasn't given.
+    let output = run_app(dir, &["--dry-run", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+
+    // The input isn't there.
+    let output = run_app(dir, &["--dry-run", "missing.txt", "missing.enc"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    // With --force it would work, but the output still isn't touched.
+    assert!(run_app(dir, &["--dry-run", "--force", "notes.txt", "notes.enc"]).status.success());
+    assert_eq!(snapshot(dir), before);
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "already here");
+}
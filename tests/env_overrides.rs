@@ -0,0 +1,88 @@
+// A few options can be set with environment variables. They take priority over the config file, and the
+// command line takes priority over them. This runs the built app with each combination and checks which wins.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{app, run_app, scratch_dir};
+
+// This function encrypts "Hello, World!" into a file next to it without a header, with some environment
+// variables set, giving back what was saved to the file named `output`.
+fn encrypt(dir: &Path, vars: &[(&str, &str)], args: &[&str], output: &str) -> String {
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let result = app(dir)
+        .args([&["--force", "--no-header"], args, &["notes.txt"]].concat())
+        .envs(vars.iter().copied())
+        .output()
+        .unwrap();
+    assert!(result.status.success(), "{vars:?} {args:?} failed: {}", String::from_utf8_lossy(&result.stderr));
+    let contents = fs::read_to_string(dir.join(output)).unwrap();
+    fs::remove_file(dir.join(output)).unwrap();
+    contents
+}
+
+#[test]
+fn cipher_from_environment_sits_between_config_file_and_command_line() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("affine.toml"), "cipher = \"affine\"\nsuffix = \"enc\"\n").unwrap();
+    let base32 = [("RUST_PROJECT_CIPHER", "base32")];
+    let config = ["--config", "affine.toml"];
+
+    // The environment beats the built-in default and the config file.
+    assert_eq!(encrypt(dir, &base32, &["--suffix", "enc"], "notes.txt.enc"), "JBSWY3DPFQQFO33SNRSCC===");
+    assert_eq!(encrypt(dir, &base32, &config, "notes.txt.enc"), "JBSWY3DPFQQFO33SNRSCC===");
+    // The command line beats the environment.
+    let args = [&config[..], &["--cipher", "rot13"]].concat();
+    assert_eq!(encrypt(dir, &base32, &args, "notes.txt.enc"), "Uryyb, Jbeyq!");
+}
+
+#[test]
+fn suffix_and_key_come_from_environment() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    let vars = [("RUST_PROJECT_SUFFIX", "secret"), ("RUST_PROJECT_KEY", "ZEBRAS")];
+    let from_env = encrypt(dir, &vars, &["--columnar"], "notes.txt.secret");
+    let from_cli = encrypt(dir, &[], &["--columnar", "--key", "ZEBRAS", "--suffix", "secret"], "notes.txt.secret");
+    assert_eq!(from_env, from_cli);
+
+    // The command line beats the environment.
+    let other_key = encrypt(dir, &vars, &["--columnar", "--key", "GIRAFFE", "--suffix", "other"], "notes.txt.other");
+    assert_ne!(other_key, from_env);
+}
+
+#[test]
+fn verbose_from_environment_replaces_quiet_from_config_file_but_not_command_line() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("quiet.toml"), "quiet = true\n").unwrap();
+    let run = |args: &[&str]| {
+        let output = app(dir)
+            .args([&["--force", "--config", "quiet.toml"], args, &["notes.txt", "notes.enc"]].concat())
+            .env("RUST_PROJECT_VERBOSE", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        output
+    };
+    assert!(String::from_utf8_lossy(&run(&[]).stderr).contains("Size of notes"));
+    let quiet = run(&["--quiet"]);
+    assert!(quiet.stdout.is_empty() && quiet.stderr.is_empty());
+}
+
+#[test]
+fn invalid_values_name_the_variable() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    // Without the variables set, the same command works.
+    assert!(run_app(dir, &["--dry-run", "notes.txt", "notes.enc"]).status.success());
+    let vars = [("RUST_PROJECT_CIPHER", "enigma"), ("RUST_PROJECT_VERBOSE", "loud"), ("RUST_PROJECT_SUFFIX", ".")];
+    for (name, value) in vars {
+        let output = app(dir).args(["notes.txt", "notes.enc"]).env(name, value).output().unwrap();
+        assert_eq!(output.status.code(), Some(2), "{name}={value}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains(name), "{name}={value}");
+    }
+}
@@ -0,0 +1,61 @@
+// The app exits with a different code for each kind of problem, so scripts can tell them apart. This runs the
+// built app into each kind of problem and checks the code it exits with.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+// This function runs the app with some arguments in a directory, giving back the code it exited with.
+fn exit_code(dir: &Path, args: &[&str]) -> i32 {
+    run_app(dir, args).status.code().unwrap()
+}
+
+#[test]
+fn success_exits_with_0() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert_eq!(exit_code(dir, &["notes.txt", "notes.enc"]), 0);
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert_eq!(exit_code(dir, &[]), 2);
+    assert_eq!(exit_code(dir, &["--no-such-option", "notes.txt", "notes.enc"]), 2);
+    assert_eq!(exit_code(dir, &["-q", "-v", "notes.txt", "notes.enc"]), 2);
+}
+
+#[test]
+fn unreadable_input_exits_with_3() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    let output = run_app(dir, &["missing.txt", "missing.enc"]);
+    assert_eq!(output.status.code(), Some(3));
+    // The message says which file couldn't be read.
+    assert!(String::from_utf8_lossy(&output.stderr).contains("could not read missing.txt: "));
+}
+
+#[test]
+fn unwritable_output_exits_with_4() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["notes.txt", "no-such-dir/notes.enc"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("could not write no-such-dir/notes.enc: "));
+    fs::write(dir.join("notes.enc"), "already here").unwrap();
+    assert_eq!(exit_code(dir, &["notes.txt", "notes.enc"]), 4);
+}
+
+#[test]
+fn decryption_failures_exit_with_5() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("binary.enc"), [0xff, 0xfe, 0x00, 0x80]).unwrap();
+    assert_eq!(exit_code(dir, &["--decrypt", "binary.enc", "binary.txt"]), 5);
+}
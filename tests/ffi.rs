@@ -0,0 +1,80 @@
+// The C interface, called from Rust the way a C program would call it. Run these with --features ffi.
+
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use std::ffi::CString;
+use std::fs;
+use std::path::Path;
+use std::ptr;
+use common::{run_app, scratch_dir};
+use rust_project::ffi::*;
+use rust_project::FileHeader;
+
+// This function turns a path into the NUL-terminated string C would pass.
+fn c_path(path: &Path) -> CString {
+    CString::new(path.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn encrypted_files_decrypt_with_the_library_and_the_app() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let (plain, encrypted, decrypted) =
+        (c_path(&dir.join("notes.txt")), c_path(&dir.join("notes.enc")), c_path(&dir.join("notes.dec")));
+    let key = CString::new("ZEBRAS").unwrap();
+
+    let result = unsafe { rfec_encrypt(plain.as_ptr(), encrypted.as_ptr(), key.as_ptr(), RFEC_CIPHER_COLUMNAR) };
+    assert_eq!(result, RFEC_OK);
+    // The header names the cipher, so the ID given when decrypting doesn't matter.
+    let result = unsafe { rfec_decrypt(encrypted.as_ptr(), decrypted.as_ptr(), key.as_ptr(), RFEC_CIPHER_ROT13) };
+    assert_eq!(result, RFEC_OK);
+    assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Hello, World!");
+
+    let output = run_app(dir, &["--decrypt", "--key", "ZEBRAS", "notes.enc", "app.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("app.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn bad_arguments_give_negative_codes() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let (plain, encrypted) = (c_path(&dir.join("notes.txt")), c_path(&dir.join("notes.enc")));
+    let missing = c_path(&dir.join("missing.txt"));
+    let bad_key = CString::new("not a number").unwrap();
+    let ptr = |text: Option<&CString>| text.map_or(ptr::null(), |text| text.as_ptr());
+    let encrypt = |input, output, key, cipher_id| unsafe { rfec_encrypt(ptr(input), ptr(output), ptr(key), cipher_id) };
+
+    assert_eq!(encrypt(None, Some(&encrypted), None, RFEC_CIPHER_ROT13), RFEC_ERR_NULL_POINTER);
+    assert_eq!(encrypt(Some(&plain), None, None, RFEC_CIPHER_ROT13), RFEC_ERR_NULL_POINTER);
+    // The columnar cipher needs a key.
+    assert_eq!(encrypt(Some(&plain), Some(&encrypted), None, RFEC_CIPHER_COLUMNAR), RFEC_ERR_NULL_POINTER);
+    assert_eq!(encrypt(Some(&plain), Some(&encrypted), None, 200), RFEC_ERR_UNKNOWN_CIPHER);
+    assert_eq!(encrypt(Some(&plain), Some(&encrypted), Some(&bad_key), RFEC_CIPHER_RAIL_FENCE), RFEC_ERR_INVALID_KEY);
+    assert_eq!(encrypt(Some(&missing), Some(&encrypted), None, RFEC_CIPHER_ROT13), RFEC_ERR_READ);
+    assert!(!dir.join("notes.enc").exists());
+}
+
+#[test]
+fn files_with_a_mac_or_other_flags_are_refused() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let mac = ["--mac", "hmac-sha256", "--key", "hunter2", "--scrypt-n", "1024", "notes.txt", "mac.enc"];
+    assert!(run_app(dir, &mac).status.success());
+    let mut compressed = FileHeader::new(RFEC_CIPHER_ROT13).with_flags(true, false).to_bytes();
+    compressed.extend_from_slice(b"Uryyb");
+    fs::write(dir.join("compressed.enc"), compressed).unwrap();
+
+    let (decrypted, key) = (c_path(&dir.join("notes.dec")), CString::new("hunter2").unwrap());
+    for name in ["mac.enc", "compressed.enc"] {
+        let encrypted = c_path(&dir.join(name));
+        let result = unsafe { rfec_decrypt(encrypted.as_ptr(), decrypted.as_ptr(), key.as_ptr(), RFEC_CIPHER_ROT13) };
+        assert_eq!(result, RFEC_ERR_CIPHER, "{name}");
+        assert!(!dir.join("notes.dec").exists());
+    }
+}
@@ -0,0 +1,68 @@
+// The file header comes in two versions: the 6-byte version 1 that only names the cipher, and version 2, which
+// also carries what a password-based cipher needs. This checks both are read back exactly as they were written,
+// that a header cut short anywhere is an error, and that the app refuses headers it can't act on.
+
+mod common;
+
+use std::fs;
+use std::io::Read;
+use common::{run_app, scratch_dir};
+use rust_project::{CipherError, FileHeader};
+
+fn version_2() -> FileHeader {
+    FileHeader::new(12).with_kdf(1, vec![0, 1, 0, 0, 0, 0, 0, 3, 0, 0, 0, 4], vec![0xaa; 16]).with_nonce(vec![0xbb; 12])
+}
+
+#[test]
+fn plain_headers_are_still_written_as_version_1() {
+    assert_eq!(FileHeader::new(4).to_bytes(), b"FENC\x01\x04");
+    assert_eq!(FileHeader::new(4).len(), FileHeader::LEN);
+}
+
+#[test]
+fn version_2_headers_are_read_back_from_a_stream() {
+    let mut data = version_2().to_bytes();
+    data.extend_from_slice(b"ciphertext");
+    let mut stream = data.as_slice();
+    assert_eq!(FileHeader::read_from(&mut stream).unwrap(), version_2());
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"ciphertext");
+}
+
+#[test]
+fn flags_are_read_back() {
+    for (compressed, armored) in [(false, false), (true, false), (false, true), (true, true)] {
+        let header = FileHeader::new(1).with_flags(compressed, armored);
+        let (read, _) = FileHeader::parse(&header.to_bytes()).unwrap().unwrap();
+        assert_eq!((read.compressed, read.armored), (compressed, armored));
+    }
+}
+
+#[test]
+fn a_header_cut_short_anywhere_is_an_error() {
+    let bytes = version_2().to_bytes();
+    for len in FileHeader::LEN..bytes.len() {
+        assert!(FileHeader::parse(&bytes[..len]).is_err(), "cut to {len} bytes");
+        assert!(FileHeader::read_from(&mut &bytes[..len]).is_err(), "cut to {len} bytes");
+    }
+}
+
+#[test]
+fn unknown_flags_are_refused() {
+    let mut bytes = version_2().to_bytes();
+    bytes[6] = 0x80;
+    assert!(matches!(FileHeader::parse(&bytes), Err(CipherError::InvalidInput(_))));
+}
+
+#[test]
+fn compressed_or_armored_files_are_refused() {
+    let temp = scratch_dir();
+    let mut data = FileHeader::new(1).with_flags(true, false).to_bytes();
+    data.extend_from_slice(b"Uryyb");
+    fs::write(temp.path().join("notes.enc"), data).unwrap();
+    let output = run_app(temp.path(), &["--decrypt", "notes.enc", "notes.txt"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("compressed or armored"));
+    assert!(!temp.path().join("notes.txt").exists());
+}
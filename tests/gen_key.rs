@@ -0,0 +1,56 @@
+// "gen-key" prints (or saves) a random key. This runs the built app to make keys and checks they're the size
+// asked for, different every time, and usable with --key-file.
+
+mod common;
+
+use std::fs;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::{run_app, scratch_dir};
+
+// This function runs "gen-key" with some options, giving back the key it printed.
+fn gen_key(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = run_app(dir, &[&["gen-key"], args].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().trim_end().to_string()
+}
+
+#[test]
+fn keys_have_the_requested_length_and_differ_every_time() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    let key = gen_key(dir, &[]);
+    assert_eq!(STANDARD.decode(&key).unwrap().len(), 32);
+    assert_ne!(gen_key(dir, &[]), key);
+
+    let key = gen_key(dir, &["--bytes", "16", "--format", "hex"]);
+    assert_eq!(key.len(), 32);
+    assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(gen_key(dir, &["--bytes", "16", "--format", "hex"]), key);
+}
+
+#[test]
+fn saved_key_works_with_key_file() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["gen-key", "--key-file", "key.txt"]);
+    assert!(output.status.success());
+    let key = fs::read_to_string(dir.join("key.txt")).unwrap();
+    assert_eq!(STANDARD.decode(key.trim_end()).unwrap().len(), 32);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(dir.join("key.txt")).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    // An existing key isn't replaced without --force.
+    assert_eq!(run_app(dir, &["gen-key", "--key-file", "key.txt"]).status.code(), Some(4));
+    assert_eq!(fs::read_to_string(dir.join("key.txt")).unwrap(), key);
+
+    let output = run_app(dir, &["--columnar", "--key-file", "key.txt", "notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    let output = run_app(dir, &["--decrypt", "--key", key.trim_end(), "notes.enc", "notes.dec"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Hello, World!");
+}
@@ -0,0 +1,111 @@
+// Input paths with "*" or "?" in them are expanded by the app itself, since not every shell does it. This runs
+// the built app on patterns and checks which files they pick, in what order, and where those files are saved.
+
+mod common;
+
+use std::fs;
+use std::time::{Duration, Instant};
+use common::{run_app, scratch_dir};
+
+// Each star in a pattern like this could match in many places. Trying every combination of them would take
+// far longer than the test's limit.
+#[test]
+fn many_stars_match_quickly() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join(format!("{}.txt", "a".repeat(60))), "Hello, World!").unwrap();
+    let start = Instant::now();
+    let output = run_app(dir, &["--dry-run", "*a*a*a*a*a*a*a*a*a*a*a*a*b", "out"]);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pattern matched no files"));
+    let output = run_app(dir, &["--dry-run", "*a*a*a*a*a*a*a*a*a*a*a*a*.t?t", "out.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+// "*.txt out/" saves every match into out, which is made if it isn't there yet, however many files matched.
+#[test]
+fn matches_are_saved_into_a_new_output_directory() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("a.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--quiet", "--no-header", "*.txt", "one/"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("one/a.txt")).unwrap(), "Uryyb, Jbeyq!");
+
+    fs::write(dir.join("b.txt"), "Goodbye!").unwrap();
+    let output = run_app(dir, &["--quiet", "--no-header", "*.txt", "two"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("two/a.txt")).unwrap(), "Uryyb, Jbeyq!");
+    assert_eq!(fs::read_to_string(dir.join("two/b.txt")).unwrap(), "Tbbqolr!");
+}
+
+#[test]
+fn an_output_directory_that_is_a_file_is_refused() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("a.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("b.txt"), "Goodbye!").unwrap();
+    fs::write(dir.join("out"), "a file").unwrap();
+    let output = run_app(dir, &["*.txt", "out"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("exists but is not a directory"));
+    assert_eq!(fs::read_to_string(dir.join("out")).unwrap(), "a file");
+}
+
+// The matches are sorted by name, whatever order the directory lists them in, so the plan is always the same.
+#[test]
+fn matches_are_sorted() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    for name in ["c.txt", "a.txt", "b2.txt", "b10.txt", "notes.md"] {
+        fs::write(dir.join(name), "Hello, World!").unwrap();
+    }
+    let output = run_app(dir, &["--dry-run", "*.txt", "out"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let inputs: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Would encrypt "))
+        .map(|line| line.split(" -> ").next().unwrap())
+        .collect();
+    assert_eq!(inputs, ["a.txt", "b10.txt", "b2.txt", "c.txt"]);
+    assert_eq!(run_app(dir, &["--dry-run", "*.txt", "out"]).stdout, output.stdout);
+}
+
+#[test]
+fn a_pattern_matching_nothing_is_an_error() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.md"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["*.txt", "out"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pattern matched no files: *.txt"));
+    assert!(!dir.join("out").exists());
+}
+
+#[test]
+fn no_glob_reads_a_file_with_a_star_in_its_name() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("*.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("other.txt"), "Goodbye!").unwrap();
+    let output = run_app(dir, &["--quiet", "--no-glob", "--no-header", "*.txt", "star.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("star.enc")).unwrap(), "Uryyb, Jbeyq!");
+}
+
+// Like most shells, a star doesn't match a name starting with a dot, unless the pattern starts with one too.
+#[test]
+fn hidden_files_are_skipped() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join(".secret.txt"), "Goodbye!").unwrap();
+    assert!(run_app(dir, &["--quiet", "*.txt", "out/"]).status.success());
+    assert!(dir.join("out/notes.txt").exists());
+    assert!(!dir.join("out/.secret.txt").exists());
+    assert!(run_app(dir, &["--quiet", ".*.txt", "hidden/"]).status.success());
+    assert!(dir.join("hidden/.secret.txt").exists());
+    assert!(!dir.join("hidden/notes.txt").exists());
+}
@@ -0,0 +1,42 @@
+// "--help" prints the usage, every option, the ciphers, and some examples. This compares it with the copy in
+// tests/snapshots/help.txt, so any change to the help text shows up in review, and checks "-h" and running with
+// no arguments print the same thing. After changing the help on purpose, update the copy with:
+//   cargo run -- --help > tests/snapshots/help.txt
+
+mod common;
+
+use common::{run_app, scratch_dir};
+
+const SNAPSHOT: &str = include_str!("snapshots/help.txt");
+
+#[test]
+fn help_matches_the_snapshot() {
+    let temp = scratch_dir();
+    for flag in ["--help", "-h"] {
+        let output = run_app(temp.path(), &[flag]);
+        assert!(output.status.success(), "{flag}");
+        assert!(output.stderr.is_empty(), "{flag}");
+        let help = String::from_utf8(output.stdout).unwrap();
+        assert!(help == SNAPSHOT, "{flag} doesn't match tests/snapshots/help.txt; if that's on purpose, update it");
+    }
+}
+
+#[test]
+fn no_arguments_prints_the_help_as_an_error() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), SNAPSHOT);
+}
+
+#[test]
+fn every_cipher_is_in_the_help() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &["--list-ciphers"]);
+    let ciphers = String::from_utf8(output.stdout).unwrap();
+    let listed = SNAPSHOT.split("\nCiphers").nth(1).unwrap();
+    for name in ciphers.lines().filter_map(|line| line.split_whitespace().next()) {
+        assert!(listed.contains(&format!("\n  {name} ")), "{name}");
+    }
+}
@@ -0,0 +1,32 @@
+// Saving over the file being read has to be asked for with "--in-place". This runs the built app with the same
+// file as its input and output, spelled a few different ways.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+#[test]
+fn same_input_and_output_is_refused() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::create_dir(dir.join("x")).unwrap();
+    for (input, output) in [("notes.txt", "notes.txt"), ("notes.txt", "./notes.txt"), ("x/../notes.txt", "notes.txt")] {
+        let result = run_app(dir, &["--force", input, output]);
+        assert_eq!(result.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&result.stderr).contains("input and output are the same file"));
+        assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+    }
+}
+
+#[test]
+fn in_place_saves_each_file_over_itself() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("a.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("b.txt"), "Goodbye!").unwrap();
+    assert!(run_app(dir, &["--quiet", "--in-place", "--no-header", "a.txt", "b.txt"]).status.success());
+    assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "Uryyb, Jbeyq!");
+    assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "Tbbqolr!");
+}
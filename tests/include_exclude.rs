@@ -0,0 +1,81 @@
+// With --recursive, "--exclude PATTERN" skips files found in input directories, "--include PATTERN" keeps only
+// the ones matching, and "--exclude-hidden" skips dotfiles. Patterns are matched against the path inside the
+// input directory, and excluding wins over including. This encrypts a small project tree with different patterns
+// and checks which files end up in the output directory.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+const FILES: [&str; 7] =
+    ["src/foo.rs", "src/lib.rs", "target/foo.rs", "target/debug/build.rs", "notes.txt", "build.log", ".env"];
+
+// This function writes the project tree, encrypts it into out/ with some patterns, and gives back the files in
+// out/ (without the .enc), sorted.
+fn encrypt_with(dir: &Path, patterns: &[&str]) -> Vec<String> {
+    for file in FILES {
+        let path = dir.join("project").join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, format!("This is {file}.\n")).unwrap();
+    }
+    let _ = fs::remove_dir_all(dir.join("out"));
+    let output = run_app(dir, &[&["--recursive", "--output-dir", "out"], patterns, &["project"]].concat());
+    assert!(output.status.success(), "{patterns:?}: {}", String::from_utf8_lossy(&output.stderr));
+
+    let mut found = Vec::new();
+    let mut dirs = vec![dir.join("out")];
+    while let Some(next) = dirs.pop() {
+        for entry in fs::read_dir(next).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                dirs.push(entry.path());
+            } else {
+                let relative = entry.path().strip_prefix(dir.join("out")).unwrap().to_string_lossy().into_owned();
+                found.push(relative.replace('\\', "/").trim_end_matches(".enc").to_string());
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+#[test]
+fn exclude_skips_a_whole_directory() {
+    let temp = scratch_dir();
+    let found = encrypt_with(temp.path(), &["--exclude", "target/**"]);
+    assert_eq!(found, [".env", "build.log", "notes.txt", "src/foo.rs", "src/lib.rs"]);
+}
+
+#[test]
+fn exclude_can_be_given_more_than_once() {
+    let temp = scratch_dir();
+    let found = encrypt_with(temp.path(), &["--exclude", "target/**", "--exclude", "*.log"]);
+    assert_eq!(found, [".env", "notes.txt", "src/foo.rs", "src/lib.rs"]);
+}
+
+#[test]
+fn include_keeps_only_what_matches() {
+    let temp = scratch_dir();
+    let found = encrypt_with(temp.path(), &["--include", "*.rs"]);
+    assert_eq!(found, ["src/foo.rs", "src/lib.rs", "target/debug/build.rs", "target/foo.rs"]);
+    let found = encrypt_with(temp.path(), &["--include", "*.txt", "--include", "*.log"]);
+    assert_eq!(found, ["build.log", "notes.txt"]);
+}
+
+#[test]
+fn exclude_wins_over_include() {
+    let temp = scratch_dir();
+    let found = encrypt_with(temp.path(), &["--include", "*.rs", "--exclude", "target/**"]);
+    assert_eq!(found, ["src/foo.rs", "src/lib.rs"]);
+    let found = encrypt_with(temp.path(), &["--include", "*.rs", "--exclude", "src/foo.rs"]);
+    assert_eq!(found, ["src/lib.rs", "target/debug/build.rs", "target/foo.rs"]);
+}
+
+#[test]
+fn exclude_hidden_skips_dotfiles() {
+    let temp = scratch_dir();
+    let found = encrypt_with(temp.path(), &["--exclude-hidden", "--exclude", "target/**"]);
+    assert_eq!(found, ["build.log", "notes.txt", "src/foo.rs", "src/lib.rs"]);
+}
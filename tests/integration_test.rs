@@ -0,0 +1,95 @@
+// Encrypts real files with the built app and decrypts them again, for every cipher and a few file sizes,
+// checking the files come back exactly as they were.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+// Every cipher, along with the options it needs for a key, and any settings only given when encrypting (since
+// they're saved in the header). chacha20-poly1305 uses scrypt with a tiny N to keep deriving its key quick.
+const CIPHERS: [(&str, &[&str], &[&str]); 10] = [
+    ("rot13", &[], &[]),
+    ("rot13-5", &[], &[]),
+    ("rot13-unicode", &[], &[]),
+    ("caesar", &["--shift", "3"], &[]),
+    ("affine", &["--affine-a", "5", "--affine-b", "8"], &[]),
+    ("rail-fence", &["--rails", "3"], &[]),
+    ("columnar", &["--key", "ZEBRAS"], &[]),
+    ("substitution", &["--alphabet", "QWERTYUIOPASDFGHJKLZXCVBNM"], &[]),
+    ("base32", &[], &[]),
+    ("chacha20-poly1305", &["--key", "hunter2"], &["--scrypt-n", "16"]),
+];
+
+// The sizes of the files to try: empty, small, and big enough to be written in several pieces.
+const SIZES: [usize; 3] = [0, 100, 10 * 1024 * 1024];
+
+// This function makes some text of the given size, with letters of both cases, digits, punctuation, and
+// line breaks, so every kind of character a cipher treats differently shows up.
+fn plaintext(size: usize) -> Vec<u8> {
+    b"The quick brown fox jumps over the lazy dog, 1234567890 times!\nPACK MY BOX WITH FIVE DOZEN LIQUOR JUGS.\n"
+        .iter()
+        .copied()
+        .cycle()
+        .take(size)
+        .collect()
+}
+
+// This function runs the app on a file in a directory, panicking with what it printed if it fails.
+fn run_ok(dir: &std::path::Path, args: &[&str]) {
+    let output = run_app(dir, args);
+    assert!(output.status.success(), "{args:?} failed: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn every_cipher_round_trips_files_of_every_size() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    for size in SIZES {
+        let original = plaintext(size);
+        fs::write(dir.join("plain.txt"), &original).unwrap();
+        for (cipher, key, settings) in CIPHERS {
+            let encrypted_name = format!("{cipher}-{size}.enc");
+            let decrypted_name = format!("{cipher}-{size}.txt");
+
+            let mut args = vec!["--quiet", "--cipher", cipher];
+            args.extend(key);
+            args.extend(settings);
+            run_ok(dir, &[&args[..], &["plain.txt", &encrypted_name]].concat());
+            let encrypted = fs::read(dir.join(&encrypted_name)).unwrap();
+            assert_ne!(encrypted, original, "{cipher} left a {size} byte file as it was");
+
+            // The header says which cipher was used, so only the key has to be given to decrypt.
+            let mut args = vec!["--quiet", "--decrypt"];
+            args.extend(key);
+            run_ok(dir, &[&args[..], &[&encrypted_name, &decrypted_name]].concat());
+            let decrypted = fs::read(dir.join(&decrypted_name)).unwrap();
+            assert!(decrypted == original, "{cipher} didn't give back the {size} byte file it encrypted");
+        }
+    }
+}
+
+// The classical ciphers can't tell a wrong key from a right one, so decrypting with the wrong key "works", but it
+// mustn't give back the original file.
+#[test]
+fn decrypting_with_the_wrong_key_does_not_give_back_the_file() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("plain.txt"), plaintext(100)).unwrap();
+    run_ok(dir, &["--quiet", "--cipher", "columnar", "--key", "ZEBRAS", "plain.txt", "plain.enc"]);
+    run_ok(dir, &["--quiet", "--decrypt", "--key", "GIRAFFE", "plain.enc", "wrong.txt"]);
+    assert_ne!(fs::read(dir.join("wrong.txt")).unwrap(), plaintext(100));
+}
+
+// chacha20-poly1305 authenticates what it encrypted, so the wrong password is refused, and nothing is saved.
+#[test]
+fn decrypting_with_the_wrong_password_is_refused() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("plain.txt"), plaintext(100)).unwrap();
+    let encrypt = ["--quiet", "--cipher", "chacha20-poly1305", "--key", "hunter2", "--scrypt-n", "16"];
+    run_ok(dir, &[&encrypt[..], &["plain.txt", "plain.enc"]].concat());
+    let output = run_app(dir, &["--quiet", "--decrypt", "--key", "hunter3", "plain.enc", "wrong.txt"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(!dir.join("wrong.txt").exists());
+}
@@ -0,0 +1,53 @@
+// Pressing Ctrl-C while an output file is being written removes the partly written file, so it can't be
+// mistaken for a finished one. This starts the built app on a big file, interrupts it part of the way through
+// writing, and checks nothing is left behind. Signals are a Unix thing, so this only runs there.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use common::{app, run_app, scratch_dir};
+
+// This function lists the names of the files in a directory, in order.
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> =
+        fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    names
+}
+
+// The signal is sent once the temporary file the output is written through shows up, so it arrives while the
+// output is being written. The file is big enough for that to take a while.
+#[test]
+fn interrupted_write_leaves_no_output() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("big.txt"), "a".repeat(200_000_000)).unwrap();
+    let mut child = app(dir).args(["--quiet", "big.txt", "big.enc"]).spawn().unwrap();
+
+    let start = Instant::now();
+    while !file_names(dir).iter().any(|name| name.ends_with(".tmp")) {
+        assert!(start.elapsed() < Duration::from_secs(120), "the output was never written");
+        assert!(child.try_wait().unwrap().is_none(), "the app finished before it could be interrupted");
+        thread::sleep(Duration::from_millis(1));
+    }
+    let killed = std::process::Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    assert!(killed.success());
+
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+    assert_eq!(file_names(dir), ["big.txt"]);
+}
+
+// Without an interruption, the finished output is all that's left, the same as before.
+#[test]
+fn uninterrupted_write_keeps_the_output() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert_eq!(run_app(dir, &["notes.txt", "notes.enc"]).status.code(), Some(0));
+    assert_eq!(file_names(dir), ["notes.enc", "notes.txt"]);
+}
@@ -0,0 +1,88 @@
+// "--jobs N" processes up to N files at once, printing what happened to each of them in a stable order once
+// they're all done. This encrypts 50 generated files with --jobs 4 and checks every output, the order of the
+// output, and the summary counts, that a file that fails doesn't stop the others, and that the results don't
+// depend on how many threads there are.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+// This function writes 50 small files, each different, to in/.
+fn write_files(dir: &Path) -> Vec<String> {
+    fs::create_dir(dir.join("in")).unwrap();
+    (0..50)
+        .map(|i| {
+            let name = format!("file-{i:02}.txt");
+            fs::write(dir.join("in").join(&name), format!("File number {i}.\n").repeat(i + 1)).unwrap();
+            name
+        })
+        .collect()
+}
+
+#[test]
+fn fifty_files_with_four_jobs() {
+    let temp = scratch_dir();
+    let names = write_files(temp.path());
+    let output = run_app(temp.path(), &["--jobs", "4", "--output-dir", "out", "in/*.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("50 files processed, 0 skipped.\n"), "{stdout}");
+    // The files are listed in order, whichever order they finished in.
+    let listed: Vec<&str> = stdout.lines().filter(|line| line.ends_with("...success.")).collect();
+    assert_eq!(listed.len(), 50);
+    for (line, name) in listed.iter().zip(&names) {
+        assert!(line.starts_with(&format!("Encrypting/decrypting in/{name} to out/{name}.enc")), "{line}");
+    }
+
+    let output = run_app(temp.path(), &["--decrypt", "--jobs", "4", "--output-dir", "back", "out/*.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("50 files processed, 0 skipped.\n"));
+    for name in &names {
+        let encrypted = fs::read(temp.path().join("out").join(format!("{name}.enc"))).unwrap();
+        let original = fs::read(temp.path().join("in").join(name)).unwrap();
+        assert_ne!(encrypted, original, "{name}");
+        assert_eq!(fs::read(temp.path().join("back").join(name)).unwrap(), original, "{name}");
+    }
+}
+
+#[test]
+fn a_file_that_fails_doesnt_stop_the_others() {
+    let temp = scratch_dir();
+    let names = write_files(temp.path());
+    // These two outputs are already there, so they aren't overwritten.
+    fs::create_dir(temp.path().join("out")).unwrap();
+    for i in [7, 31] {
+        fs::write(temp.path().join("out").join(format!("{}.enc", names[i])), "keep me").unwrap();
+    }
+    let output = run_app(temp.path(), &["--jobs", "4", "--output-dir", "out", "in/*.txt"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("48 files processed, 2 skipped.\n"));
+    for (i, name) in names.iter().enumerate() {
+        let encrypted = fs::read(temp.path().join("out").join(format!("{name}.enc"))).unwrap();
+        assert_eq!(encrypted == b"keep me", i == 7 || i == 31, "{name}");
+    }
+}
+
+// rot13 has no random nonce, so one thread and four have to save exactly the same files, and print exactly the
+// same thing about them.
+#[test]
+fn one_thread_and_four_give_the_same_results() {
+    let runs: Vec<_> = ["1", "4"]
+        .into_iter()
+        .map(|threads| {
+            let temp = scratch_dir();
+            let names = write_files(temp.path());
+            let args = ["--cipher", "rot13", "--threads", threads, "--output-dir", "out", "in/*.txt"];
+            let output = run_app(temp.path(), &args);
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+            let out = temp.path().join("out");
+            let encrypted: Vec<Vec<u8>> =
+                names.iter().map(|name| fs::read(out.join(format!("{name}.enc"))).unwrap()).collect();
+            (output.stdout, encrypted)
+        })
+        .collect();
+    assert_eq!(String::from_utf8_lossy(&runs[0].0), String::from_utf8_lossy(&runs[1].0));
+    assert!(runs[0].1 == runs[1].1);
+}
@@ -0,0 +1,135 @@
+// "--output-format jwe" writes a JWE compact token (RFC 7516) instead of the binary format, encrypted with
+// AES-256-GCM using the key as it is ("dir" and "A256GCM"). There's no JWT library to check against here, so this
+// checks AES-256-GCM against the example in RFC 7516 itself, and decodes the app's tokens by hand the way any
+// other JWE library would: five base64url parts, the header as JSON, and the header text as AES-GCM's additional
+// data. It also checks a wrong key, a changed token, and options that can't go with a token are refused.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use common::{run_app, scratch_dir};
+use rust_project::jwe::{deserialize_jwe_compact, JweHeader};
+use rust_project::{Aes256Gcm, Cipher};
+
+const PLAIN: &[u8] = b"The true sign of intelligence is not knowledge but imagination.";
+
+// This function makes a key with gen-key, saves it to key.txt, and encrypts notes.txt to notes.jwe with it.
+fn encrypt(dir: &Path) -> String {
+    fs::write(dir.join("notes.txt"), PLAIN).unwrap();
+    let output = run_app(dir, &["gen-key", "--key-file", "key.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run_app(dir, &["--output-format", "jwe", "--key-file", "key.txt", "notes.txt", "notes.jwe"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read_to_string(dir.join("notes.jwe")).unwrap()
+}
+
+// This function decrypts notes.jwe to notes.out with some key arguments, giving back the exit code.
+fn decrypt(dir: &Path, key: &[&str]) -> Option<i32> {
+    let _ = fs::remove_file(dir.join("notes.out"));
+    let args = [&["--decrypt", "--output-format", "jwe"], key, &["notes.jwe", "notes.out"]].concat();
+    run_app(dir, &args).status.code()
+}
+
+// RFC 7516, appendix A.1: the content encryption key, IV, and protected header from the example, and the
+// ciphertext and tag it gives.
+#[test]
+fn aes_256_gcm_matches_rfc_7516() {
+    let key = [
+        177, 161, 244, 128, 84, 143, 225, 115, 63, 180, 3, 255, 107, 154, 212, 246, 138, 7, 110, 91, 112, 46, 34,
+        105, 47, 130, 203, 46, 122, 234, 64, 252,
+    ];
+    let iv = URL_SAFE_NO_PAD.decode("48V1_ALb6US04U3b").unwrap();
+    let aad = b"eyJhbGciOiJSU0EtT0FFUCIsImVuYyI6IkEyNTZHQ00ifQ";
+    let sealed = Aes256Gcm.encrypt(&key, &iv, PLAIN, aad).unwrap();
+    let (ciphertext, tag) = sealed.split_at(PLAIN.len());
+    let expected = "5eym8TW_c8SuK0ltJ3rpYIzOeDQz7TALvtu6UG9oMo4vpzs9tX_EFShS8iB7j6jiSdiwkIr3ajwQzaBtQD_A";
+    assert_eq!(URL_SAFE_NO_PAD.encode(ciphertext), expected);
+    assert_eq!(URL_SAFE_NO_PAD.encode(tag), "XFBoMYUZodetZdvTiFvSkQ");
+    assert_eq!(Aes256Gcm.decrypt(&key, &iv, &sealed, aad).unwrap(), PLAIN);
+}
+
+#[test]
+fn tokens_are_mechanically_valid_and_decode_by_hand() {
+    let temp = scratch_dir();
+    let token = encrypt(temp.path());
+    let parts: Vec<&str> = token.trim_end().split('.').collect();
+    assert_eq!(parts.len(), 5, "{token}");
+    assert!(parts.iter().all(|part| part.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')));
+
+    let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[0]).unwrap()).unwrap();
+    assert_eq!(header, serde_json::json!({ "alg": "dir", "enc": "A256GCM" }));
+    assert!(parts[1].is_empty());
+    let iv = URL_SAFE_NO_PAD.decode(parts[2]).unwrap();
+    let mut sealed = URL_SAFE_NO_PAD.decode(parts[3]).unwrap();
+    let tag = URL_SAFE_NO_PAD.decode(parts[4]).unwrap();
+    assert_eq!((iv.len(), sealed.len(), tag.len()), (12, PLAIN.len(), 16));
+
+    let key = fs::read_to_string(temp.path().join("key.txt")).unwrap();
+    let key = STANDARD.decode(key.trim_end()).unwrap();
+    sealed.extend(tag);
+    assert_eq!(Aes256Gcm.decrypt(&key, &iv, &sealed, parts[0].as_bytes()).unwrap(), PLAIN);
+
+    let decoded = deserialize_jwe_compact(token.trim_end()).unwrap();
+    assert_eq!(decoded.header, JweHeader::direct_a256gcm());
+}
+
+#[test]
+fn tokens_round_trip_and_each_one_has_its_own_iv() {
+    let temp = scratch_dir();
+    let first = encrypt(temp.path());
+    assert_eq!(decrypt(temp.path(), &["--key-file", "key.txt"]), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+
+    let output = run_app(temp.path(), &["--output-format", "jwe", "--key-file", "key.txt", "notes.txt", "again.jwe"]);
+    assert!(output.status.success());
+    let second = fs::read_to_string(temp.path().join("again.jwe")).unwrap();
+    assert_ne!(first.split('.').nth(2), second.split('.').nth(2));
+}
+
+#[test]
+fn a_wrong_key_or_a_changed_token_is_refused() {
+    let temp = scratch_dir();
+    let token = encrypt(temp.path());
+    let other = STANDARD.encode([7; 32]);
+    assert_eq!(decrypt(temp.path(), &["--key", &other]), Some(5));
+    assert!(!temp.path().join("notes.out").exists());
+
+    let changes = [
+        token.replacen("eyJ", "eyK", 1),
+        token.replacen("..", ".AAAA.", 1),
+        token.replace('.', ".."),
+    ];
+    for changed in changes.into_iter().chain((2..5).map(|part| {
+        let mut parts: Vec<String> = token.trim_end().split('.').map(String::from).collect();
+        let first = parts[part].remove(0);
+        parts[part].insert(0, if first == 'A' { 'B' } else { 'A' });
+        parts.join(".")
+    })) {
+        fs::write(temp.path().join("notes.jwe"), &changed).unwrap();
+        assert_eq!(decrypt(temp.path(), &["--key-file", "key.txt"]), Some(5), "{changed}");
+        assert!(!temp.path().join("notes.out").exists());
+    }
+}
+
+#[test]
+fn options_that_cant_go_with_a_token_are_refused() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    for args in [
+        &["--output-format", "jwe"][..],
+        &["--output-format", "yaml", "--key", "hunter2"],
+        &["--output-format", "jwe", "--key", "hunter2", "--cipher", "rot13"],
+        &["--output-format", "jwe", "--key", "hunter2", "--profile", "fast"],
+        &["--output-format", "jwe", "--key", "hunter2", "--mac", "hmac-sha256"],
+    ] {
+        let output = run_app(temp.path(), &[args, &["notes.txt", "notes.jwe"]].concat());
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+    }
+    // A key that isn't 32 bytes is a problem with the key, not the command line.
+    let output = run_app(temp.path(), &["--output-format", "jwe", "--key", "hunter2", "notes.txt", "notes.jwe"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(!temp.path().join("notes.jwe").exists());
+}
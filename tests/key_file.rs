@@ -0,0 +1,51 @@
+// The columnar cipher's key can be read from a file with --key-file instead of being typed on the command line.
+// This runs the built app with keys in files and checks they're used.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+// A key file's trailing line break isn't part of the key, so it encrypts the same way as the key typed out.
+#[test]
+fn key_from_file_is_used_like_key_on_command_line() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("key.txt"), "ZEBRAS\n").unwrap();
+    let output = run_app(dir, &["--columnar", "--key", "ZEBRAS", "notes.txt", "typed.enc"]);
+    assert!(output.status.success());
+    let output = run_app(dir, &["--columnar", "--key-file", "key.txt", "notes.txt", "file.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read(dir.join("file.enc")).unwrap(), fs::read(dir.join("typed.enc")).unwrap());
+
+    fs::write(dir.join("other.txt"), "GIRAFFE\r\n").unwrap();
+    let output = run_app(dir, &["--decrypt", "--key-file", "other.txt", "file.enc", "wrong.txt"]);
+    assert!(output.status.success());
+    assert_ne!(fs::read_to_string(dir.join("wrong.txt")).unwrap(), "Hello, World!");
+    let output = run_app(dir, &["--decrypt", "--key-file", "key.txt", "file.enc", "right.txt"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(dir.join("right.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn key_and_key_file_cannot_both_be_given() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("key.txt"), "ZEBRAS").unwrap();
+    let output = run_app(dir, &["--columnar", "--key", "ZEBRAS", "--key-file", "key.txt", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--key-file"));
+}
+
+#[test]
+fn missing_key_file_is_reported() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--columnar", "--key-file", "missing.txt", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Could not read key file missing.txt"));
+    assert!(!dir.join("notes.enc").exists());
+}
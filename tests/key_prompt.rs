@@ -0,0 +1,64 @@
+// Without a --key, the columnar cipher asks for one on the terminal, twice when encrypting. This runs the built
+// app on a pseudo-terminal, typing keys into it, and without a terminal, where there's nobody to ask.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+// "script" takes its arguments differently on other systems, so these only run on Linux.
+#[cfg(target_os = "linux")]
+mod terminal {
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use std::process::{Command, Output, Stdio};
+    use crate::common::{run_app, scratch_dir};
+
+    // This function runs the app on a pseudo-terminal made by "script", typing each line of `typed` at it.
+    fn run_on_terminal(dir: &Path, args: &str, typed: &str) -> Output {
+        let app = env!("CARGO_BIN_EXE_rust_project");
+        let mut child = Command::new("script")
+            .args(["-qec", &format!("'{app}' --no-config {args}"), "/dev/null"])
+            .current_dir(dir)
+            .env_remove("RUST_PROJECT_KEY")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(typed.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    #[test]
+    fn key_typed_twice_is_used_and_mismatched_keys_are_refused() {
+        let temp = scratch_dir();
+        let dir = temp.path();
+        fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+        assert!(run_app(dir, &["--columnar", "--key", "ZEBRAS", "notes.txt", "typed.enc"]).status.success());
+
+        let output = run_on_terminal(dir, "--columnar notes.txt prompted.enc", "ZEBRAS\nZEBRAS\n");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        assert_eq!(fs::read(dir.join("prompted.enc")).unwrap(), fs::read(dir.join("typed.enc")).unwrap());
+
+        let output = run_on_terminal(dir, "--columnar notes.txt mismatched.enc", "ZEBRAS\nGIRAFFE\n");
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("The keys don't match."));
+        assert!(!dir.join("mismatched.enc").exists());
+
+        // Decrypting only asks once.
+        let output = run_on_terminal(dir, "--decrypt --columnar prompted.enc notes.dec", "ZEBRAS\n");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Hello, World!");
+    }
+}
+
+#[test]
+fn missing_key_without_a_terminal_is_an_error() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--columnar", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs a --key"));
+}
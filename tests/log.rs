@@ -0,0 +1,82 @@
+// With "--log", every file processed adds a JSON object on a line of its own to the log file. This runs the
+// built app a couple of times and checks what ends up in the log.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use serde_json::Value;
+
+// This function reads every entry in a log file.
+fn read_log(path: &std::path::Path) -> Vec<Value> {
+    fs::read_to_string(path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+#[test]
+fn each_run_appends_an_entry() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert!(run_app(dir, &["--log", "audit.log", "notes.txt", "notes.enc"]).status.success());
+    // The second run fails, since the output is already there, and that's logged too.
+    assert!(!run_app(dir, &["--log", "audit.log", "notes.txt", "notes.enc"]).status.success());
+
+    let entries = read_log(&dir.join("audit.log"));
+    assert_eq!(entries.len(), 2);
+    for entry in &entries {
+        assert!(entry["timestamp"].as_str().unwrap().ends_with('Z'));
+        assert_eq!(entry["operation"], "encrypt");
+        assert_eq!(entry["cipher"], "rot13");
+        assert_eq!(entry["input"], "notes.txt");
+        assert_eq!(entry["output"], "notes.enc");
+    }
+    assert_eq!(entries[0]["result"], "ok");
+    assert_eq!(entries[0]["bytes_in"], 13);
+    assert_eq!(entries[1]["result"], "error");
+    assert_eq!(entries[1]["kind"], "output_exists");
+    assert!(entries[1]["message"].as_str().unwrap().contains("already exists"));
+}
+
+#[test]
+fn unwritable_log_only_warns() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--log", "no-such-dir/audit.log", "notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning:"));
+    assert!(dir.join("notes.enc").exists());
+}
+
+#[test]
+fn log_file_is_another_name_for_log() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    assert!(run_app(dir, &["--log-file", "audit.log", "notes.txt", "notes.enc"]).status.success());
+    fs::write(dir.join("config.toml"), "log-file = \"config.log\"\n").unwrap();
+    let output = run_app(dir, &["--config", "config.toml", "--decrypt", "notes.enc", "notes.dec"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(read_log(&dir.join("audit.log"))[0]["operation"], "encrypt");
+    assert_eq!(read_log(&dir.join("config.log"))[0]["operation"], "decrypt");
+}
+
+// Text given on the command line has no input file, and isn't copied into the log.
+#[test]
+fn text_is_logged_without_its_contents() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    assert!(run_app(dir, &["--log", "audit.log", "--text", "Hello, World!"]).status.success());
+    assert!(run_app(dir, &["--log", "audit.log", "--text", "Hello, World!", "notes.enc"]).status.success());
+
+    let log = fs::read_to_string(dir.join("audit.log")).unwrap();
+    assert!(!log.contains("Hello"));
+    let entries = read_log(&dir.join("audit.log"));
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0]["input"].is_null());
+    assert!(entries[0]["output"].is_null());
+    assert_eq!(entries[1]["output"], "notes.enc");
+    assert_eq!(entries[1]["result"], "ok");
+    assert_eq!(entries[1]["bytes_in"], 13);
+}
@@ -0,0 +1,46 @@
+// The details of what's going on are logged to stderr, at a level picked with -v or RUST_LOG. This runs the
+// built app at a few levels and checks which messages show up.
+
+mod common;
+
+use std::fs;
+use common::{app, run_app, scratch_dir};
+
+#[test]
+fn details_are_only_logged_when_asked_for() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Size of notes"));
+}
+
+#[test]
+fn verbose_flags_raise_the_level() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+
+    // -v logs what each step did, but not what went into it.
+    let stderr = String::from_utf8(run_app(dir, &["-v", "notes.txt", "one.enc"]).stderr).unwrap();
+    assert!(stderr.contains("Size of notes: 13 bytes"));
+    assert!(!stderr.contains("Cipher: rot13"));
+
+    // -vv logs that too.
+    let stderr = String::from_utf8(run_app(dir, &["-vv", "notes.txt", "two.enc"]).stderr).unwrap();
+    assert!(stderr.contains("Cipher: rot13"));
+    assert!(stderr.contains("Contents of notes:"));
+}
+
+#[test]
+fn rust_log_picks_the_level() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = app(dir).args(["notes.txt", "notes.enc"]).env("RUST_LOG", "debug").output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Size of notes: 13 bytes"));
+    assert!(stderr.contains("Cipher: rot13"));
+}
@@ -0,0 +1,115 @@
+// "--mac hmac-sha256" adds an HMAC-SHA256 tag after a classical cipher's output, keyed with a password, so a
+// changed file is refused instead of decrypting to nonsense. This checks tags are computed and compared
+// correctly, that files round-trip with the tag, that changing any byte outside the KDF's settings (or using
+// the wrong password) is refused before anything is written, and that --mac is refused where it can't work.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+use rust_project::{compute_hmac, verify_mac_constant_time, FileHeader, MAC_LEN};
+
+const PLAIN: &[u8] = b"Attack at dawn, from the north side of the hill.\n";
+
+// This function encrypts notes.txt to notes.enc with a MAC keyed with "hunter2", using some cipher settings.
+// scrypt with a small N keeps it quick.
+fn encrypt(dir: &Path, cipher: &[&str]) -> Vec<u8> {
+    fs::write(dir.join("notes.txt"), PLAIN).unwrap();
+    let mac = ["--mac", "hmac-sha256", "--key", "hunter2", "--scrypt-n", "1024", "notes.txt", "notes.enc"];
+    let output = run_app(dir, &[cipher, &mac[..]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read(dir.join("notes.enc")).unwrap()
+}
+
+// This function decrypts notes.enc to notes.out with some extra arguments, giving back the exit code.
+fn decrypt(dir: &Path, extra: &[&str]) -> Option<i32> {
+    let _ = fs::remove_file(dir.join("notes.out"));
+    run_app(dir, &[&["--decrypt"], extra, &["notes.enc", "notes.out"]].concat()).status.code()
+}
+
+// RFC 4231, test case 1.
+#[test]
+fn compute_hmac_matches_rfc_4231() {
+    let tag = compute_hmac(&[0x0b; 20], b"Hi There");
+    let hex: String = tag.iter().map(|byte| format!("{byte:02x}")).collect();
+    assert_eq!(hex, "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7");
+}
+
+// A comparison that stops early would only notice this after checking every other byte, which is exactly the
+// case a timing attack works its way towards.
+#[test]
+fn tags_that_differ_only_in_the_last_byte_dont_match() {
+    let tag = compute_hmac(b"key", b"message");
+    for bit in 0..8 {
+        let mut forged = tag;
+        forged[MAC_LEN - 1] ^= 1 << bit;
+        assert!(!verify_mac_constant_time(&tag, &forged), "bit {bit}");
+    }
+    assert!(verify_mac_constant_time(&tag, &tag));
+}
+
+#[test]
+fn files_with_a_mac_round_trip() {
+    let temp = scratch_dir();
+    for cipher in [&["--cipher", "rot13"][..], &["--shift", "3"]] {
+        let encrypted = encrypt(temp.path(), cipher);
+        let (header, rest) = FileHeader::parse(&encrypted).unwrap().unwrap();
+        assert!(header.mac);
+        assert_eq!((header.kdf_id, header.salt.len()), (3, 16));
+        assert_eq!(rest.len(), PLAIN.len() + MAC_LEN);
+        assert_eq!(decrypt(temp.path(), &[cipher, &["--key", "hunter2"][..]].concat()), Some(0));
+        assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+        fs::remove_file(temp.path().join("notes.enc")).unwrap();
+    }
+}
+
+#[test]
+fn a_changed_file_is_refused_before_anything_is_written() {
+    let temp = scratch_dir();
+    let original = encrypt(temp.path(), &["--cipher", "rot13"]);
+    let header = FileHeader::parse(&original).unwrap().unwrap().0;
+    // Changing the KDF's settings could make it take any amount of time or memory, so those bytes are left alone.
+    let params = 9..9 + header.kdf_params.len();
+    for i in (0..original.len()).filter(|i| !params.contains(i)) {
+        let mut changed = original.clone();
+        changed[i] ^= 1;
+        fs::write(temp.path().join("notes.enc"), changed).unwrap();
+        assert_eq!(decrypt(temp.path(), &["--key", "hunter2"]), Some(5), "byte {i}");
+        assert!(!temp.path().join("notes.out").exists(), "byte {i}");
+    }
+    // Cutting the tag off (or short) is the same as changing it.
+    for len in [original.len() - 1, original.len() - MAC_LEN, header.len()] {
+        fs::write(temp.path().join("notes.enc"), &original[..len]).unwrap();
+        assert_eq!(decrypt(temp.path(), &["--key", "hunter2"]), Some(5), "cut to {len} bytes");
+        assert!(!temp.path().join("notes.out").exists());
+    }
+}
+
+#[test]
+fn the_wrong_password_or_none_is_refused() {
+    let temp = scratch_dir();
+    encrypt(temp.path(), &["--cipher", "rot13"]);
+    assert_eq!(decrypt(temp.path(), &["--key", "hunter3"]), Some(5));
+    assert_eq!(decrypt(temp.path(), &[]), Some(2));
+    assert_eq!(decrypt(temp.path(), &["--detect-shift"]), Some(5));
+    assert!(!temp.path().join("notes.out").exists());
+}
+
+#[test]
+fn mac_is_refused_where_it_cant_work() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    for args in [
+        &["--mac", "hmac-sha256"][..],
+        &["--mac", "md5", "--key", "hunter2"],
+        &["--mac", "hmac-sha256", "--key", "ZEBRAS", "--columnar"],
+        &["--mac", "hmac-sha256", "--key", "hunter2", "--cipher", "chacha20-poly1305"],
+        &["--mac", "hmac-sha256", "--key", "hunter2", "--no-header"],
+        &["--mac", "hmac-sha256", "--key", "hunter2", "--decrypt"],
+    ] {
+        let output = run_app(temp.path(), &[args, &["notes.txt", "notes.enc"]].concat());
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+    }
+    assert!(!temp.path().join("notes.enc").exists());
+}
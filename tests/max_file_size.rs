@@ -0,0 +1,54 @@
+// With --max-file-size, input files bigger than the limit are refused instead of read, so a mistyped path to
+// something huge (or endless, like /dev/zero) fails quickly. This runs the built app on files of different sizes.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+#[test]
+fn files_over_the_limit_are_refused() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("big.txt"), vec![b'a'; 2 * 1024 * 1024]).unwrap();
+    let output = run_app(dir, &["--max-file-size", "1MB", "big.txt", "big.enc"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("big.txt is at least 2097152 bytes, more than --max-file-size allows (1000000 bytes)"));
+    assert!(!dir.join("big.enc").exists());
+
+    // Mapping the file into memory doesn't get around the limit.
+    let output = run_app(dir, &["--mmap", "--max-file-size", "1MB", "big.txt", "big.enc"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!dir.join("big.enc").exists());
+}
+
+#[test]
+fn files_up_to_the_limit_are_read() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("small.txt"), vec![b'a'; 1000]).unwrap();
+    let output = run_app(dir, &["--max-file-size", "1000", "small.txt", "small.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(dir.join("small.enc").exists());
+}
+
+// /dev/zero has no size to check, and never ends, so the limit is checked while it's being read.
+#[cfg(unix)]
+#[test]
+fn endless_input_stops_at_the_limit() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    let output = run_app(dir, &["--max-file-size", "1MiB", "--json", "/dev/zero", "zero.enc"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"file_too_large\""));
+    assert!(!dir.join("zero.enc").exists());
+}
+
+#[test]
+fn the_limit_needs_a_size() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &["--max-file-size", "huge", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-file-size needs a size"));
+}
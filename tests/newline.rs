@@ -0,0 +1,95 @@
+// Line endings in the plain text are left exactly as they are unless --newline asks for them all to be LF or
+// CRLF. This runs the built app on files with CRLF, LF, and mixed line endings, and ones without a line ending at
+// the end, and checks what comes back out.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+const CRLF: &[u8] = b"first\r\nsecond\r\nthird\r\n";
+const LF: &[u8] = b"first\nsecond\nthird\n";
+const MIXED: &[u8] = b"first\r\nsecond\nthird\r\nfourth\n";
+const NO_FINAL_NEWLINE: &[u8] = b"first\nsecond\r\nlast line";
+
+// This function encrypts `plain` and decrypts it again, with `encrypt_args` and `decrypt_args` added to each,
+// giving back what the decrypted file holds.
+fn round_trip(dir: &Path, plain: &[u8], encrypt_args: &[&str], decrypt_args: &[&str]) -> Vec<u8> {
+    fs::write(dir.join("plain.txt"), plain).unwrap();
+    let output = run_app(dir, &[encrypt_args, &["--force", "plain.txt", "plain.enc"]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run_app(dir, &[decrypt_args, &["--decrypt", "--force", "plain.enc", "plain.dec"]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read(dir.join("plain.dec")).unwrap()
+}
+
+#[test]
+fn line_endings_are_preserved_by_default() {
+    let temp = scratch_dir();
+    for plain in [CRLF, LF, MIXED, NO_FINAL_NEWLINE] {
+        assert_eq!(round_trip(temp.path(), plain, &[], &[]), plain);
+        assert_eq!(round_trip(temp.path(), plain, &["--newline", "preserve"], &["--newline", "preserve"]), plain);
+    }
+}
+
+// Every cipher gets the same text, so line endings survive ones that move characters around too.
+#[test]
+fn line_endings_are_preserved_by_every_cipher() {
+    let temp = scratch_dir();
+    let ciphers: [&[&str]; 4] =
+        [&["--rails", "3"], &["--affine-a", "5", "--affine-b", "8"], &["--base32"], &["--unicode"]];
+    for cipher in ciphers {
+        assert_eq!(round_trip(temp.path(), MIXED, cipher, cipher), MIXED);
+        assert_eq!(round_trip(temp.path(), NO_FINAL_NEWLINE, cipher, cipher), NO_FINAL_NEWLINE);
+    }
+}
+
+#[test]
+fn encrypting_with_lf_or_crlf_changes_every_line_ending() {
+    let temp = scratch_dir();
+    let mixed_lf: &[u8] = b"first\nsecond\nthird\nfourth\n";
+    let mixed_crlf: &[u8] = b"first\r\nsecond\r\nthird\r\nfourth\r\n";
+    for (plain, lf, crlf) in [(CRLF, LF, CRLF), (LF, LF, CRLF), (MIXED, mixed_lf, mixed_crlf)] {
+        assert_eq!(round_trip(temp.path(), plain, &["--newline", "lf"], &[]), lf);
+        assert_eq!(round_trip(temp.path(), plain, &["--newline", "crlf"], &[]), crlf);
+    }
+}
+
+// Decrypting converts the decrypted text, whatever line endings it was encrypted with.
+#[test]
+fn decrypting_with_lf_or_crlf_changes_every_line_ending() {
+    let temp = scratch_dir();
+    assert_eq!(round_trip(temp.path(), MIXED, &[], &["--newline", "lf"]), b"first\nsecond\nthird\nfourth\n");
+    assert_eq!(
+        round_trip(temp.path(), MIXED, &[], &["--newline", "crlf"]),
+        b"first\r\nsecond\r\nthird\r\nfourth\r\n"
+    );
+}
+
+// A last line without a line ending doesn't get one, and a "\r" that isn't followed by "\n" isn't a line ending.
+#[test]
+fn a_last_line_without_a_line_ending_is_left_without_one() {
+    let temp = scratch_dir();
+    let crlf = round_trip(temp.path(), NO_FINAL_NEWLINE, &["--newline", "crlf"], &[]);
+    assert_eq!(crlf, b"first\r\nsecond\r\nlast line");
+    assert_eq!(round_trip(temp.path(), NO_FINAL_NEWLINE, &["--newline", "lf"], &[]), b"first\nsecond\nlast line");
+    assert_eq!(round_trip(temp.path(), b"a\rb\r\n\r", &["--newline", "lf"], &[]), b"a\rb\n\r");
+}
+
+// The printed result ends with a line break of its own, after the converted text.
+#[test]
+fn text_is_converted_too() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &["--newline", "crlf", "--text", "Hello\nWorld\n"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"Uryyb\r\nJbeyq\r\n\n");
+}
+
+#[test]
+fn unknown_styles_are_refused() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &["--newline", "cr", "plain.txt", "plain.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("use preserve, lf, or crlf"));
+}
@@ -0,0 +1,89 @@
+// With --output-dir, each output file is named after its input file: encrypting adds the extension (.enc, or the
+// --ext EXT), and decrypting removes it, or adds .dec if it isn't there. This checks the names for files with no
+// extension, several dots, a leading dot, or the extension already on the end.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+
+// This function writes some files to in/, processes each one into out/ with some options, and checks the output
+// files have the expected names and that the right ones were made.
+fn check_names(dir: &Path, options: &[&str], names: &[(&str, &str)]) {
+    let _ = fs::remove_dir_all(dir.join("in"));
+    let _ = fs::remove_dir_all(dir.join("out"));
+    fs::create_dir(dir.join("in")).unwrap();
+    for (input, _) in names {
+        fs::write(dir.join("in").join(input), format!("This was {input}.\n")).unwrap();
+        let output = run_app(dir, &[options, &["--output-dir", "out", &format!("in/{input}")]].concat());
+        assert!(output.status.success(), "{input}: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    let mut made: Vec<String> =
+        fs::read_dir(dir.join("out")).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    made.sort();
+    let mut expected: Vec<&str> = names.iter().map(|(_, output)| *output).collect();
+    expected.sort();
+    assert_eq!(made, expected, "{options:?}");
+}
+
+#[test]
+fn encrypting_adds_the_extension() {
+    let temp = scratch_dir();
+    check_names(
+        temp.path(),
+        &[],
+        &[
+            ("README", "README.enc"),
+            ("archive.tar.gz", "archive.tar.gz.enc"),
+            (".bashrc", ".bashrc.enc"),
+            ("notes.enc", "notes.enc.enc"),
+        ],
+    );
+}
+
+#[test]
+fn decrypting_removes_the_extension_or_adds_dec() {
+    let temp = scratch_dir();
+    check_names(
+        temp.path(),
+        &["--decrypt"],
+        &[
+            ("README.enc", "README"),
+            ("archive.tar.gz.enc", "archive.tar.gz"),
+            (".bashrc.enc", ".bashrc"),
+            ("notes.enc.enc", "notes.enc"),
+            ("notes.ENC", "notes.ENC.dec"),
+            ("plain", "plain.dec"),
+            ("plain.txt", "plain.txt.dec"),
+            (".enc", ".enc.dec"),
+        ],
+    );
+}
+
+#[test]
+fn another_extension_can_be_picked() {
+    let temp = scratch_dir();
+    check_names(temp.path(), &["--ext", "lock"], &[("notes.txt", "notes.txt.lock"), ("notes.enc", "notes.enc.lock")]);
+    check_names(
+        temp.path(),
+        &["--decrypt", "--ext", "lock"],
+        &[("notes.txt.lock", "notes.txt"), ("notes.enc", "notes.enc.dec"), ("a.lock.lock", "a.lock")],
+    );
+}
+
+#[test]
+fn names_round_trip() {
+    let temp = scratch_dir();
+    let names = ["README", "archive.tar.gz", ".bashrc", "notes.enc"];
+    fs::create_dir(temp.path().join("in")).unwrap();
+    for name in names {
+        fs::write(temp.path().join("in").join(name), format!("This was {name}.\n")).unwrap();
+        let output = run_app(temp.path(), &["--output-dir", "out", &format!("in/{name}")]);
+        assert!(output.status.success());
+        let encrypted = format!("out/{name}.enc");
+        let output = run_app(temp.path(), &["--decrypt", "--output-dir", "back", &encrypted]);
+        assert!(output.status.success(), "{name}");
+        assert_eq!(fs::read_to_string(temp.path().join("back").join(name)).unwrap(), format!("This was {name}.\n"));
+    }
+}
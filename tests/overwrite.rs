@@ -0,0 +1,66 @@
+// Output files that already exist are never overwritten unless that's asked for with --force, and
+// --skip-existing leaves those files out instead. This runs the built app on outputs that are already there.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+#[test]
+fn existing_output_fails_without_force() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("notes.enc"), "old contents").unwrap();
+    let output = run_app(dir, &["notes.txt", "notes.enc"]);
+    assert_ne!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+
+    assert!(run_app(dir, &["--force", "notes.txt", "notes.enc"]).status.success());
+    assert_ne!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+}
+
+// The config file can turn --force on, and --no-overwrite turns it off again.
+#[test]
+fn no_overwrite_beats_force_from_config_file() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("notes.enc"), "old contents").unwrap();
+    fs::write(dir.join("config.toml"), "force = true\n").unwrap();
+    let output = run_app(dir, &["--config", "config.toml", "--no-overwrite", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+}
+
+#[test]
+fn skip_existing_leaves_those_files_out() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("a.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("b.txt"), "Hello, World!").unwrap();
+    fs::create_dir(dir.join("out")).unwrap();
+    fs::write(dir.join("out/a.txt.enc"), "old contents").unwrap();
+    let output = run_app(dir, &["--skip-existing", "--output-dir", "out", "a.txt", "b.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 files processed, 1 skipped."));
+    assert_eq!(fs::read_to_string(dir.join("out/a.txt.enc")).unwrap(), "old contents");
+    assert!(dir.join("out/b.txt.enc").exists());
+
+    // A dry run says which files would be skipped.
+    fs::remove_file(dir.join("out/b.txt.enc")).unwrap();
+    let output = run_app(dir, &["--dry-run", "--skip-existing", "--output-dir", "out", "a.txt", "b.txt"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would skip a.txt"));
+}
+
+#[test]
+fn skip_existing_and_force_cannot_both_be_given() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--skip-existing", "--force", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!dir.join("notes.enc").exists());
+}
@@ -0,0 +1,138 @@
+// chacha20-poly1305 derives its key from a password with a KDF picked by --kdf, and puts which one it was, its
+// settings, the salt, and the nonce in the header. This checks files round-trip with each KDF and with custom
+// and preset scrypt settings, that the settings are stored in the header, and that a wrong password, a changed
+// file, or settings that don't make sense or cost too much are refused.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use rust_project::FileHeader;
+
+const PLAIN: &[u8] = b"Meet me by the old oak tree at midnight.\n";
+
+// This function encrypts notes.txt to notes.enc with the password "hunter2" and some extra arguments, and gives
+// back the header of the encrypted file.
+fn encrypt(dir: &std::path::Path, extra: &[&str]) -> FileHeader {
+    fs::write(dir.join("notes.txt"), PLAIN).unwrap();
+    let mut args = vec!["--cipher", "chacha20-poly1305", "--key", "hunter2"];
+    args.extend_from_slice(extra);
+    args.extend(["notes.txt", "notes.enc"]);
+    let output = run_app(dir, &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    FileHeader::parse(&fs::read(dir.join("notes.enc")).unwrap()).unwrap().unwrap().0
+}
+
+// This function decrypts notes.enc to notes.out with a password, giving back the exit code.
+fn decrypt(dir: &std::path::Path, password: &str) -> Option<i32> {
+    let _ = fs::remove_file(dir.join("notes.out"));
+    run_app(dir, &["--decrypt", "--force", "--key", password, "notes.enc", "notes.out"]).status.code()
+}
+
+#[test]
+fn scrypt_settings_are_stored_in_the_header() {
+    let temp = scratch_dir();
+    let header = encrypt(temp.path(), &["--scrypt-n", "1024", "--scrypt-r", "4", "--scrypt-p", "2"]);
+    assert_eq!((header.version, header.cipher_id, header.kdf_id), (2, 12, 3));
+    assert_eq!(header.kdf_params, [10, 0, 0, 0, 4, 0, 0, 0, 2]);
+    assert_eq!((header.salt.len(), header.nonce.len()), (16, 12));
+    // Decrypting reads the settings from the header, so they don't have to be given again.
+    assert_eq!(decrypt(temp.path(), "hunter2"), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+}
+
+#[test]
+fn a_preset_can_have_some_of_its_settings_replaced() {
+    let temp = scratch_dir();
+    let header = encrypt(temp.path(), &["--scrypt-preset", "interactive"]);
+    assert_eq!(header.kdf_params, [14, 0, 0, 0, 8, 0, 0, 0, 1]);
+    let header = encrypt(temp.path(), &["--force", "--scrypt-preset", "interactive", "--scrypt-p", "3"]);
+    assert_eq!(header.kdf_params, [14, 0, 0, 0, 8, 0, 0, 0, 3]);
+    assert_eq!(decrypt(temp.path(), "hunter2"), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+}
+
+#[test]
+fn argon2id_is_the_default_and_pbkdf2_can_be_picked() {
+    let temp = scratch_dir();
+    assert_eq!(encrypt(temp.path(), &[]).kdf_id, 1);
+    assert_eq!(decrypt(temp.path(), "hunter2"), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+    let header = encrypt(temp.path(), &["--force", "--kdf", "pbkdf2"]);
+    assert_eq!((header.kdf_id, header.kdf_params.as_slice()), (2, &600_000u32.to_be_bytes()[..]));
+    assert_eq!(decrypt(temp.path(), "hunter2"), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+}
+
+#[test]
+fn a_wrong_password_or_a_changed_file_is_refused() {
+    let temp = scratch_dir();
+    encrypt(temp.path(), &["--scrypt-n", "1024"]);
+    assert_eq!(decrypt(temp.path(), "hunter3"), Some(5));
+    assert!(!temp.path().join("notes.out").exists());
+
+    let original = fs::read(temp.path().join("notes.enc")).unwrap();
+    let header_len = FileHeader::parse(&original).unwrap().unwrap().0.len();
+    // A byte of the salt, a byte of the nonce, and the last byte of the tag.
+    for i in [header_len - 20, header_len - 1, original.len() - 1] {
+        let mut changed = original.clone();
+        changed[i] ^= 1;
+        fs::write(temp.path().join("notes.enc"), changed).unwrap();
+        assert_eq!(decrypt(temp.path(), "hunter2"), Some(5), "byte {i}");
+        assert!(!temp.path().join("notes.out").exists());
+    }
+}
+
+#[test]
+fn settings_that_dont_make_sense_are_refused() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    let chacha = ["--cipher", "chacha20-poly1305", "--key", "hunter2"];
+    for extra in [
+        &["--scrypt-n", "1000"][..],
+        &["--scrypt-n", "1"],
+        &["--scrypt-r", "0"],
+        &["--scrypt-p", "many"],
+        &["--scrypt-preset", "quick"],
+        &["--kdf", "bcrypt"],
+        &["--kdf", "pbkdf2", "--scrypt-n", "1024"],
+        &["--no-header"],
+    ] {
+        let output = run_app(temp.path(), &[&chacha[..], extra, &["notes.txt", "notes.enc"]].concat());
+        assert_eq!(output.status.code(), Some(2), "{extra:?}");
+    }
+    // The KDF is only picked when encrypting with a password.
+    let output = run_app(temp.path(), &["--kdf", "scrypt", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!temp.path().join("notes.enc").exists());
+}
+
+#[test]
+fn settings_that_cost_too_much_are_refused() {
+    let temp = scratch_dir();
+    encrypt(temp.path(), &[]);
+    let original = fs::read(temp.path().join("notes.enc")).unwrap();
+    let header = FileHeader::parse(&original).unwrap().unwrap().0;
+    let body = &original[header.len()..];
+    // Each KDF in turn, asking for far more memory or passes than deriving a key should ever take.
+    for (kdf_id, kdf_params) in [
+        (1, [&0xffff_ff00u32.to_be_bytes()[..], &[0, 0, 0, 3, 0, 0, 0, 4]].concat()),
+        (1, vec![0, 1, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 4]),
+        (2, u32::MAX.to_be_bytes().to_vec()),
+        (3, vec![40, 0, 0, 0, 8, 0, 0, 0, 1]),
+        (3, vec![20, 0, 0, 0, 8, 0, 0, 0, 2]),
+    ] {
+        let mut changed = FileHeader { kdf_id, kdf_params: kdf_params.clone(), ..header.clone() }.to_bytes();
+        changed.extend_from_slice(body);
+        fs::write(temp.path().join("notes.enc"), changed).unwrap();
+        assert_eq!(decrypt(temp.path(), "hunter2"), Some(5), "{kdf_id}: {kdf_params:?}");
+        assert!(!temp.path().join("notes.out").exists());
+    }
+    // Encrypting with settings that couldn't be read back is refused up front.
+    let chacha = ["--cipher", "chacha20-poly1305", "--key", "hunter2"];
+    for extra in [&["--scrypt-n", "2097152"][..], &["--scrypt-preset", "sensitive", "--scrypt-p", "2"]] {
+        let output = run_app(temp.path(), &[&chacha[..], extra, &["notes.txt", "big.enc"]].concat());
+        assert_eq!(output.status.code(), Some(2), "{extra:?}");
+        assert!(!temp.path().join("big.enc").exists());
+    }
+}
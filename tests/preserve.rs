@@ -0,0 +1,88 @@
+// The output file gets the input file's permissions, and with --preserve its modification time too. This runs
+// the built app on files with known modes and times and checks the output files ended up with the same ones.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use common::{run_app, scratch_dir};
+
+// A modification time well in the past, so it can't be mistaken for the time the output was written.
+const OLD_TIME: Duration = Duration::from_secs(978_307_200);
+
+// This function writes a file with some permissions and an old modification time.
+fn write_file(path: &Path, mode: u32) {
+    fs::write(path, "Hello, World!").unwrap();
+    fs::File::options().write(true).open(path).unwrap().set_modified(SystemTime::UNIX_EPOCH + OLD_TIME).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+// This function gives back a file's permission bits.
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+// This function gives back how far a file's modification time is from OLD_TIME.
+fn time_off(path: &Path) -> Duration {
+    let modified = fs::metadata(path).unwrap().modified().unwrap().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    modified.abs_diff(OLD_TIME)
+}
+
+#[test]
+fn in_place_keeps_the_original_mode_and_time() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    write_file(&dir.join("a.txt"), 0o700);
+    assert!(run_app(dir, &["--quiet", "--in-place", "--preserve", "a.txt"]).status.success());
+    assert_eq!(mode(&dir.join("a.txt")), 0o700);
+    assert!(time_off(&dir.join("a.txt")) < Duration::from_secs(1));
+    assert_ne!(fs::read_to_string(dir.join("a.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn preserve_copies_the_mode_and_time() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    write_file(&dir.join("notes.txt"), 0o640);
+    assert!(run_app(dir, &["--quiet", "--preserve", "notes.txt", "notes.enc"]).status.success());
+    assert_eq!(mode(&dir.join("notes.enc")), 0o640);
+    assert!(time_off(&dir.join("notes.enc")) < Duration::from_secs(1));
+}
+
+#[test]
+fn read_only_input_gives_a_read_only_output() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    write_file(&dir.join("notes.txt"), 0o444);
+    assert!(run_app(dir, &["--quiet", "--preserve", "notes.txt", "notes.enc"]).status.success());
+    assert_eq!(mode(&dir.join("notes.enc")), 0o444);
+    assert!(time_off(&dir.join("notes.enc")) < Duration::from_secs(1));
+    assert!(run_app(dir, &["--quiet", "--decrypt", "notes.enc", "back.txt"]).status.success());
+    assert_eq!(fs::read_to_string(dir.join("back.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn executable_input_gives_an_executable_output() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    write_file(&dir.join("script.sh"), 0o755);
+    assert!(run_app(dir, &["--quiet", "script.sh", "script.enc"]).status.success());
+    assert_eq!(mode(&dir.join("script.enc")), 0o755);
+    assert!(run_app(dir, &["--quiet", "--no-preserve-perms", "script.sh", "plain.enc"]).status.success());
+    assert_eq!(mode(&dir.join("plain.enc")) & 0o111, 0);
+}
+
+#[test]
+fn preserve_time_copies_the_modification_time() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    write_file(&dir.join("notes.txt"), 0o644);
+    assert!(run_app(dir, &["--quiet", "--preserve-time", "notes.txt", "kept.enc"]).status.success());
+    assert!(time_off(&dir.join("kept.enc")) < Duration::from_secs(1));
+    assert!(run_app(dir, &["--quiet", "notes.txt", "new.enc"]).status.success());
+    assert!(time_off(&dir.join("new.enc")) > Duration::from_secs(1));
+}
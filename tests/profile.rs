@@ -0,0 +1,90 @@
+// --profile picks a cipher and a KDF together, and "info" shows which profile a file was encrypted with, from its
+// header. This checks a file encrypted with each profile decrypts again and is shown as that profile, that
+// anything else is shown as custom, and that picking a cipher or KDF as well as a profile gives a warning.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use common::{run_app, scratch_dir};
+use rust_project::{CipherProfile, FileHeader};
+
+const PLAIN: &[u8] = b"The spare key is under the third flowerpot.\n";
+
+// This function runs "info" on notes.enc, giving back what it printed.
+fn info(dir: &Path) -> String {
+    let output = run_app(dir, &["info", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn files_encrypted_with_each_profile_decrypt_and_show_it() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    for profile in CipherProfile::ALL {
+        let args = ["--force", "--profile", profile.name, "--key", "hunter2", "notes.txt", "notes.enc"];
+        let output = run_app(temp.path(), &args);
+        assert!(output.status.success(), "{}: {}", profile.name, String::from_utf8_lossy(&output.stderr));
+        assert!(output.stderr.is_empty(), "{}", profile.name);
+        assert!(info(temp.path()).contains(&format!("Profile: {}\n", profile.name)));
+
+        let output = run_app(temp.path(), &["--decrypt", "--force", "--key", "hunter2", "notes.enc", "notes.out"]);
+        assert!(output.status.success(), "{}: {}", profile.name, String::from_utf8_lossy(&output.stderr));
+        assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN, "{}", profile.name);
+    }
+}
+
+#[test]
+fn balanced_is_what_chacha20_poly1305_uses_by_default() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    let output = run_app(temp.path(), &["--cipher", "chacha20-poly1305", "--key", "hunter2", "notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    assert!(info(temp.path()).contains("Profile: balanced\n"));
+}
+
+#[test]
+fn anything_else_is_custom() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    for args in [
+        &["--cipher", "chacha20-poly1305", "--scrypt-n", "1024"][..],
+        &["--mac", "hmac-sha256", "--scrypt-n", "1024"],
+        &["--shift", "3"],
+    ] {
+        let output = run_app(temp.path(), &[args, &["--force", "--key", "hunter2", "notes.txt", "notes.enc"]].concat());
+        assert!(output.status.success(), "{args:?}");
+        assert!(info(temp.path()).contains("Profile: custom\n"), "{args:?}");
+    }
+    let output = run_app(temp.path(), &["info", "--json", "notes.enc"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["cipher"], "caesar");
+    assert_eq!(json["kdf"], serde_json::Value::Null);
+    assert_eq!(json["profile"], "custom");
+}
+
+#[test]
+fn a_profile_replaces_the_cipher_and_kdf_with_a_warning() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    let profile = ["--profile", "fast", "--cipher", "caesar", "--shift", "3"];
+    let output = run_app(temp.path(), &[&profile[..], &["--key", "hunter2", "notes.txt", "notes.enc"]].concat());
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning: --profile fast replaces --cipher"), "{stderr}");
+    let (header, _) = FileHeader::parse(&fs::read(temp.path().join("notes.enc")).unwrap()).unwrap().unwrap();
+    assert_eq!((header.cipher_id, header.kdf_id), (12, 3));
+}
+
+#[test]
+fn unknown_profiles_and_files_without_a_header_are_refused() {
+    let temp = scratch_dir();
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    let output = run_app(temp.path(), &["--profile", "quick", "--key", "hunter2", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(run_app(temp.path(), &["info"]).status.code(), Some(2));
+    let output = run_app(temp.path(), &["info", "notes.txt"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("doesn't start with a header"));
+}
@@ -0,0 +1,107 @@
+// "--recipient-key" encrypts a file for the holder of an RSA public key: a random session key is encrypted with
+// RSA-OAEP and the file with ChaCha20-Poly1305, and "--private-key" undoes it. This makes real 2048-bit keys and
+// checks files round-trip, that the wrong private key or a change to the encrypted session key, the nonce, or the
+// ciphertext is refused, and that a private key encrypted with a passphrase needs "--passphrase-file".
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use common::{run_app, scratch_dir};
+use rand::rngs::OsRng;
+use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::RsaPrivateKey;
+use rust_project::FileHeader;
+
+const PLAIN: &[u8] = b"The rendezvous has moved to the boathouse, same time.\n";
+
+// Making an RSA key takes a while, so the two keys these tests need are only made once.
+fn keys() -> &'static [RsaPrivateKey; 2] {
+    static KEYS: OnceLock<[RsaPrivateKey; 2]> = OnceLock::new();
+    KEYS.get_or_init(|| [0, 1].map(|_| RsaPrivateKey::new(&mut OsRng, 2048).unwrap()))
+}
+
+// This function writes the first key pair to public.pem and private.pem and the second private key to
+// other.pem, and encrypts notes.txt to notes.enc for the first one, giving back the encrypted file.
+fn encrypt(dir: &Path) -> Vec<u8> {
+    let [key, other] = keys();
+    fs::write(dir.join("public.pem"), key.to_public_key().to_public_key_pem(LineEnding::LF).unwrap()).unwrap();
+    fs::write(dir.join("private.pem"), key.to_pkcs8_pem(LineEnding::LF).unwrap().as_bytes()).unwrap();
+    fs::write(dir.join("other.pem"), other.to_pkcs8_pem(LineEnding::LF).unwrap().as_bytes()).unwrap();
+    fs::write(dir.join("notes.txt"), PLAIN).unwrap();
+    let output = run_app(dir, &["--force", "--recipient-key", "public.pem", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read(dir.join("notes.enc")).unwrap()
+}
+
+// This function decrypts notes.enc to notes.out with some extra arguments, giving back the exit code.
+fn decrypt(dir: &Path, extra: &[&str]) -> Option<i32> {
+    let _ = fs::remove_file(dir.join("notes.out"));
+    run_app(dir, &[&["--decrypt"], extra, &["notes.enc", "notes.out"]].concat()).status.code()
+}
+
+#[test]
+fn files_round_trip_with_a_2048_bit_key() {
+    let temp = scratch_dir();
+    let encrypted = encrypt(temp.path());
+    let (header, rest) = FileHeader::parse(&encrypted).unwrap().unwrap();
+    assert_eq!(header.cipher_id, 7);
+    // The session key's length, the session key encrypted with a 2048-bit key, the nonce, and the tag.
+    assert_eq!(rest.len(), 2 + 256 + 12 + PLAIN.len() + 16);
+    assert_eq!(decrypt(temp.path(), &["--private-key", "private.pem"]), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+
+    // Encrypting the same file again uses a new session key and nonce.
+    let output = run_app(temp.path(), &["--recipient-key", "public.pem", "notes.txt", "again.enc"]);
+    assert!(output.status.success());
+    assert_ne!(fs::read(temp.path().join("again.enc")).unwrap(), encrypted);
+}
+
+#[test]
+fn the_wrong_private_key_or_none_is_refused() {
+    let temp = scratch_dir();
+    encrypt(temp.path());
+    assert_eq!(decrypt(temp.path(), &["--private-key", "other.pem"]), Some(5));
+    assert_eq!(decrypt(temp.path(), &[]), Some(2));
+    assert!(!temp.path().join("notes.out").exists());
+}
+
+#[test]
+fn a_changed_key_block_nonce_or_ciphertext_is_refused() {
+    let temp = scratch_dir();
+    let original = encrypt(temp.path());
+    let start = FileHeader::parse(&original).unwrap().unwrap().0.len();
+    // A byte of the encrypted session key, a byte of the nonce, a byte of the ciphertext, and the tag's last byte.
+    for i in [start + 2 + 100, start + 2 + 256 + 5, start + 2 + 256 + 12 + 3, original.len() - 1] {
+        let mut changed = original.clone();
+        changed[i] ^= 1;
+        fs::write(temp.path().join("notes.enc"), changed).unwrap();
+        assert_eq!(decrypt(temp.path(), &["--private-key", "private.pem"]), Some(5), "byte {i}");
+        assert!(!temp.path().join("notes.out").exists(), "byte {i}");
+    }
+    fs::write(temp.path().join("notes.enc"), &original[..start + 100]).unwrap();
+    assert_eq!(decrypt(temp.path(), &["--private-key", "private.pem"]), Some(5));
+}
+
+#[test]
+fn an_encrypted_private_key_needs_its_passphrase() {
+    let temp = scratch_dir();
+    encrypt(temp.path());
+    let pem = keys()[0].to_pkcs8_encrypted_pem(&mut OsRng, "correct horse", LineEnding::LF).unwrap();
+    fs::write(temp.path().join("locked.pem"), pem.as_bytes()).unwrap();
+    fs::write(temp.path().join("passphrase.txt"), "correct horse\n").unwrap();
+    fs::write(temp.path().join("wrong.txt"), "battery staple\n").unwrap();
+
+    let output = run_app(temp.path(), &["--decrypt", "--private-key", "locked.pem", "notes.enc", "notes.out"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--passphrase-file"));
+    let locked = ["--private-key", "locked.pem", "--passphrase-file"];
+    assert_eq!(decrypt(temp.path(), &[&locked[..], &["wrong.txt"]].concat()), Some(5));
+    assert!(!temp.path().join("notes.out").exists());
+    assert_eq!(decrypt(temp.path(), &[&locked[..], &["passphrase.txt"]].concat()), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+
+    // The passphrase is only for a private key.
+    assert_eq!(decrypt(temp.path(), &["--passphrase-file", "passphrase.txt"]), Some(2));
+}
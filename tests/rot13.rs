@@ -0,0 +1,48 @@
+// Known answers for ROT13 on hand-picked edge cases. The properties it has for any text are checked on random
+// strings in rot13_properties.rs.
+
+use rust_project::rot13;
+
+#[test]
+fn empty_string_stays_empty() {
+    assert_eq!(rot13(""), "");
+}
+
+#[test]
+fn lowercase_only() {
+    assert_eq!(rot13("abcdefghijklmnopqrstuvwxyz"), "nopqrstuvwxyzabcdefghijklm");
+}
+
+#[test]
+fn uppercase_only() {
+    assert_eq!(rot13("ABCDEFGHIJKLMNOPQRSTUVWXYZ"), "NOPQRSTUVWXYZABCDEFGHIJKLM");
+}
+
+#[test]
+fn mixed_case_keeps_the_case_of_each_letter() {
+    assert_eq!(rot13("RuSt LaNg"), "EhFg YnAt");
+}
+
+#[test]
+fn hello_world() {
+    assert_eq!(rot13("Hello, World!"), "Uryyb, Jbeyq!");
+}
+
+#[test]
+fn digits_spaces_and_punctuation_are_unchanged() {
+    let text = "0123456789 \t\n!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+    assert_eq!(rot13(text), text);
+}
+
+#[test]
+fn non_ascii_characters_are_unchanged() {
+    assert_eq!(rot13("Héllo"), "Uéyyb");
+    assert_eq!(rot13("日本語 🦀 Ωμέγα"), "日本語 🦀 Ωμέγα");
+}
+
+#[test]
+fn applying_twice_gives_back_the_text() {
+    for text in ["", "Hello, World!", "Héllo", "Why did the chicken cross the road?", "🦀 Ferris 🦀"] {
+        assert_eq!(rot13(&rot13(text)), text);
+    }
+}
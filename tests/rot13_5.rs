@@ -0,0 +1,42 @@
+// Known answers for ROT13 with ROT5, which rotates letters by 13 and digits by 5, and a check that the app can
+// encrypt and decrypt files with it.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use rust_project::rot13_5;
+
+#[test]
+fn letters_and_digits_round_trip() {
+    assert_eq!(rot13_5("abc123"), "nop678");
+    assert_eq!(rot13_5("nop678"), "abc123");
+}
+
+// Digits wrap around within 0 to 9 and letters within A to Z, so neither turns into the other.
+#[test]
+fn digits_and_letters_wrap_separately() {
+    assert_eq!(rot13_5("0123456789"), "5678901234");
+    assert_eq!(rot13_5("xyzXYZ"), "klmKLM");
+    assert_eq!(rot13_5("z9Z9"), "m4M4");
+}
+
+#[test]
+fn everything_else_is_unchanged() {
+    let text = " \t\n!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~ 日本語 🦀";
+    assert_eq!(rot13_5(text), text);
+}
+
+#[test]
+fn files_are_encrypted_and_decrypted_with_the_cipher_option() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Room 101 at 9:45").unwrap();
+    let output = run_app(dir, &["--cipher", "rot13-5", "--no-header", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "Ebbz 656 ng 4:90");
+
+    let output = run_app(dir, &["--decrypt", "--cipher", "rot13-5", "--no-header", "notes.enc", "notes.dec"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Room 101 at 9:45");
+}
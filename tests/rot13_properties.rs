@@ -0,0 +1,40 @@
+// Properties ROT13 must have for any text at all, checked on many random strings (including ones with
+// multi-byte Unicode characters) rather than a few hand-picked examples.
+
+use proptest::prelude::*;
+use rust_project::rot13;
+
+proptest! {
+    // ROT13 is its own inverse, so applying it twice gives back the original text.
+    #[test]
+    fn rot13_twice_gives_back_the_text(text in any::<String>()) {
+        prop_assert_eq!(rot13(&rot13(&text)), text);
+    }
+
+    // Only ASCII letters change, and each one stays a letter of the same case. Everything else, including
+    // multi-byte characters, stays exactly where it was.
+    #[test]
+    fn rot13_only_changes_ascii_letters(text in any::<String>()) {
+        let rotated = rot13(&text);
+        prop_assert_eq!(rotated.chars().count(), text.chars().count());
+        for (before, after) in text.chars().zip(rotated.chars()) {
+            if before.is_ascii_alphabetic() {
+                prop_assert!(after.is_ascii_alphabetic());
+                prop_assert_eq!(before.is_ascii_uppercase(), after.is_ascii_uppercase());
+                prop_assert_ne!(before, after);
+            } else {
+                prop_assert_eq!(before, after);
+            }
+        }
+    }
+
+    // Each letter moves exactly 13 places, wrapping around at the end of the alphabet. This catches a mistake
+    // in where the alphabet is split (A to M going forwards, N to Z going backwards).
+    #[test]
+    fn rot13_moves_letters_13_places(letter in "[a-zA-Z]") {
+        let before = letter.as_bytes()[0];
+        let after = rot13(&letter).as_bytes()[0];
+        let base = if before.is_ascii_uppercase() { b'A' } else { b'a' };
+        prop_assert_eq!(after - base, (before - base + 13) % 26);
+    }
+}
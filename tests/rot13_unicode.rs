@@ -0,0 +1,60 @@
+// Known answers for the Unicode ROT13, which rotates Greek, Cyrillic, and Hebrew letters halfway through their
+// own alphabets as well as Latin ones, and a check that the app encrypts and decrypts files with --unicode.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+use rust_project::{rot13, rot13_unicode};
+
+#[test]
+fn cyrillic_is_rotated_16_places() {
+    assert_eq!(rot13_unicode("АБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯ"), "РСТУФХЦЧШЩЪЫЬЭЮЯАБВГДЕЖЗИЙКЛМНОП");
+    assert_eq!(rot13_unicode("Привет, мир!"), "Яаштхв, ьша!");
+    assert_eq!(rot13_unicode("Яаштхв, ьша!"), "Привет, мир!");
+}
+
+#[test]
+fn greek_is_rotated_12_places() {
+    assert_eq!(rot13_unicode("ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩ"), "ΝΞΟΠΡΣΤΥΦΧΨΩΑΒΓΔΕΖΗΘΙΚΛΜ");
+    assert_eq!(rot13_unicode("Γεια σου"), "Ορφν ζγθ");
+    assert_eq!(rot13_unicode("Ορφν ζγθ"), "Γεια σου");
+}
+
+// Hebrew has 27 letters counting the final forms, so the last one, ת, stays put and the other 26 are rotated 13.
+#[test]
+fn the_last_letter_of_an_odd_sized_alphabet_stays_put() {
+    assert_eq!(rot13_unicode("ת"), "ת");
+    assert_eq!(rot13_unicode("שלום"), "לשעא");
+    assert_eq!(rot13_unicode("לשעא"), "שלום");
+}
+
+#[test]
+fn ascii_comes_out_like_rot13() {
+    let text = "Hello, World! 0123456789";
+    assert_eq!(rot13_unicode(text), rot13(text));
+}
+
+// A combining accent stays where it is, on whichever letter it follows. Letters outside the basic alphabets,
+// like Ё, final sigma, and precomposed accented letters, are left alone.
+#[test]
+fn combining_marks_and_other_letters_pass_through() {
+    assert_eq!(rot13_unicode("е\u{301}"), "х\u{301}");
+    assert_eq!(rot13_unicode("e\u{301}"), "r\u{301}");
+    assert_eq!(rot13_unicode("Ёё ς é 日本語 🦀"), "Ёё ς é 日本語 🦀");
+}
+
+#[test]
+fn files_are_encrypted_and_decrypted_with_the_unicode_option() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Привет, мир! Γεια σου.").unwrap();
+    let output = run_app(dir, &["--unicode", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(fs::read_to_string(dir.join("notes.enc")).unwrap().ends_with("Яаштхв, ьша! Ορφν ζγθ."));
+
+    // The header says which cipher was used, so decrypting doesn't need --unicode again.
+    let output = run_app(dir, &["--decrypt", "notes.enc", "notes.dec"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Привет, мир! Γεια σου.");
+}
@@ -0,0 +1,44 @@
+// Keys derived by the library are held in a SecretKey, which overwrites them with zeros when it's dropped. This
+// keeps a pointer to the bytes of a derived key, drops the key while its memory is still there to look at, and
+// checks nothing of the key is left behind.
+
+use std::mem::MaybeUninit;
+use std::ptr;
+use rust_project::kdf::{derive_key_scrypt, derive_subkeys};
+use rust_project::SecretKey;
+
+// This function drops a key in place and gives back what's left in the memory it was in. The key lives in a
+// MaybeUninit, so the memory stays put (and isn't reused) after the key is dropped.
+fn bytes_left_after_drop(key: SecretKey) -> [u8; 32] {
+    let mut slot = MaybeUninit::new(key);
+    let bytes: *const [u8; 32] = unsafe { slot.assume_init_ref() }.as_bytes();
+    assert_ne!(unsafe { ptr::read_volatile(bytes) }, [0; 32], "the key was all zeros to begin with");
+    unsafe { slot.assume_init_drop() };
+    unsafe { ptr::read_volatile(bytes) }
+}
+
+#[test]
+fn derived_sub_keys_are_zeroed_when_dropped() {
+    let key = derive_subkeys(&[7; 32], b"salt", b"cipher");
+    assert_eq!(bytes_left_after_drop(key), [0; 32]);
+}
+
+#[test]
+fn keys_derived_from_a_password_are_zeroed_when_dropped() {
+    let key = derive_key_scrypt(b"password", b"NaCl", 16, 1, 1).unwrap();
+    assert_eq!(bytes_left_after_drop(key), [0; 32]);
+}
+
+#[test]
+fn copied_keys_are_zeroed_when_dropped() {
+    let key = SecretKey::from_slice(&[0x5a; 32]).unwrap();
+    let copy = key.clone();
+    assert_eq!(bytes_left_after_drop(key), [0; 32]);
+    assert_eq!(copy.as_bytes(), &[0x5a; 32]);
+}
+
+#[test]
+fn keys_are_left_out_when_printed() {
+    let key = SecretKey::from_slice(&[0x5a; 32]).unwrap();
+    assert_eq!(format!("{key:?}"), "SecretKey(..)");
+}
@@ -0,0 +1,74 @@
+// With --shred, each input file is overwritten with random bytes and deleted once its output file is saved.
+// This runs the built app on files in a scratch directory and checks what's left afterwards.
+
+mod common;
+
+use std::fs;
+use common::{run_app, scratch_dir};
+
+#[test]
+fn shredded_input_is_gone_and_output_is_kept() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--shred", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!dir.join("notes.txt").exists());
+    assert!(dir.join("notes.enc").exists());
+
+    let output = run_app(dir, &["--decrypt", "notes.enc", "notes.txt"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn several_passes_shred_every_input_file() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("a.txt"), "a".repeat(100_000)).unwrap();
+    fs::write(dir.join("b.txt"), "").unwrap();
+    let output = run_app(dir, &["--shred-passes", "3", "--output-dir", "out", "a.txt", "b.txt"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!dir.join("a.txt").exists());
+    assert!(!dir.join("b.txt").exists());
+    assert!(dir.join("out/a.txt.enc").exists());
+    assert!(dir.join("out/b.txt.enc").exists());
+}
+
+// The input file is only shredded once its output file is saved, so one that fails is left alone.
+#[test]
+fn input_is_kept_when_the_output_is_not_saved() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    fs::write(dir.join("notes.enc"), "old contents").unwrap();
+    let output = run_app(dir, &["--shred", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+    assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+}
+
+#[test]
+fn dry_run_shreds_nothing() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--dry-run", "--shred", "notes.txt", "notes.enc"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would shred notes.txt"));
+    assert!(dir.join("notes.txt").exists());
+    assert!(!dir.join("notes.enc").exists());
+}
+
+#[test]
+fn shred_needs_a_separate_output_file() {
+    let temp = scratch_dir();
+    let dir = temp.path();
+    fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+    let output = run_app(dir, &["--shred", "--in-place", "notes.txt"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--shred can't be used"));
+    let output = run_app(dir, &["--shred-passes", "0", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "Hello, World!");
+}
@@ -0,0 +1,28 @@
+// A file whose key comes from a password gets a cipher key and a MAC key, both derived from one master key with
+// HKDF and a different info string. This checks the two are never the same, and never the master key itself,
+// whatever the master key and salt are.
+
+use rust_project::kdf::derive_subkeys;
+
+#[test]
+fn cipher_and_mac_sub_keys_differ_for_the_same_master_key() {
+    for byte in [0, 1, 0x5a, 0xff] {
+        let master = [byte; 32];
+        for salt in [&b""[..], b"salt", &[byte; 16]] {
+            let cipher = derive_subkeys(&master, salt, b"cipher");
+            let mac = derive_subkeys(&master, salt, b"mac");
+            assert_ne!(cipher.as_bytes(), mac.as_bytes(), "master {byte:#04x}, salt {salt:?}");
+            assert_ne!(cipher.as_bytes(), &master);
+            assert_ne!(mac.as_bytes(), &master);
+        }
+    }
+}
+
+#[test]
+fn sub_keys_depend_on_the_salt() {
+    let master = [7; 32];
+    let one = derive_subkeys(&master, b"salt one", b"cipher");
+    let two = derive_subkeys(&master, b"salt two", b"cipher");
+    assert_ne!(one.as_bytes(), two.as_bytes());
+    assert_eq!(one.as_bytes(), derive_subkeys(&master, b"salt one", b"cipher").as_bytes());
+}
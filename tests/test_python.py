@@ -0,0 +1,46 @@
+"""Calls the Python extension module the way a Python program would. Build it first with "maturin develop"."""
+
+import unittest
+
+import rfec
+
+
+class RoundTripTest(unittest.TestCase):
+    def test_bytes_come_back_as_they_were(self):
+        encrypted = rfec.encrypt_bytes(b"Hello, World!", "correct horse")
+        self.assertIsInstance(encrypted, bytes)
+        self.assertNotIn(b"Hello, World!", encrypted)
+        self.assertEqual(rfec.decrypt_bytes(encrypted, "correct horse"), b"Hello, World!")
+
+    def test_any_bytes_can_be_encrypted(self):
+        data = bytes(range(256))
+        self.assertEqual(rfec.decrypt_bytes(rfec.encrypt_bytes(data, "correct horse"), "correct horse"), data)
+
+    def test_output_starts_with_the_app_header(self):
+        # A version 2 header, naming chacha20-poly1305 (ID 12).
+        self.assertTrue(rfec.encrypt_bytes(b"Hello, World!", "correct horse").startswith(b"FENC\x02\x0c"))
+
+    def test_wrong_passphrase_raises_value_error(self):
+        encrypted = rfec.encrypt_bytes(b"Hello, World!", "correct horse")
+        with self.assertRaises(ValueError):
+            rfec.decrypt_bytes(encrypted, "wrong horse")
+
+    def test_changed_data_raises_value_error(self):
+        encrypted = bytearray(rfec.encrypt_bytes(b"Hello, World!", "correct horse"))
+        encrypted[-1] ^= 1
+        with self.assertRaises(ValueError):
+            rfec.decrypt_bytes(bytes(encrypted), "correct horse")
+
+    def test_problems_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            rfec.encrypt_bytes(b"Hello, World!", "")
+        # Encrypted with another cipher (ROT13, ID 1).
+        with self.assertRaises(ValueError):
+            rfec.decrypt_bytes(b"FENC\x01\x01Uryyb", "correct horse")
+        # No header at all.
+        with self.assertRaises(ValueError):
+            rfec.decrypt_bytes(b"Hello, World!", "correct horse")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,31 @@
+// Calls the ciphers through the WebAssembly package that "make wasm" builds into ./pkg, the way a browser
+// extension or Electron app would.
+
+const { encrypt_rot13, encode_base32, decode_base32, encrypt_aes_gcm, decrypt_aes_gcm } = require("./pkg");
+
+test("rot13 encrypts text and is its own inverse", () => {
+  expect(encrypt_rot13("Hello, World!")).toBe("Uryyb, Jbeyq!");
+  expect(encrypt_rot13(encrypt_rot13("Hello, World!"))).toBe("Hello, World!");
+});
+
+test("base32 round-trips bytes", () => {
+  const data = new Uint8Array([0, 1, 2, 253, 254, 255]);
+  const encoded = encode_base32(data);
+  expect(new TextDecoder().decode(encoded)).toBe("AAAQF7P674======");
+  expect(decode_base32(encoded)).toEqual(data);
+});
+
+test("invalid base32 throws an Error", () => {
+  expect(() => decode_base32(new TextEncoder().encode("not base32!"))).toThrow(Error);
+});
+
+test("aes-256-gcm round-trips bytes and refuses the wrong key", () => {
+  const key = new Uint8Array(32).fill(7);
+  const data = new TextEncoder().encode("attack at dawn");
+  const sealed = encrypt_aes_gcm(data, key);
+  expect(sealed.length).toBe(12 + data.length + 16);
+  expect(decrypt_aes_gcm(sealed, key)).toEqual(data);
+  expect(encrypt_aes_gcm(data, key)).not.toEqual(sealed);
+  expect(() => decrypt_aes_gcm(sealed, new Uint8Array(32).fill(8))).toThrow(Error);
+  expect(() => decrypt_aes_gcm(sealed.slice(0, 5), key)).toThrow(Error);
+});
@@ -0,0 +1,89 @@
+// Started on a terminal without any arguments, the app asks what to do instead of just showing its help. This
+// runs the built app on a pseudo-terminal, answers its questions, and checks what it saved.
+
+mod common;
+
+use common::{run_app, scratch_dir};
+
+// Without a terminal to ask on, the app shows how to run it and fails straight away, so scripts don't hang.
+#[test]
+fn no_arguments_without_a_terminal_shows_help() {
+    let temp = scratch_dir();
+    let output = run_app(temp.path(), &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage:"));
+}
+
+// "script" takes its arguments differently on other systems, so these only run on Linux.
+#[cfg(target_os = "linux")]
+mod terminal {
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use std::process::{Command, Output, Stdio};
+    use crate::common::{run_app, scratch_dir};
+
+    // This function runs the app without any arguments on a pseudo-terminal made by "script", typing each line
+    // of `typed` at it.
+    fn run_on_terminal(dir: &Path, typed: &str) -> Output {
+        let app = env!("CARGO_BIN_EXE_rust_project");
+        let mut child = Command::new("script")
+            .args(["-qec", &format!("'{app}'"), "/dev/null"])
+            .current_dir(dir)
+            .env("XDG_CONFIG_HOME", dir)
+            .env_remove("RUST_PROJECT_CIPHER")
+            .env_remove("RUST_PROJECT_KEY")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(typed.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    #[test]
+    fn missing_input_is_asked_for_again_and_rot13_is_the_default() {
+        let temp = scratch_dir();
+        let dir = temp.path();
+        fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+        let output = run_on_terminal(dir, "missing.txt\nnotes.txt\nnotes.enc\n\n");
+        let printed = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{printed}");
+        assert!(printed.contains("There's no file called missing.txt"));
+        assert!(printed.contains("1. rot13"));
+        assert!(run_app(dir, &["--decrypt", "notes.enc", "notes.dec"]).status.success());
+        assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Hello, World!");
+    }
+
+    #[test]
+    fn existing_output_is_only_overwritten_when_confirmed() {
+        let temp = scratch_dir();
+        let dir = temp.path();
+        fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+        fs::write(dir.join("notes.enc"), "old contents").unwrap();
+        assert!(run_app(dir, &["--rails", "3", "notes.txt", "typed.enc"]).status.success());
+
+        // Declining asks for somewhere else to save to. The rail fence cipher also asks for its number of rails.
+        let output = run_on_terminal(dir, "notes.txt\nnotes.enc\nn\nrails.enc\n3\n3\n");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        assert_eq!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+        assert_eq!(fs::read(dir.join("rails.enc")).unwrap(), fs::read(dir.join("typed.enc")).unwrap());
+
+        let output = run_on_terminal(dir, "notes.txt\nnotes.enc\ny\n1\n");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        assert_ne!(fs::read_to_string(dir.join("notes.enc")).unwrap(), "old contents");
+    }
+
+    #[test]
+    fn bad_cipher_numbers_are_asked_for_again() {
+        let temp = scratch_dir();
+        let dir = temp.path();
+        fs::write(dir.join("notes.txt"), "Hello, World!").unwrap();
+        let output = run_on_terminal(dir, "notes.txt\nnotes.enc\n0\nseven\n6\n");
+        let printed = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{printed}");
+        assert_eq!(printed.matches("Pick a number from 1 to 10.").count(), 2);
+        assert!(run_app(dir, &["--decrypt", "notes.enc", "notes.dec"]).status.success());
+        assert_eq!(fs::read_to_string(dir.join("notes.dec")).unwrap(), "Hello, World!");
+    }
+}
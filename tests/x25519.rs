@@ -0,0 +1,118 @@
+// "--recipient-key" with an age-style X25519 key (age1...) encrypts a file for the holder of the matching secret
+// key (AGE-SECRET-KEY-1...), using an ephemeral key exchange, HKDF-SHA256, and ChaCha20-Poly1305. This checks
+// the bech32 key encoding against age's own, that files round-trip, and that the wrong key, a changed file, or a
+// key with a bad checksum is refused.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use bech32::{Bech32, Hrp};
+use common::{run_app, scratch_dir};
+use rand::rngs::OsRng;
+use rust_project::FileHeader;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const PLAIN: &[u8] = b"Leave the package with the doorman, not the concierge.\n";
+
+// This function writes an age-style key pair the way age-keygen does, with the secret key's file starting with
+// comments, giving back the public key.
+fn write_key_pair(dir: &Path, name: &str) -> String {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = bech32::encode::<Bech32>(Hrp::parse("age").unwrap(), PublicKey::from(&secret).as_bytes()).unwrap();
+    let encoded = bech32::encode::<Bech32>(Hrp::parse("age-secret-key-").unwrap(), secret.as_bytes()).unwrap();
+    let contents = format!("# created: 2024-01-01T00:00:00Z\n# public key: {public}\n{}\n", encoded.to_uppercase());
+    fs::write(dir.join(format!("{name}.key")), contents).unwrap();
+    fs::write(dir.join(format!("{name}.pub")), format!("{public}\n")).unwrap();
+    public
+}
+
+// This function encrypts notes.txt to notes.enc for the "alice" key pair, giving back the encrypted file.
+fn encrypt(dir: &Path) -> Vec<u8> {
+    write_key_pair(dir, "alice");
+    write_key_pair(dir, "bob");
+    fs::write(dir.join("notes.txt"), PLAIN).unwrap();
+    let output = run_app(dir, &["--force", "--recipient-key", "alice.pub", "notes.txt", "notes.enc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read(dir.join("notes.enc")).unwrap()
+}
+
+// This function decrypts notes.enc to notes.out with a secret key file, giving back the exit code.
+fn decrypt(dir: &Path, key: &str) -> Option<i32> {
+    let _ = fs::remove_file(dir.join("notes.out"));
+    run_app(dir, &["--decrypt", "--private-key", key, "notes.enc", "notes.out"]).status.code()
+}
+
+// The key pair from age's test files: the secret key is 0x42 repeated.
+#[test]
+fn keys_are_encoded_the_way_age_encodes_them() {
+    let secret = "AGE-SECRET-KEY-1GFPYYSJZGFPYYSJZGFPYYSJZGFPYYSJZGFPYYSJZGFPYYSJZGFPQ4EGAEX";
+    let (hrp, bytes) = bech32::decode(secret).unwrap();
+    assert_eq!((hrp.to_lowercase(), bytes.as_slice()), (String::from("age-secret-key-"), &[0x42; 32][..]));
+    let public = PublicKey::from(&StaticSecret::from([0x42; 32]));
+    let encoded = bech32::encode::<Bech32>(Hrp::parse("age").unwrap(), public.as_bytes()).unwrap();
+    assert_eq!(encoded, "age1zvkyg2lqzraa2lnjvqej32nkuu0ues2s82hzrye869xeexvn73equnujwj");
+    assert_eq!(bech32::decode(&encoded).unwrap().1, public.as_bytes());
+}
+
+#[test]
+fn files_round_trip() {
+    let temp = scratch_dir();
+    let encrypted = encrypt(temp.path());
+    let (header, rest) = FileHeader::parse(&encrypted).unwrap().unwrap();
+    assert_eq!(header.cipher_id, 8);
+    // The ephemeral public key, the nonce, and the tag.
+    assert_eq!(rest.len(), 32 + 12 + PLAIN.len() + 16);
+    assert_eq!(decrypt(temp.path(), "alice.key"), Some(0));
+    assert_eq!(fs::read(temp.path().join("notes.out")).unwrap(), PLAIN);
+
+    // Every file gets its own ephemeral key.
+    let output = run_app(temp.path(), &["--recipient-key", "alice.pub", "notes.txt", "again.enc"]);
+    assert!(output.status.success());
+    let again = fs::read(temp.path().join("again.enc")).unwrap();
+    assert_ne!(again[header.len()..header.len() + 32], encrypted[header.len()..header.len() + 32]);
+}
+
+#[test]
+fn the_wrong_key_is_refused() {
+    let temp = scratch_dir();
+    encrypt(temp.path());
+    assert_eq!(decrypt(temp.path(), "bob.key"), Some(5));
+    // A public key isn't a secret key.
+    assert_eq!(decrypt(temp.path(), "alice.pub"), Some(5));
+    assert!(!temp.path().join("notes.out").exists());
+}
+
+#[test]
+fn a_changed_file_is_refused() {
+    let temp = scratch_dir();
+    let original = encrypt(temp.path());
+    let start = FileHeader::parse(&original).unwrap().unwrap().0.len();
+    // A byte of the ephemeral public key, the nonce, the ciphertext, and the tag.
+    for i in [start + 7, start + 32 + 5, start + 32 + 12 + 3, original.len() - 1] {
+        let mut changed = original.clone();
+        changed[i] ^= 1;
+        fs::write(temp.path().join("notes.enc"), changed).unwrap();
+        assert_eq!(decrypt(temp.path(), "alice.key"), Some(5), "byte {i}");
+        assert!(!temp.path().join("notes.out").exists(), "byte {i}");
+    }
+    fs::write(temp.path().join("notes.enc"), &original[..start + 40]).unwrap();
+    assert_eq!(decrypt(temp.path(), "alice.key"), Some(5));
+}
+
+#[test]
+fn a_key_with_a_bad_checksum_is_refused() {
+    let temp = scratch_dir();
+    let public = write_key_pair(temp.path(), "alice");
+    fs::write(temp.path().join("notes.txt"), PLAIN).unwrap();
+    // Changing the last character breaks the checksum.
+    let last = if public.ends_with('q') { 'p' } else { 'q' };
+    fs::write(temp.path().join("broken.pub"), format!("{}{last}\n", &public[..public.len() - 1])).unwrap();
+    let output = run_app(temp.path(), &["--recipient-key", "broken.pub", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bech32"));
+    // A secret key where a public key should be is refused too.
+    let output = run_app(temp.path(), &["--recipient-key", "alice.key", "notes.txt", "notes.enc"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(!temp.path().join("notes.enc").exists());
+}